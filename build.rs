@@ -0,0 +1,17 @@
+#[cfg(feature = "proto")]
+fn compile_protos() {
+    let protos = [
+        "proto/tensorflow/core/framework/graph.proto",
+        "proto/tensorflow/core/framework/tensor.proto",
+        "proto/tensorflow/core/protobuf/config.proto",
+        "proto/tensorflow/core/protobuf/meta_graph.proto",
+    ];
+
+    println!("cargo:rerun-if-changed=proto");
+    prost_build::compile_protos(&protos, &["proto"]).expect("Failed to compile protos");
+}
+
+fn main() {
+    #[cfg(feature = "proto")]
+    compile_protos();
+}
@@ -0,0 +1,8 @@
+use rustflow::Graph;
+
+fn main() {
+    let proto = include_str!("../tests/data/addition.pb");
+    let graph = Graph::from_protobuff(proto).unwrap();
+
+    print!("{}", graph.to_dot(true).unwrap());
+}
@@ -0,0 +1,430 @@
+use crate::{AnyTensor, Error, Graph, Output, Result, Session};
+use std::iter;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use tf;
+
+/// Dynamic batching configuration
+#[derive(Debug, Clone)]
+pub struct BatchConfig {
+    /// Maximal number of examples run in single batch
+    pub max_batch_size: usize,
+    /// Maximal time batch is waiting to be filled, counted since its
+    /// first example arrived
+    pub timeout: Duration,
+    /// If set, examples of different shapes (but the same rank) are padded
+    /// with zeros to the largest example in batch. Otherwise examples of
+    /// different shapes arriving together are run in separate batches.
+    pub padding: bool,
+}
+
+impl Default for BatchConfig {
+    fn default() -> Self {
+        BatchConfig {
+            max_batch_size: 32,
+            timeout: Duration::from_millis(5),
+            padding: false,
+        }
+    }
+}
+
+/// Snapshot of batcher statistics
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BatchMetrics {
+    /// Number of batches run so far
+    pub batches: u64,
+    /// Number of examples run so far, in all batches
+    pub examples: u64,
+    /// Configured maximal batch size
+    pub max_batch_size: usize,
+}
+
+impl BatchMetrics {
+    /// Returns average batch fill ratio, as fraction of `max_batch_size`
+    pub fn fill_ratio(&self) -> f64 {
+        if self.batches == 0 {
+            return 0.0;
+        }
+
+        self.examples as f64 / (self.batches as f64 * self.max_batch_size as f64)
+    }
+}
+
+#[derive(Default)]
+struct Counters {
+    batches: AtomicU64,
+    examples: AtomicU64,
+}
+
+struct Request {
+    inputs: Vec<AnyTensor>,
+    response: mpsc::Sender<Result<Vec<AnyTensor>>>,
+}
+
+/// Batched inputs and outputs, kept by operation name and output index,
+/// as they are found in session graph on batcher thread
+struct Signature {
+    inputs: Vec<(String, usize)>,
+    outputs: Vec<(String, usize)>,
+}
+
+impl Signature {
+    fn new(inputs: &[Output], outputs: &[Output]) -> Self {
+        let named = |outputs: &[Output]| {
+            outputs
+                .iter()
+                .map(|output| (output.operation().name().to_owned(), output.index()))
+                .collect()
+        };
+        Signature {
+            inputs: named(inputs),
+            outputs: named(outputs),
+        }
+    }
+
+    /// Finds inputs and outputs in given graph
+    fn resolve<'a>(&self, graph: &'a Graph) -> Result<(Vec<Output<'a>>, Vec<Output<'a>>)> {
+        let find = |outputs: &[(String, usize)]| {
+            outputs
+                .iter()
+                .map(|(name, idx)| graph.find_output(name, *idx))
+                .collect::<Result<Vec<_>>>()
+        };
+        Ok((find(&self.inputs)?, find(&self.outputs)?))
+    }
+}
+
+/// Dynamic batching on top of session. Single examples, which may come from
+/// many threads, are queued and concatenated along new first axis into
+/// batched tensors, which are run at once. Fetched outputs are split along
+/// first axis back to callers.
+///
+/// ```rust
+/// # use rustflow::batcher::{BatchConfig, Batcher};
+/// # use rustflow::session::SessionBuilder;
+/// # use rustflow::{Graph, Tensor};
+/// # use std::sync::Arc;
+/// let proto = include_str!("../tests/data/addition.pb");
+/// let graph = Arc::new(Graph::from_protobuff(proto).unwrap());
+/// let session = SessionBuilder::with_shared_graph(graph.clone())
+///     .unwrap()
+///     .build()
+///     .map(Arc::new)
+///     .unwrap();
+///
+/// let x = graph.operation_by_name("x").unwrap().outputs().next().unwrap();
+/// let y = graph.operation_by_name("y").unwrap().outputs().next().unwrap();
+/// let z = graph.operation_by_name("z").unwrap().outputs().next().unwrap();
+/// let batcher = Batcher::new(session, &[x, y], &[z], BatchConfig::default()).unwrap();
+///
+/// let x_val = Tensor::from_slice(&[2], &[1, 2]).unwrap();
+/// let y_val = Tensor::from_slice(&[2], &[3, 4]).unwrap();
+/// let result = batcher.run(vec![x_val.into(), y_val.into()]).unwrap();
+/// let z_val = result.into_iter().next().unwrap().downcast::<i32>().unwrap();
+/// assert_eq!(&[2], z_val.shape());
+/// assert_eq!(&[4, 6], &z_val[..]);
+/// ```
+pub struct Batcher {
+    // `mpsc::Sender` is not `Sync`, and batcher is meant to be shared
+    requests: Mutex<Option<mpsc::Sender<Request>>>,
+    dtypes: Vec<Option<tf::TF_DataType>>,
+    counters: Arc<Counters>,
+    max_batch_size: usize,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl Batcher {
+    /// Creates batcher feeding `inputs` and fetching `outputs` of session
+    /// graph. All outputs of graph have to be batched along first axis.
+    /// Inputs and outputs are found by name in session graph, and if some
+    /// of them is missing, `Error::OperationNotFound` is returned.
+    pub fn new(
+        session: Arc<Session<'static>>,
+        inputs: &[Output],
+        outputs: &[Output],
+        config: BatchConfig,
+    ) -> Result<Self> {
+        let signature = Signature::new(inputs, outputs);
+        let (inputs, _) = signature.resolve(session.graph())?;
+        let dtypes = inputs.iter().map(Output::dtype).collect();
+
+        let (sender, receiver) = mpsc::channel();
+        let counters = Arc::new(Counters::default());
+        let max_batch_size = config.max_batch_size.max(1);
+
+        let worker = BatchWorker {
+            session,
+            signature,
+            config: BatchConfig {
+                max_batch_size,
+                ..config
+            },
+            counters: counters.clone(),
+        };
+        let worker = thread::spawn(move || worker.work(receiver));
+
+        Ok(Batcher {
+            requests: Mutex::new(Some(sender)),
+            dtypes,
+            counters,
+            max_batch_size,
+            worker: Some(worker),
+        })
+    }
+
+    /// Runs single example, blocking until batch it is part of is run.
+    /// `inputs` are single example values for batcher inputs, in the same
+    /// order, without batch axis. Returned outputs are also without batch
+    /// axis. If padding is enabled, returned outputs are padded to the
+    /// largest example in batch.
+    pub fn run(&self, inputs: Vec<AnyTensor>) -> Result<Vec<AnyTensor>> {
+        if inputs.len() != self.dtypes.len() {
+            return Err(Error::InvalidBatchInput(format!(
+                "expected {} inputs, got {}",
+                self.dtypes.len(),
+                inputs.len()
+            )));
+        }
+
+        for (idx, (input, dtype)) in inputs.iter().zip(&self.dtypes).enumerate() {
+            if Some(input.dtype()) != *dtype {
+                return Err(Error::InvalidBatchInput(format!(
+                    "input {} expected to be {:?}, got {:?}",
+                    idx,
+                    dtype,
+                    input.dtype()
+                )));
+            }
+        }
+
+        let (response, result) = mpsc::channel();
+        self.requests
+            .lock()
+            .unwrap()
+            .as_ref()
+            .ok_or(Error::BatcherShutdown)?
+            .send(Request { inputs, response })
+            .map_err(|_| Error::BatcherShutdown)?;
+
+        result.recv().map_err(|_| Error::BatcherShutdown)?
+    }
+
+    /// Returns batching statistics
+    pub fn metrics(&self) -> BatchMetrics {
+        BatchMetrics {
+            batches: self.counters.batches.load(Ordering::Relaxed),
+            examples: self.counters.examples.load(Ordering::Relaxed),
+            max_batch_size: self.max_batch_size,
+        }
+    }
+}
+
+impl Drop for Batcher {
+    fn drop(&mut self) {
+        // Closing requests channel stops worker, after all queued examples
+        // are run
+        self.requests.lock().unwrap().take();
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+struct BatchWorker {
+    session: Arc<Session<'static>>,
+    signature: Signature,
+    config: BatchConfig,
+    counters: Arc<Counters>,
+}
+
+impl BatchWorker {
+    fn work(&self, requests: mpsc::Receiver<Request>) {
+        while let Ok(first) = requests.recv() {
+            let mut batch = vec![first];
+            let deadline = Instant::now() + self.config.timeout;
+
+            while batch.len() < self.config.max_batch_size {
+                let now = Instant::now();
+                if now >= deadline {
+                    break;
+                }
+
+                match requests.recv_timeout(deadline - now) {
+                    Ok(request) => batch.push(request),
+                    Err(_) => break,
+                }
+            }
+
+            self.process(batch);
+        }
+    }
+
+    /// Runs examples collected together. Examples which can't be
+    /// concatenated (of different shapes, or ranks if padding is enabled)
+    /// are grouped by their shapes, and every group is run as separate
+    /// batch, in order of arrival of its first example.
+    fn process(&self, batch: Vec<Request>) {
+        let mut groups: Vec<Vec<Request>> = vec![];
+        for request in batch {
+            match groups
+                .iter()
+                .position(|group| self.fits(&request, &group[0]))
+            {
+                Some(idx) => groups[idx].push(request),
+                None => groups.push(vec![request]),
+            }
+        }
+
+        for batch in groups {
+            match self.run_batch(&batch) {
+                Ok(results) => {
+                    for (request, outputs) in batch.into_iter().zip(results) {
+                        let _ = request.response.send(Ok(outputs));
+                    }
+                }
+                Err(err) => {
+                    for request in batch {
+                        let _ = request.response.send(Err(err.clone()));
+                    }
+                }
+            }
+        }
+    }
+
+    fn fits(&self, request: &Request, reference: &Request) -> bool {
+        request
+            .inputs
+            .iter()
+            .zip(&reference.inputs)
+            .all(|(input, other)| {
+                if self.config.padding {
+                    input.shape().len() == other.shape().len()
+                } else {
+                    input.shape() == other.shape()
+                }
+            })
+    }
+
+    fn run_batch(&self, batch: &[Request]) -> Result<Vec<Vec<AnyTensor>>> {
+        let (signature_inputs, signature_outputs) = self.signature.resolve(self.session.graph())?;
+        let inputs = (0..signature_inputs.len())
+            .map(|idx| {
+                let examples: Vec<_> = batch.iter().map(|request| &request.inputs[idx]).collect();
+                concat(&examples)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let input_values: Vec<_> = inputs.iter().map(|input| input.tensor).collect();
+        let signature_inputs: Vec<_> = signature_inputs.iter().map(|input| input.output).collect();
+        let signature_outputs: Vec<_> = signature_outputs
+            .iter()
+            .map(|output| output.output)
+            .collect();
+
+        // Handles come from session graph, and input values are owned
+        // tensors
+        let outputs = unsafe {
+            self.session.run_raw(
+                &signature_inputs,
+                &input_values,
+                &signature_outputs,
+                &[],
+                "Batcher::run",
+            )
+        }?;
+
+        self.counters.batches.fetch_add(1, Ordering::Relaxed);
+        self.counters
+            .examples
+            .fetch_add(batch.len() as u64, Ordering::Relaxed);
+
+        let mut results: Vec<_> = batch.iter().map(|_| vec![]).collect();
+        for output in &outputs {
+            for (result, example) in results.iter_mut().zip(split(output, batch.len())?) {
+                result.push(example);
+            }
+        }
+
+        Ok(results)
+    }
+}
+
+/// Concatenates examples along new first axis, padding them with zeros to
+/// the largest one. All examples have to be of the same type and rank.
+fn concat(examples: &[&AnyTensor]) -> Result<AnyTensor> {
+    let dtype = examples[0].dtype();
+    let elem_size = unsafe { tf::TF_DataTypeSize(dtype) };
+    let rank = examples[0].shape().len();
+    let example_shape: Vec<i64> = (0..rank)
+        .map(|dim| {
+            examples
+                .iter()
+                .map(|example| example.shape()[dim])
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    let example_len = example_shape.iter().product::<i64>() as usize * elem_size;
+    let mut data = vec![0u8; example_len * examples.len()];
+    if example_len > 0 {
+        for (example, dst) in examples.iter().zip(data.chunks_mut(example_len)) {
+            pad_copy(
+                example.as_bytes(),
+                example.shape(),
+                dst,
+                &example_shape,
+                elem_size,
+            );
+        }
+    }
+
+    let shape: Vec<i64> = iter::once(examples.len() as i64)
+        .chain(example_shape)
+        .collect();
+    AnyTensor::from_bytes(dtype, &shape, &data)
+}
+
+/// Copies `src` data into larger `dst` of the same rank
+fn pad_copy(src: &[u8], src_shape: &[i64], dst: &mut [u8], dst_shape: &[i64], elem_size: usize) {
+    if src_shape == dst_shape {
+        dst[..src.len()].copy_from_slice(src);
+        return;
+    }
+
+    let src_stride = src_shape[1..].iter().product::<i64>() as usize * elem_size;
+    let dst_stride = dst_shape[1..].iter().product::<i64>() as usize * elem_size;
+    for idx in 0..src_shape[0] as usize {
+        pad_copy(
+            &src[idx * src_stride..(idx + 1) * src_stride],
+            &src_shape[1..],
+            &mut dst[idx * dst_stride..(idx + 1) * dst_stride],
+            &dst_shape[1..],
+            elem_size,
+        );
+    }
+}
+
+/// Splits batched tensor along its first axis
+fn split(tensor: &AnyTensor, size: usize) -> Result<Vec<AnyTensor>> {
+    let shape = tensor.shape();
+    if shape.first() != Some(&(size as i64)) {
+        return Err(Error::InvalidBatchInput(format!(
+            "output of shape {:?} is not batched along first axis",
+            shape
+        )));
+    }
+
+    let data = tensor.as_bytes();
+    let example_len = data.len() / size;
+    (0..size)
+        .map(|idx| {
+            AnyTensor::from_bytes(
+                tensor.dtype(),
+                &shape[1..],
+                &data[idx * example_len..(idx + 1) * example_len],
+            )
+        })
+        .collect()
+}
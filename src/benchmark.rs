@@ -0,0 +1,115 @@
+//! Measuring latency and throughput of repeated runs, like session runs,
+//! performed concurrently by several threads
+//!
+//! ```rust
+//! # use rustflow::benchmark::measure;
+//! let report = measure(2, 5, || Ok(())).unwrap();
+//! assert_eq!(5, report.iterations);
+//! assert!(report.p50 <= report.p99);
+//! ```
+
+use crate::Result;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Latency statistics of runs measured with given number of threads
+#[derive(Debug, Clone, PartialEq)]
+pub struct Report {
+    /// Number of threads performing runs
+    pub threads: usize,
+    /// Total number of runs
+    pub iterations: usize,
+    /// Median latency
+    pub p50: Duration,
+    /// 90th percentile of latency
+    pub p90: Duration,
+    /// 99th percentile of latency
+    pub p99: Duration,
+    /// Runs finished per second
+    pub throughput: f64,
+}
+
+/// Returns latency below which `percent` of runs finished, using
+/// nearest-rank method on sorted latencies. Panics if `latencies` is
+/// empty.
+///
+/// ```rust
+/// # use rustflow::benchmark::percentile;
+/// # use std::time::Duration;
+/// let latencies: Vec<_> = (1..=10).map(Duration::from_millis).collect();
+/// assert_eq!(Duration::from_millis(5), percentile(&latencies, 50));
+/// assert_eq!(Duration::from_millis(10), percentile(&latencies, 99));
+/// ```
+pub fn percentile(latencies: &[Duration], percent: usize) -> Duration {
+    let rank = (latencies.len() as f64 * percent as f64 / 100.0).ceil() as usize;
+    latencies[rank.clamp(1, latencies.len()) - 1]
+}
+
+/// Splits `iterations` runs among `threads` threads, giving remaining runs
+/// to first threads
+///
+/// ```rust
+/// # use rustflow::benchmark::split_iterations;
+/// assert_eq!(vec![4, 3, 3], split_iterations(10, 3));
+/// assert_eq!(vec![1, 1, 0], split_iterations(2, 3));
+/// ```
+pub fn split_iterations(iterations: usize, threads: usize) -> Vec<usize> {
+    (0..threads)
+        .map(|thread| iterations / threads + (thread < iterations % threads) as usize)
+        .collect()
+}
+
+/// Calls `run` `iterations` times split among `threads` threads, measuring
+/// latency of every call. First error returned by `run` stops thread which
+/// got it, and is returned once all threads finish. Panics if `threads` or
+/// `iterations` is zero.
+pub fn measure<F>(threads: usize, iterations: usize, run: F) -> Result<Report>
+where
+    F: Fn() -> Result<()> + Sync,
+{
+    assert!(threads > 0, "number of threads has to be positive");
+    assert!(iterations > 0, "number of iterations has to be positive");
+
+    let start = Instant::now();
+    let results: Vec<Result<Vec<Duration>>> = thread::scope(|scope| {
+        let workers: Vec<_> = split_iterations(iterations, threads)
+            .into_iter()
+            .map(|count| {
+                let run = &run;
+                scope.spawn(move || {
+                    let mut latencies = Vec::with_capacity(count);
+                    for _ in 0..count {
+                        let start = Instant::now();
+                        run()?;
+                        latencies.push(start.elapsed());
+                    }
+                    Ok(latencies)
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .map(|worker| {
+                worker
+                    .join()
+                    .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+            })
+            .collect()
+    });
+    let elapsed = start.elapsed();
+
+    let mut latencies = vec![];
+    for result in results {
+        latencies.extend(result?);
+    }
+    latencies.sort();
+
+    Ok(Report {
+        threads,
+        iterations,
+        p50: percentile(&latencies, 50),
+        p90: percentile(&latencies, 90),
+        p99: percentile(&latencies, 99),
+        throughput: iterations as f64 / elapsed.as_secs_f64(),
+    })
+}
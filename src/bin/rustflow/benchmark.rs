@@ -0,0 +1,180 @@
+//! Measuring session run latency and throughput
+
+use super::{find_output, graph_inputs, graph_outputs, tensor_name};
+use failure::{bail, format_err, Fallible};
+use rustflow::benchmark::measure;
+use rustflow::graph::OwnedGraph;
+use rustflow::session::SessionBuilder;
+use rustflow::tensor_type::data_type_name;
+use rustflow::{AnyTensor, Output, Tensor, TensorType};
+use serde_json::json;
+use std::time::Duration;
+
+/// Benchmark parameters
+pub struct Options {
+    /// Tensors to fetch, graph outputs if empty
+    pub fetches: Vec<String>,
+    /// Size of unknown dimensions of fed tensors
+    pub batch_size: i64,
+    /// Number of untimed runs before measurement
+    pub warmup: usize,
+    /// Number of timed runs, for every thread count
+    pub iterations: usize,
+    /// Numbers of threads running session concurrently
+    pub threads: Vec<usize>,
+    /// Threads used by tensorflow for single operation
+    pub intra_op_threads: usize,
+    /// Threads used by tensorflow for independent operations
+    pub inter_op_threads: usize,
+    /// Prints results as JSON
+    pub json: bool,
+}
+
+/// Xorshift generator - feeds only need to be arbitrary, not statistically
+/// sound
+struct Random(u64);
+
+impl Random {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// Returns value in `[0, 1)` range
+    fn unit(&mut self) -> f64 {
+        (self.next() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    fn values<T, F>(&mut self, shape: &[i64], mut value: F) -> Fallible<AnyTensor>
+    where
+        T: TensorType + Copy + 'static,
+        F: FnMut(&mut Self) -> T,
+    {
+        let len = shape.iter().product::<i64>() as usize;
+        let data: Vec<_> = (0..len).map(|_| value(self)).collect();
+        Ok(Tensor::from_slice(shape, &data)?.into())
+    }
+}
+
+/// Creates random tensor fitting placeholder type and shape
+fn random_feed(
+    graph: &OwnedGraph,
+    input: Output,
+    batch_size: i64,
+    random: &mut Random,
+) -> Fallible<AnyTensor> {
+    let name = tensor_name(input);
+    let shape = graph
+        .tensor_shape(input)?
+        .ok_or_else(|| format_err!("{} has unknown rank", name))?;
+    let shape: Vec<_> = shape
+        .into_iter()
+        .map(|dim| if dim < 0 { batch_size } else { dim })
+        .collect();
+
+    match input.dtype() {
+        Some(tf::TF_FLOAT) => random.values(&shape, |r| r.unit() as f32),
+        Some(tf::TF_DOUBLE) => random.values(&shape, Random::unit),
+        Some(tf::TF_INT32) => random.values(&shape, |r| (r.next() % 10) as i32),
+        Some(tf::TF_INT64) => random.values(&shape, |r| (r.next() % 10) as i64),
+        Some(tf::TF_UINT8) => random.values(&shape, |r| r.next() as u8),
+        Some(tf::TF_BOOL) => {
+            let len = shape.iter().product::<i64>() as usize;
+            let data: Vec<_> = (0..len).map(|_| (random.next() & 1) as u8).collect();
+            Ok(AnyTensor::from_bytes(tf::TF_BOOL, &shape, &data)?)
+        }
+        Some(dtype) => bail!(
+            "cannot generate {} values for {}",
+            data_type_name(dtype),
+            name
+        ),
+        None => bail!("{} has unknown type", name),
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+/// Benchmarks graph with random feeds for all placeholders, printing
+/// latency percentiles and throughput for every thread count
+pub fn benchmark(graph: OwnedGraph, options: &Options) -> Fallible<()> {
+    if options.iterations == 0 {
+        bail!("number of iterations has to be positive");
+    }
+    if options.threads.contains(&0) {
+        bail!("number of threads has to be positive");
+    }
+
+    let mut random = Random(0x2545_f491_4f6c_dd1d);
+    let feeds = graph_inputs(&graph)
+        .into_iter()
+        .map(|input| {
+            let tensor = random_feed(&graph, input, options.batch_size, &mut random)?;
+            Ok((tensor_name(input), tensor))
+        })
+        .collect::<Fallible<Vec<_>>>()?;
+    let fetches = if options.fetches.is_empty() {
+        graph_outputs(&graph).into_iter().map(tensor_name).collect()
+    } else {
+        options.fetches.clone()
+    };
+
+    let session = SessionBuilder::with_graph(&graph)?
+        .set_parallelism(options.intra_op_threads, options.inter_op_threads)?
+        .build()?;
+    let feeds = feeds
+        .iter()
+        .map(|(name, tensor)| Ok((find_output(&graph, name)?, tensor)))
+        .collect::<Fallible<Vec<_>>>()?;
+    let fetches = fetches
+        .iter()
+        .map(|name| find_output(&graph, name))
+        .collect::<Fallible<Vec<_>>>()?;
+    let run = || session.run(&feeds, &fetches, &[]).map(|_| ());
+
+    for _ in 0..options.warmup {
+        run()?;
+    }
+    let reports = options
+        .threads
+        .iter()
+        .map(|threads| measure(*threads, options.iterations, run))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if options.json {
+        let reports: Vec<_> = reports
+            .iter()
+            .map(|report| {
+                json!({
+                    "threads": report.threads,
+                    "iterations": report.iterations,
+                    "p50_ms": millis(report.p50),
+                    "p90_ms": millis(report.p90),
+                    "p99_ms": millis(report.p99),
+                    "throughput": report.throughput,
+                })
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&reports)?);
+    } else {
+        println!(
+            "{:>8} {:>10} {:>10} {:>10} {:>10} {:>12}",
+            "threads", "runs", "p50 ms", "p90 ms", "p99 ms", "runs/s"
+        );
+        for report in reports {
+            println!(
+                "{:>8} {:>10} {:>10.3} {:>10.3} {:>10.3} {:>12.1}",
+                report.threads,
+                report.iterations,
+                millis(report.p50),
+                millis(report.p90),
+                millis(report.p99),
+                report.throughput
+            );
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,101 @@
+//! Printing graph structure and registered operations
+
+use super::{describe_output, graph_inputs, graph_outputs, tensor_name};
+use failure::Fallible;
+use rustflow::op_def::{ops_registry, ArgDef};
+use rustflow::tensor_type::data_type_name;
+use rustflow::Graph;
+
+/// Prints graph inputs (placeholders), outputs (tensors not consumed by
+/// any operation) and all operations
+pub fn inspect(graph: &Graph) -> Fallible<()> {
+    println!("Inputs:");
+    for input in graph_inputs(graph) {
+        println!(
+            "  {} {}",
+            tensor_name(input),
+            describe_output(graph, input)?
+        );
+    }
+
+    println!("Outputs:");
+    for output in graph_outputs(graph) {
+        println!(
+            "  {} {}",
+            tensor_name(output),
+            describe_output(graph, output)?
+        );
+    }
+
+    println!("Operations:");
+    for op in graph.operations() {
+        let mut inputs: Vec<_> = op
+            .inputs()
+            .map(|input| tensor_name(input.producer()))
+            .collect();
+        inputs.extend(
+            op.control_inputs()
+                .iter()
+                .map(|op| format!("^{}", op.name())),
+        );
+        let outputs = op
+            .outputs()
+            .map(|output| describe_output(graph, output))
+            .collect::<Fallible<Vec<_>>>()?;
+
+        print!("  {} = {}({})", op.name(), op.op_type(), inputs.join(", "));
+        if !outputs.is_empty() {
+            print!(" -> {}", outputs.join(", "));
+        }
+        if !op.device().is_empty() {
+            print!(" @ {}", op.device());
+        }
+        println!();
+    }
+    Ok(())
+}
+
+/// Describes operation argument, like `x: T` or `values: N * T`
+fn describe_arg(arg: &ArgDef) -> String {
+    let dtype = match arg.dtype {
+        Some(dtype) => data_type_name(dtype).to_owned(),
+        None if !arg.type_list_attr.is_empty() => arg.type_list_attr.clone(),
+        None => arg.type_attr.clone(),
+    };
+    let dtype = if arg.number_attr.is_empty() {
+        dtype
+    } else {
+        format!("{} * {}", arg.number_attr, dtype)
+    };
+    let dtype = if arg.is_ref {
+        format!("Ref({})", dtype)
+    } else {
+        dtype
+    };
+    format!("{}: {}", arg.name, dtype)
+}
+
+/// Prints signatures of registered operations, sorted by name
+pub fn ops(filter: Option<&str>, verbose: bool) -> Fallible<()> {
+    let registry = ops_registry()?;
+    let mut ops: Vec<_> = registry
+        .ops()
+        .filter(|op| op.name.contains(filter.unwrap_or("")))
+        .collect();
+    ops.sort_by(|a, b| a.name.cmp(&b.name));
+
+    for op in ops {
+        let inputs: Vec<_> = op.input_args.iter().map(describe_arg).collect();
+        let outputs: Vec<_> = op.output_args.iter().map(describe_arg).collect();
+        println!(
+            "{}({}) -> ({})",
+            op.name,
+            inputs.join(", "),
+            outputs.join(", ")
+        );
+        if verbose && !op.summary.is_empty() {
+            println!("    {}", op.summary);
+        }
+    }
+    Ok(())
+}
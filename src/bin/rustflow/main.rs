@@ -0,0 +1,218 @@
+//! Command line tool for inspecting and running tensorflow graphs
+
+mod benchmark;
+mod inspect;
+mod run;
+
+use failure::{format_err, Fallible};
+use rustflow::graph::OwnedGraph;
+use rustflow::tensor_type::data_type_name;
+use rustflow::{Error, Graph, Output};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+use structopt::StructOpt;
+
+#[derive(StructOpt)]
+#[structopt(name = "rustflow", about = "Inspects and runs tensorflow graphs")]
+enum Command {
+    /// Lists graph inputs, outputs and operations with their types and
+    /// shapes
+    Inspect {
+        /// Serialized `GraphDef` file
+        #[structopt(parse(from_os_str))]
+        graph: PathBuf,
+    },
+    /// Prints graph as Graphviz DOT document
+    Dot {
+        /// Serialized `GraphDef` file
+        #[structopt(parse(from_os_str))]
+        graph: PathBuf,
+        /// Groups operations in clusters by name scopes
+        #[structopt(long)]
+        cluster: bool,
+    },
+    /// Runs graph, printing fetched tensors as JSON
+    Run {
+        /// Serialized `GraphDef` file
+        #[structopt(parse(from_os_str))]
+        graph: PathBuf,
+        /// Value fed to tensor, as `name=value`, where value is `.npy` or
+        /// `.json` file, or JSON literal
+        #[structopt(long = "feed", number_of_values = 1, parse(try_from_str = run::Feed::parse))]
+        feeds: Vec<run::Feed>,
+        /// Tensor to fetch
+        #[structopt(long = "fetch", number_of_values = 1)]
+        fetches: Vec<String>,
+        /// Operation to run without fetching its outputs
+        #[structopt(long = "target", number_of_values = 1)]
+        targets: Vec<String>,
+    },
+    /// Measures latency and throughput of running graph with random feeds
+    /// for all placeholders
+    Benchmark {
+        /// Serialized `GraphDef` file
+        #[structopt(parse(from_os_str))]
+        graph: PathBuf,
+        /// Tensor to fetch, by default all graph outputs are fetched
+        #[structopt(long = "fetch", number_of_values = 1)]
+        fetches: Vec<String>,
+        /// Size of unknown dimensions of fed tensors
+        #[structopt(long, default_value = "1")]
+        batch_size: i64,
+        /// Number of runs before measurement
+        #[structopt(long, default_value = "10")]
+        warmup: usize,
+        /// Number of measured runs, for every thread count
+        #[structopt(long, default_value = "100")]
+        iterations: usize,
+        /// Numbers of threads running graph concurrently
+        #[structopt(long, default_value = "1", use_delimiter = true)]
+        threads: Vec<usize>,
+        /// Threads used by tensorflow for single operation, 0 for default
+        #[structopt(long, default_value = "0")]
+        intra_op_threads: usize,
+        /// Threads used by tensorflow for independent operations, 0 for
+        /// default
+        #[structopt(long, default_value = "0")]
+        inter_op_threads: usize,
+        /// Prints results as JSON
+        #[structopt(long)]
+        json: bool,
+    },
+    /// Lists operations registered in tensorflow
+    Ops {
+        /// Lists only operations which names contain this text
+        filter: Option<String>,
+        /// Prints operation summaries
+        #[structopt(short, long)]
+        verbose: bool,
+    },
+    /// Prints tensorflow version
+    Version,
+}
+
+/// Loads graph from serialized `GraphDef` file
+fn load_graph(path: &Path) -> Fallible<OwnedGraph> {
+    let data =
+        fs::read(path).map_err(|err| format_err!("cannot read {}: {}", path.display(), err))?;
+    Ok(Graph::from_protobuff_bytes(&data)?)
+}
+
+/// Finds tensor given as `operation` or `operation:index`
+fn find_output<'a>(graph: &'a Graph, name: &str) -> Fallible<Output<'a>> {
+    let (op_name, index) = match name.rfind(':') {
+        Some(pos) => match name[pos + 1..].parse() {
+            Ok(index) => (&name[..pos], index),
+            Err(_) => (name, 0),
+        },
+        None => (name, 0),
+    };
+
+    let op = graph
+        .operation_by_name(op_name)
+        .ok_or_else(|| Error::OperationNotFound(op_name.to_owned()))?;
+    op.output(index)
+        .ok_or_else(|| format_err!("operation {} has no output {}", op_name, index))
+}
+
+/// Returns name of tensor, in `operation:index` format
+fn tensor_name(output: Output) -> String {
+    format!("{}:{}", output.operation().name(), output.index())
+}
+
+/// Returns graph inputs - outputs of placeholders
+fn graph_inputs(graph: &Graph) -> Vec<Output> {
+    graph
+        .operations()
+        .filter(|op| op.op_type() == "Placeholder")
+        .flat_map(|op| op.outputs())
+        .collect()
+}
+
+/// Returns graph outputs - tensors not consumed by any operation
+fn graph_outputs(graph: &Graph) -> Vec<Output> {
+    let mut consumed = HashSet::new();
+    for op in graph.operations() {
+        for input in op.inputs() {
+            consumed.insert(tensor_name(input.producer()));
+        }
+    }
+
+    graph
+        .operations()
+        .flat_map(|op| op.outputs())
+        .filter(|output| !consumed.contains(&tensor_name(*output)))
+        .collect()
+}
+
+/// Describes tensors produced by output, like `float [?, 3]`
+fn describe_output(graph: &Graph, output: Output) -> Fallible<String> {
+    let dtype = output.dtype().map_or("?", data_type_name);
+    let shape = match graph.tensor_shape(output)? {
+        Some(shape) => {
+            let dims: Vec<_> = shape
+                .iter()
+                .map(|dim| match dim {
+                    -1 => "?".to_owned(),
+                    dim => dim.to_string(),
+                })
+                .collect();
+            format!("[{}]", dims.join(", "))
+        }
+        None => "?".to_owned(),
+    };
+    Ok(format!("{} {}", dtype, shape))
+}
+
+fn execute(command: Command) -> Fallible<()> {
+    match command {
+        Command::Inspect { graph } => inspect::inspect(&*load_graph(&graph)?),
+        Command::Dot { graph, cluster } => {
+            print!("{}", load_graph(&graph)?.to_dot(cluster)?);
+            Ok(())
+        }
+        Command::Run {
+            graph,
+            feeds,
+            fetches,
+            targets,
+        } => run::run(&*load_graph(&graph)?, &feeds, &fetches, &targets),
+        Command::Benchmark {
+            graph,
+            fetches,
+            batch_size,
+            warmup,
+            iterations,
+            threads,
+            intra_op_threads,
+            inter_op_threads,
+            json,
+        } => {
+            let options = benchmark::Options {
+                fetches,
+                batch_size,
+                warmup,
+                iterations,
+                threads,
+                intra_op_threads,
+                inter_op_threads,
+                json,
+            };
+            benchmark::benchmark(load_graph(&graph)?, &options)
+        }
+        Command::Ops { filter, verbose } => inspect::ops(filter.as_deref(), verbose),
+        Command::Version => {
+            println!("{}", rustflow::tf_version());
+            Ok(())
+        }
+    }
+}
+
+fn main() {
+    if let Err(err) = execute(Command::from_args()) {
+        eprintln!("error: {}", err);
+        process::exit(1);
+    }
+}
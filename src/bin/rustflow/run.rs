@@ -0,0 +1,73 @@
+//! Running graph with tensors fed from command line
+
+use super::find_output;
+use failure::{format_err, Fallible};
+use rustflow::session::SessionBuilder;
+use rustflow::{AnyTensor, Graph};
+use std::fs::{self, File};
+
+/// Tensor fed to graph, given as `name=value`
+pub struct Feed {
+    name: String,
+    value: String,
+}
+
+impl Feed {
+    pub fn parse(feed: &str) -> Result<Self, String> {
+        let pos = feed
+            .find('=')
+            .ok_or_else(|| format!("feed {:?} is not in name=value format", feed))?;
+        Ok(Feed {
+            name: feed[..pos].to_owned(),
+            value: feed[pos + 1..].to_owned(),
+        })
+    }
+
+    /// Loads fed tensor. JSON values are converted to `dtype`, while `.npy`
+    /// files have their own types.
+    fn load(&self, dtype: Option<tf::TF_DataType>) -> Fallible<AnyTensor> {
+        if self.value.ends_with(".npy") {
+            return Ok(AnyTensor::read_npy(File::open(&self.value)?)?);
+        }
+
+        let json = if self.value.ends_with(".json") {
+            fs::read_to_string(&self.value)?
+        } else {
+            self.value.clone()
+        };
+        let dtype = dtype.ok_or_else(|| format_err!("{} has unknown type", self.name))?;
+        Ok(AnyTensor::from_json(&serde_json::from_str(&json)?, dtype)?)
+    }
+}
+
+/// Runs graph, printing fetched tensors
+pub fn run(graph: &Graph, feeds: &[Feed], fetches: &[String], targets: &[String]) -> Fallible<()> {
+    let mut inputs = vec![];
+    for feed in feeds {
+        let output = find_output(graph, &feed.name)?;
+        inputs.push((output, feed.load(output.dtype())?));
+    }
+    let inputs: Vec<_> = inputs
+        .iter()
+        .map(|(output, tensor)| (*output, tensor))
+        .collect();
+    let outputs = fetches
+        .iter()
+        .map(|fetch| find_output(graph, fetch))
+        .collect::<Fallible<Vec<_>>>()?;
+    let targets = targets
+        .iter()
+        .map(|target| {
+            graph
+                .operation_by_name(target)
+                .ok_or_else(|| format_err!("operation {} not found", target))
+        })
+        .collect::<Fallible<Vec<_>>>()?;
+
+    let session = SessionBuilder::with_graph(graph)?.build()?;
+    let results = session.run(&inputs, &outputs, &targets)?;
+    for (fetch, tensor) in fetches.iter().zip(results) {
+        println!("{}: {}", fetch, tensor.to_json()?);
+    }
+    Ok(())
+}
@@ -68,3 +68,50 @@ impl<'a> Drop for StrBuffer<'a> {
         unsafe { tf::TF_DeleteBuffer(self.buffer) }
     }
 }
+
+/// Buffer which data is allocated and filled by tensorflow, for
+/// example with serialized protobuf
+pub(crate) struct OwnedBuffer(*mut tf::TF_Buffer);
+
+impl OwnedBuffer {
+    pub fn new() -> Self {
+        OwnedBuffer(unsafe { tf::TF_NewBuffer() })
+    }
+
+    /// Creates buffer with copy of given data
+    pub fn from_bytes(data: &[u8]) -> Self {
+        OwnedBuffer(unsafe {
+            tf::TF_NewBufferFromString(data.as_ptr() as *const std::ffi::c_void, data.len())
+        })
+    }
+
+    pub fn get(&mut self) -> *mut tf::TF_Buffer {
+        self.0
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        unsafe { buffer_data(&*self.0) }
+    }
+}
+
+impl Buffer for OwnedBuffer {
+    fn buffer(&self) -> &tf::TF_Buffer {
+        unsafe { &*self.0 }
+    }
+}
+
+impl Drop for OwnedBuffer {
+    fn drop(&mut self) {
+        unsafe { tf::TF_DeleteBuffer(self.0) }
+    }
+}
+
+/// Returns data of buffer filled by tensorflow. Function is unsafe, because
+/// callee has to ensure, that buffer is valid and its data is not modified
+/// as long as returned slice is used.
+pub(crate) unsafe fn buffer_data(buffer: &tf::TF_Buffer) -> &[u8] {
+    if buffer.data.is_null() {
+        return &[];
+    }
+    std::slice::from_raw_parts(buffer.data as *const u8, buffer.length)
+}
@@ -0,0 +1,268 @@
+//! Reading tensorflow checkpoints in V2 bundle format, as written by
+//! `train::Saver` or python `tf.train.Checkpoint`, without building graph.
+//! Checkpoint consists of `<prefix>.index` table describing variables,
+//! and `<prefix>.data-<shard>-of-<shards>` files with their values.
+//!
+//! ```rust
+//! # use rustflow::checkpoint::CheckpointReader;
+//! let prefix = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/checkpoint/model");
+//! let checkpoint = CheckpointReader::open(prefix).unwrap();
+//! assert!(checkpoint.variables().any(|variable| variable.name == "dense/kernel"));
+//!
+//! let kernel = checkpoint.load::<f32>("dense/kernel").unwrap();
+//! assert_eq!(&[2, 3], kernel.shape());
+//! ```
+
+use crate::crc::{crc32c, crc32c_extend, unmask};
+use crate::op_def::decode_shape;
+use crate::sstable::read_table;
+use crate::tensor_type::data_type_from_raw;
+use crate::wire::{read_varint, Reader};
+use crate::{AnyTensor, Error, Result, Tensor, TensorType};
+use std::convert::{TryFrom, TryInto};
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use tf;
+
+fn invalid(message: impl Into<String>) -> Error {
+    Error::InvalidCheckpoint(message.into())
+}
+
+/// Variable stored in checkpoint
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VariableInfo {
+    /// Variable name, as given to saver
+    pub name: String,
+    /// Type of variable values
+    pub dtype: tf::TF_DataType,
+    /// Full variable shape
+    pub shape: Vec<i64>,
+    /// True if variable is stored in slices (partitioned variable), which
+    /// are not supported by `CheckpointReader::load`
+    pub partitioned: bool,
+    shard: usize,
+    offset: u64,
+    size: usize,
+    crc32c: u32,
+}
+
+impl VariableInfo {
+    /// Decodes `BundleEntryProto` of variable with given name
+    fn decode(name: String, data: &[u8]) -> Result<Self> {
+        let mut variable = VariableInfo {
+            name,
+            dtype: tf::TF_FLOAT,
+            shape: vec![],
+            partitioned: false,
+            shard: 0,
+            offset: 0,
+            size: 0,
+            crc32c: 0,
+        };
+        for field in Reader::new(data) {
+            match field? {
+                (1, value) => {
+                    let raw = value.varint()? as i32;
+                    variable.dtype = data_type_from_raw(raw)
+                        .ok_or_else(|| invalid(format!("unknown data type {}", raw)))?;
+                }
+                (2, value) => {
+                    variable.shape = decode_shape(value.bytes()?)?
+                        .ok_or_else(|| invalid("variable shape has unknown rank"))?;
+                }
+                (3, value) => variable.shard = value.varint()? as usize,
+                (4, value) => variable.offset = value.varint()?,
+                (5, value) => variable.size = value.varint()? as usize,
+                (6, value) => variable.crc32c = unmask(value.fixed32()?),
+                (7, _) => variable.partitioned = true,
+                _ => (),
+            }
+        }
+        Ok(variable)
+    }
+}
+
+/// Decodes data of string tensor with `len` elements: lengths of all
+/// strings as varints, masked CRC-32C of lengths, and then strings itself.
+/// Returns strings together with CRC-32C of whole tensor, which tensorflow
+/// computes over lengths as integers rather than varints.
+fn decode_strings(mut data: &[u8], len: usize) -> Result<(Vec<&[u8]>, u32)> {
+    let truncated = || invalid("truncated string tensor");
+
+    let mut lengths = vec![];
+    let mut crc = 0;
+    for _ in 0..len {
+        let length = read_varint(&mut data).map_err(|_| truncated())?;
+        lengths.push(length as usize);
+        // Lengths fitting in 32 bits are checksummed as such, for
+        // compatibility with older checkpoints
+        crc = match u32::try_from(length) {
+            Ok(length) => crc32c_extend(crc, &length.to_le_bytes()),
+            Err(_) => crc32c_extend(crc, &length.to_le_bytes()),
+        };
+    }
+
+    let checksum = data.get(..4).ok_or_else(truncated)?;
+    if unmask(u32::from_le_bytes(checksum.try_into().unwrap())) != crc {
+        return Err(invalid("string lengths checksum mismatch"));
+    }
+    crc = crc32c_extend(crc, checksum);
+    data = &data[4..];
+
+    let mut values = vec![];
+    for length in lengths {
+        if length > data.len() {
+            return Err(truncated());
+        }
+        let (value, rest) = data.split_at(length);
+        crc = crc32c_extend(crc, value);
+        values.push(value);
+        data = rest;
+    }
+    Ok((values, crc))
+}
+
+/// Checkpoint opened for reading. Only index is read when opening,
+/// variables values are read from data files when loaded.
+pub struct CheckpointReader {
+    prefix: PathBuf,
+    shards: usize,
+    // Sorted by name, as stored in index
+    variables: Vec<VariableInfo>,
+}
+
+impl CheckpointReader {
+    /// Opens checkpoint with given path prefix, like `model.ckpt` for
+    /// checkpoint stored in `model.ckpt.index` and
+    /// `model.ckpt.data-00000-of-00001` files
+    pub fn open(prefix: impl AsRef<Path>) -> Result<Self> {
+        let prefix = prefix.as_ref().to_owned();
+        let index = fs::read(Self::path(&prefix, ".index"))?;
+
+        let mut shards = None;
+        let mut variables = vec![];
+        for (key, value) in read_table(&index)? {
+            if key.is_empty() {
+                shards = Some(Self::decode_header(value)?);
+            } else if key[0] == 0 {
+                // Slices of partitioned variables are stored under encoded
+                // keys, starting with zero byte
+                continue;
+            } else {
+                let name = String::from_utf8(key)
+                    .map_err(|_| invalid("variable name is not valid utf-8"))?;
+                variables.push(VariableInfo::decode(name, value)?);
+            }
+        }
+
+        let shards = shards.ok_or_else(|| invalid("index has no header"))?;
+        if let Some(variable) = variables.iter().find(|variable| variable.shard >= shards) {
+            return Err(invalid(format!(
+                "{} is stored in shard {} of {}",
+                variable.name, variable.shard, shards
+            )));
+        }
+
+        Ok(CheckpointReader {
+            prefix,
+            shards,
+            variables,
+        })
+    }
+
+    /// Decodes `BundleHeaderProto`, returning number of data shards
+    fn decode_header(data: &[u8]) -> Result<usize> {
+        let mut shards = 0;
+        for field in Reader::new(data) {
+            match field? {
+                (1, value) => shards = value.varint()? as usize,
+                (2, value) if value.varint()? != 0 => {
+                    return Err(invalid("big endian checkpoints are not supported"))
+                }
+                _ => (),
+            }
+        }
+        Ok(shards)
+    }
+
+    fn path(prefix: &Path, suffix: &str) -> PathBuf {
+        let mut path = prefix.as_os_str().to_owned();
+        path.push(suffix);
+        path.into()
+    }
+
+    /// Returns iterator over all variables stored in checkpoint, ordered by
+    /// their names
+    pub fn variables(&self) -> impl Iterator<Item = &VariableInfo> {
+        self.variables.iter()
+    }
+
+    /// Returns variable with given name
+    ///
+    /// ```rust
+    /// # use rustflow::checkpoint::CheckpointReader;
+    /// # use rustflow::TensorType;
+    /// # let prefix = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/checkpoint/model");
+    /// let checkpoint = CheckpointReader::open(prefix).unwrap();
+    /// let step = checkpoint.variable("global_step").unwrap();
+    /// assert_eq!(i64::TF_TYPE, step.dtype);
+    /// assert!(step.shape.is_empty());
+    /// assert!(checkpoint.variable("missing").is_none());
+    /// ```
+    pub fn variable(&self, name: &str) -> Option<&VariableInfo> {
+        self.variables
+            .binary_search_by(|variable| variable.name.as_str().cmp(name))
+            .ok()
+            .map(|idx| &self.variables[idx])
+    }
+
+    /// Loads value of variable with given name, verifying its checksum.
+    /// If there is no such variable, `Error::VariableNotFound` is returned.
+    pub fn load_any(&self, name: &str) -> Result<AnyTensor> {
+        let variable = self
+            .variable(name)
+            .ok_or_else(|| Error::VariableNotFound(name.to_owned()))?;
+        if variable.partitioned {
+            return Err(invalid(format!(
+                "{} is partitioned, which is not supported",
+                name
+            )));
+        }
+
+        let shard = format!(".data-{:05}-of-{:05}", variable.shard, self.shards);
+        let mut file = File::open(Self::path(&self.prefix, &shard))?;
+        if variable.offset.saturating_add(variable.size as u64) > file.metadata()?.len() {
+            return Err(invalid(format!("data of {} exceeds shard size", name)));
+        }
+        file.seek(SeekFrom::Start(variable.offset))?;
+        let mut data = vec![0; variable.size];
+        file.read_exact(&mut data)?;
+        let checksum_mismatch = || invalid(format!("checksum mismatch of {}", name));
+
+        if variable.dtype == tf::TF_STRING {
+            let len = variable.shape.iter().product::<i64>() as usize;
+            let (values, crc) = decode_strings(&data, len)?;
+            if crc != variable.crc32c {
+                return Err(checksum_mismatch());
+            }
+            AnyTensor::from_strings(&variable.shape, &values)
+        } else {
+            if crc32c(&data) != variable.crc32c {
+                return Err(checksum_mismatch());
+            }
+            AnyTensor::from_bytes(variable.dtype, &variable.shape, &data)
+        }
+    }
+
+    /// Loads value of variable with given name, as `load_any` does. If
+    /// variable is not of type `T`, `Error::TypeMismatch` is returned.
+    pub fn load<T: TensorType>(&self, name: &str) -> Result<Tensor<T>> {
+        self.load_any(name)?
+            .downcast()
+            .map_err(|tensor| Error::TypeMismatch {
+                expected: T::TF_TYPE,
+                found: tensor.dtype(),
+            })
+    }
+}
@@ -0,0 +1,331 @@
+//! Control flow constructs - loops and conditionals - built into graph
+
+use crate::{Error, Function, Graph, Output, Result, Status};
+use std::ffi::CString;
+use std::slice;
+use tf;
+
+/// Loop being built. Dropping it before loop is finished aborts it, freeing
+/// condition and body sub-graphs.
+struct WhileBuilder {
+    params: tf::TF_WhileParams,
+    finished: bool,
+}
+
+impl WhileBuilder {
+    /// Returns handle to one of loop sub-graphs, which is owned by loop
+    /// params
+    fn graph(graph: *const tf::TF_Graph) -> Graph {
+        unsafe { Graph::from_ptr(graph as *mut _) }
+    }
+
+    /// Returns loop variables in one of loop sub-graphs
+    fn inputs<'a>(inputs: *const tf::TF_Output, ninputs: usize) -> Vec<Output<'a>> {
+        unsafe { slice::from_raw_parts(inputs, ninputs) }
+            .iter()
+            .map(|input| unsafe { Output::new(input.oper, input.index) })
+            .collect()
+    }
+}
+
+impl Drop for WhileBuilder {
+    fn drop(&mut self) {
+        if !self.finished {
+            unsafe { tf::TF_AbortWhile(&self.params) }
+        }
+    }
+}
+
+impl Graph {
+    /// Adds while loop to graph. Loop variables are initialized with
+    /// `inputs`, then `body` is computing new values of loop variables as
+    /// long as `cond` evaluates to true. Both `cond` and `body` get sub-graph
+    /// to which their operations have to be added, and loop variables in this
+    /// sub-graph. Returns final values of loop variables.
+    ///
+    /// ```rust
+    /// # use rustflow::{Graph, Tensor, TensorType};
+    /// # let graph = Graph::new();
+    /// # let ten = Tensor::from_slice(&[], &[10]).unwrap();
+    /// # let ten = graph
+    /// #     .new_operation("Const", "ten").unwrap()
+    /// #     .set_attr_tensor("value", ten.as_ref()).unwrap()
+    /// #     .set_attr_type("dtype", i32::TF_TYPE).unwrap()
+    /// #     .finish().unwrap();
+    /// # let ten = ten.output(0).unwrap();
+    /// let outputs = graph
+    ///     .while_loop(
+    ///         &[ten],
+    ///         |graph, vars| {
+    ///             let less = graph
+    ///                 .new_operation("Less", "less")?
+    ///                 .add_input(vars[0])
+    ///                 .add_input(vars[0])
+    ///                 .finish()?;
+    ///             Ok(less.output(0).unwrap())
+    ///         },
+    ///         |_, vars| Ok(vars.to_vec()),
+    ///     )
+    ///     .unwrap();
+    /// assert_eq!(1, outputs.len());
+    /// ```
+    pub fn while_loop<'a, C, B>(
+        &'a self,
+        inputs: &[Output<'a>],
+        cond: C,
+        body: B,
+    ) -> Result<Vec<Output<'a>>>
+    where
+        C: for<'g> FnOnce(&'g Graph, &[Output<'g>]) -> Result<Output<'g>>,
+        B: for<'g> FnOnce(&'g Graph, &[Output<'g>]) -> Result<Vec<Output<'g>>>,
+    {
+        self.while_loop_impl(None, inputs, cond, body)
+    }
+
+    /// Adds while loop the same way as `Graph::while_loop`, but loop
+    /// operations are named with given name. Name has to be unique in graph.
+    pub fn while_loop_with_name<'a, C, B>(
+        &'a self,
+        name: &str,
+        inputs: &[Output<'a>],
+        cond: C,
+        body: B,
+    ) -> Result<Vec<Output<'a>>>
+    where
+        C: for<'g> FnOnce(&'g Graph, &[Output<'g>]) -> Result<Output<'g>>,
+        B: for<'g> FnOnce(&'g Graph, &[Output<'g>]) -> Result<Vec<Output<'g>>>,
+    {
+        self.while_loop_impl(Some(name), inputs, cond, body)
+    }
+
+    fn while_loop_impl<'a, C, B>(
+        &'a self,
+        name: Option<&str>,
+        inputs: &[Output<'a>],
+        cond: C,
+        body: B,
+    ) -> Result<Vec<Output<'a>>>
+    where
+        C: for<'g> FnOnce(&'g Graph, &[Output<'g>]) -> Result<Output<'g>>,
+        B: for<'g> FnOnce(&'g Graph, &[Output<'g>]) -> Result<Vec<Output<'g>>>,
+    {
+        let name = name.map(CString::new).transpose()?;
+        let mut inputs: Vec<_> = inputs.iter().map(|input| input.output).collect();
+        let ninputs = inputs.len();
+
+        let mut status = Status::new();
+        let params = unsafe {
+            tf::TF_NewWhile(
+                self.get_ptr(),
+                inputs.as_mut_ptr(),
+                ninputs as i32,
+                status.get(),
+            )
+        };
+        status.to_result()?;
+        let mut builder = WhileBuilder {
+            params,
+            finished: false,
+        };
+        if let Some(name) = &name {
+            builder.params.name = name.as_ptr();
+        }
+
+        let cond_graph = WhileBuilder::graph(builder.params.cond_graph);
+        let cond_inputs = WhileBuilder::inputs(builder.params.cond_inputs, ninputs);
+        builder.params.cond_output = cond(&cond_graph, &cond_inputs)?.output;
+
+        let body_graph = WhileBuilder::graph(builder.params.body_graph);
+        let body_inputs = WhileBuilder::inputs(builder.params.body_inputs, ninputs);
+        let body_outputs = body(&body_graph, &body_inputs)?;
+        if body_outputs.len() != ninputs {
+            return Err(Error::InvalidControlFlow(format!(
+                "loop body returned {} outputs for {} loop variables",
+                body_outputs.len(),
+                ninputs
+            )));
+        }
+        let outputs = unsafe { slice::from_raw_parts_mut(builder.params.body_outputs, ninputs) };
+        for (output, body_output) in outputs.iter_mut().zip(body_outputs) {
+            *output = body_output.output;
+        }
+
+        let mut outputs = vec![
+            tf::TF_Output {
+                oper: std::ptr::null_mut(),
+                index: 0
+            };
+            ninputs
+        ];
+        // Loop params are freed by `TF_FinishWhile` even if it fails
+        builder.finished = true;
+        unsafe { tf::TF_FinishWhile(&builder.params, status.get(), outputs.as_mut_ptr()) };
+        status.to_result()?;
+
+        Ok(outputs
+            .into_iter()
+            .map(|output| unsafe { Output::new(output.oper, output.index) })
+            .collect())
+    }
+
+    /// Adds conditional to graph, using `If` operation. Depending on value of
+    /// scalar `pred`, `then_branch` or `else_branch` is computed. Branches get
+    /// own graph to which their operations have to be added, and `inputs`
+    /// in this graph, and have to return outputs of the same types. Returns
+    /// outputs of computed branch.
+    ///
+    /// Branches are converted to functions named with given `name`, which
+    /// has to be unique in graph.
+    ///
+    /// ```rust
+    /// # use rustflow::{Graph, Tensor, TensorType};
+    /// # let graph = Graph::new();
+    /// # let pred = Tensor::from_slice(&[], &[true]).unwrap();
+    /// # let pred = graph
+    /// #     .new_operation("Const", "pred").unwrap()
+    /// #     .set_attr_tensor("value", pred.as_ref()).unwrap()
+    /// #     .set_attr_type("dtype", bool::TF_TYPE).unwrap()
+    /// #     .finish().unwrap();
+    /// # let pred = pred.output(0).unwrap();
+    /// # let x = Tensor::from_slice(&[], &[1.0f32]).unwrap();
+    /// # let x = graph
+    /// #     .new_operation("Const", "x").unwrap()
+    /// #     .set_attr_tensor("value", x.as_ref()).unwrap()
+    /// #     .set_attr_type("dtype", f32::TF_TYPE).unwrap()
+    /// #     .finish().unwrap();
+    /// # let x = x.output(0).unwrap();
+    /// let outputs = graph
+    ///     .cond(
+    ///         "abs",
+    ///         pred,
+    ///         &[x],
+    ///         |_, inputs| Ok(inputs.to_vec()),
+    ///         |graph, inputs| {
+    ///             let neg = graph
+    ///                 .new_operation("Neg", "neg")?
+    ///                 .add_input(inputs[0])
+    ///                 .finish()?;
+    ///             Ok(vec![neg.output(0).unwrap()])
+    ///         },
+    ///     )
+    ///     .unwrap();
+    /// assert_eq!(Some(f32::TF_TYPE), outputs[0].dtype());
+    /// ```
+    pub fn cond<'a, T, E>(
+        &'a self,
+        name: &str,
+        pred: Output<'a>,
+        inputs: &[Output<'a>],
+        then_branch: T,
+        else_branch: E,
+    ) -> Result<Vec<Output<'a>>>
+    where
+        T: for<'g> FnOnce(&'g Graph, &[Output<'g>]) -> Result<Vec<Output<'g>>>,
+        E: for<'g> FnOnce(&'g Graph, &[Output<'g>]) -> Result<Vec<Output<'g>>>,
+    {
+        self.cond_impl("If", name, pred, inputs, then_branch, else_branch)
+    }
+
+    /// Adds conditional the same way as `Graph::cond`, but using
+    /// `StatelessIf` operation, which allows more optimizations. Branches
+    /// must not contain stateful operations.
+    pub fn stateless_cond<'a, T, E>(
+        &'a self,
+        name: &str,
+        pred: Output<'a>,
+        inputs: &[Output<'a>],
+        then_branch: T,
+        else_branch: E,
+    ) -> Result<Vec<Output<'a>>>
+    where
+        T: for<'g> FnOnce(&'g Graph, &[Output<'g>]) -> Result<Vec<Output<'g>>>,
+        E: for<'g> FnOnce(&'g Graph, &[Output<'g>]) -> Result<Vec<Output<'g>>>,
+    {
+        self.cond_impl("StatelessIf", name, pred, inputs, then_branch, else_branch)
+    }
+
+    fn cond_impl<'a, T, E>(
+        &'a self,
+        op_type: &str,
+        name: &str,
+        pred: Output<'a>,
+        inputs: &[Output<'a>],
+        then_branch: T,
+        else_branch: E,
+    ) -> Result<Vec<Output<'a>>>
+    where
+        T: for<'g> FnOnce(&'g Graph, &[Output<'g>]) -> Result<Vec<Output<'g>>>,
+        E: for<'g> FnOnce(&'g Graph, &[Output<'g>]) -> Result<Vec<Output<'g>>>,
+    {
+        let input_types = dtypes(inputs)?;
+        let pred_type = dtypes(&[pred])?[0];
+        let (then_branch, then_types) =
+            branch(&format!("{}_then", name), &input_types, then_branch)?;
+        let (else_branch, else_types) =
+            branch(&format!("{}_else", name), &input_types, else_branch)?;
+        if then_types != else_types {
+            return Err(Error::InvalidControlFlow(format!(
+                "branches output types differ: {:?} and {:?}",
+                then_types, else_types
+            )));
+        }
+
+        self.copy_function(&then_branch, None)?;
+        self.copy_function(&else_branch, None)?;
+        let op = self
+            .new_operation(op_type, name)?
+            .add_input(pred)
+            .add_input_list(inputs)
+            .set_attr_type("Tcond", pred_type)?
+            .set_attr_type_list("Tin", &input_types)?
+            .set_attr_type_list("Tout", &then_types)?
+            .set_attr_func_name("then_branch", then_branch.name())?
+            .set_attr_func_name("else_branch", else_branch.name())?
+            .finish()?;
+
+        Ok(op.outputs().collect())
+    }
+}
+
+fn dtypes(outputs: &[Output]) -> Result<Vec<tf::TF_DataType>> {
+    outputs
+        .iter()
+        .map(|output| {
+            output.dtype().ok_or_else(|| {
+                Error::InvalidControlFlow(format!(
+                    "output of {:?} has unknown type",
+                    output.operation().name()
+                ))
+            })
+        })
+        .collect()
+}
+
+/// Builds conditional branch in separate graph, returning it as function
+/// together with its output types
+fn branch<F>(
+    name: &str,
+    input_types: &[tf::TF_DataType],
+    build: F,
+) -> Result<(Function, Vec<tf::TF_DataType>)>
+where
+    F: for<'g> FnOnce(&'g Graph, &[Output<'g>]) -> Result<Vec<Output<'g>>>,
+{
+    let graph = Graph::new();
+    let inputs = input_types
+        .iter()
+        .enumerate()
+        .map(|(idx, dtype)| {
+            let input = graph
+                .new_operation("Placeholder", &format!("input_{}", idx))?
+                .set_attr_type("dtype", *dtype)?
+                .finish()?;
+            Ok(input.output(0).unwrap())
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let outputs = build(&graph, &inputs)?;
+    let output_types = dtypes(&outputs)?;
+    let function = Function::from_graph(&graph, name, &inputs, &outputs)?;
+    Ok((function, output_types))
+}
@@ -0,0 +1,57 @@
+//! CRC checksums used by file formats
+
+/// Builds lookup table for reflected CRC-32 with given polynomial
+const fn table(polynomial: u32) -> [u32; 256] {
+    let mut table = [0; 256];
+    let mut idx = 0;
+    while idx < 256 {
+        let mut crc = idx as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ polynomial
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[idx] = crc;
+        idx += 1;
+    }
+    table
+}
+
+/// Table for CRC-32 (IEEE), as used by zip archives
+const CRC32: [u32; 256] = table(0xedb8_8320);
+
+/// Table for CRC-32C (Castagnoli), as used by tensorflow checkpoints
+const CRC32C: [u32; 256] = table(0x82f6_3b78);
+
+fn checksum(table: &[u32; 256], crc: u32, data: &[u8]) -> u32 {
+    !data.iter().fold(!crc, |crc, byte| {
+        table[((crc ^ u32::from(*byte)) & 0xff) as usize] ^ (crc >> 8)
+    })
+}
+
+/// Returns CRC-32 (IEEE) checksum of data
+pub fn crc32(data: &[u8]) -> u32 {
+    checksum(&CRC32, 0, data)
+}
+
+/// Returns CRC-32C (Castagnoli) checksum of data
+pub fn crc32c(data: &[u8]) -> u32 {
+    crc32c_extend(0, data)
+}
+
+/// Returns CRC-32C checksum of concatenation of data with checksum `crc`
+/// and `data`
+pub fn crc32c_extend(crc: u32, data: &[u8]) -> u32 {
+    checksum(&CRC32C, crc, data)
+}
+
+/// Reverts masking of CRC-32C checksum, as stored by tensorflow (after
+/// leveldb), which makes checksums of data containing checksums less
+/// predictable
+pub fn unmask(masked: u32) -> u32 {
+    masked.wrapping_sub(0xa282_ead8).rotate_left(15)
+}
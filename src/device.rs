@@ -0,0 +1,62 @@
+use crate::{Result, Status};
+use std::ffi::CStr;
+use tf;
+
+/// Description of device available for session
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeviceInfo {
+    /// Full device name, like `/job:localhost/replica:0/task:0/device:CPU:0`
+    pub name: String,
+    /// Device type, like `CPU` or `GPU`
+    pub device_type: String,
+    /// Amount of memory available on device in bytes
+    pub memory_bytes: i64,
+}
+
+/// Wrapper for tensorflow `TF_DeviceList`, ensuring its deletion
+pub(crate) struct DeviceList(*mut tf::TF_DeviceList);
+
+impl DeviceList {
+    /// Function is unsafe, because callee has to ensure, that `list`
+    /// is valid TF_DeviceList object which is not owned by anything else
+    pub unsafe fn new(list: *mut tf::TF_DeviceList) -> Self {
+        DeviceList(list)
+    }
+
+    /// Reads all devices information from list
+    pub fn devices(&self) -> Result<Vec<DeviceInfo>> {
+        let count = unsafe { tf::TF_DeviceListCount(self.0) };
+        (0..count).map(|idx| self.device(idx)).collect()
+    }
+
+    fn device(&self, idx: i32) -> Result<DeviceInfo> {
+        let mut status = Status::new();
+
+        let name = unsafe { tf::TF_DeviceListName(self.0, idx, status.get()) };
+        status.to_result()?;
+        let name = unsafe { CStr::from_ptr(name) }
+            .to_string_lossy()
+            .into_owned();
+
+        let device_type = unsafe { tf::TF_DeviceListType(self.0, idx, status.get()) };
+        status.to_result()?;
+        let device_type = unsafe { CStr::from_ptr(device_type) }
+            .to_string_lossy()
+            .into_owned();
+
+        let memory_bytes = unsafe { tf::TF_DeviceListMemoryBytes(self.0, idx, status.get()) };
+        status.to_result()?;
+
+        Ok(DeviceInfo {
+            name,
+            device_type,
+            memory_bytes,
+        })
+    }
+}
+
+impl Drop for DeviceList {
+    fn drop(&mut self) {
+        unsafe { tf::TF_DeleteDeviceList(self.0) }
+    }
+}
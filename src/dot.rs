@@ -0,0 +1,144 @@
+//! Exporting graph structure as Graphviz DOT document
+
+use crate::tensor_type::data_type_name;
+use crate::{Graph, Operation, Output, Result};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+/// Name scope of operations, with nested scopes
+#[derive(Default)]
+struct Scope<'a> {
+    operations: Vec<Operation<'a>>,
+    scopes: BTreeMap<String, Scope<'a>>,
+}
+
+impl<'a> Scope<'a> {
+    fn insert(&mut self, path: &[&str], op: Operation<'a>) {
+        match path.split_first() {
+            Some((scope, path)) => self
+                .scopes
+                .entry((*scope).to_owned())
+                .or_default()
+                .insert(path, op),
+            None => self.operations.push(op),
+        }
+    }
+
+    fn write(&self, dot: &mut String, prefix: &str, indent: usize) {
+        let pad = "  ".repeat(indent);
+        for op in &self.operations {
+            write_operation(dot, &pad, *op);
+        }
+        for (name, scope) in &self.scopes {
+            let path = format!("{}{}", prefix, name);
+            writeln!(
+                dot,
+                "{}subgraph {} {{",
+                pad,
+                quote(&format!("cluster_{}", path))
+            )
+            .unwrap();
+            writeln!(dot, "{}  label={};", pad, quote(name)).unwrap();
+            scope.write(dot, &format!("{}/", path), indent + 1);
+            writeln!(dot, "{}}}", pad).unwrap();
+        }
+    }
+}
+
+/// Quotes DOT identifier, escaping new lines so they are kept in labels
+fn quote(id: &str) -> String {
+    let id = id
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n");
+    format!("\"{}\"", id)
+}
+
+fn write_operation(dot: &mut String, pad: &str, op: Operation) {
+    let mut label = format!("{}\n{}", op.name(), op.op_type());
+    if !op.device().is_empty() {
+        label = format!("{}\n{}", label, op.device());
+    }
+    writeln!(
+        dot,
+        "{}{} [label={}];",
+        pad,
+        quote(op.name()),
+        quote(&label)
+    )
+    .unwrap();
+}
+
+/// Describes tensors passed through data edge, like `float [?, 3]`
+fn edge_label(graph: &Graph, output: Output) -> Result<String> {
+    let mut label = output.dtype().map_or("", data_type_name).to_owned();
+    if let Some(shape) = graph.tensor_shape(output)? {
+        let dims: Vec<_> = shape
+            .iter()
+            .map(|dim| match dim {
+                -1 => "?".to_owned(),
+                dim => dim.to_string(),
+            })
+            .collect();
+        label = format!("{} [{}]", label, dims.join(", "));
+    }
+    Ok(label.trim().to_owned())
+}
+
+impl Graph {
+    /// Returns Graphviz DOT document describing graph. Operations are
+    /// labelled with their names, types and devices, data edges with types
+    /// and shapes of passed tensors, and control edges are dashed. If
+    /// `cluster_scopes` is set, operations are grouped in clusters by name
+    /// scopes (`/` separated prefixes of names).
+    ///
+    /// ```rust
+    /// # use rustflow::Graph;
+    /// let proto = include_str!("../tests/data/addition.pb");
+    /// let graph = Graph::from_protobuff(proto).unwrap();
+    /// let dot = graph.to_dot(false).unwrap();
+    /// assert!(dot.starts_with("digraph {"));
+    /// assert!(dot.contains("\"x\" -> \"z\""));
+    /// ```
+    pub fn to_dot(&self, cluster_scopes: bool) -> Result<String> {
+        let mut dot = String::from("digraph {\n  node [shape=box];\n");
+
+        let mut root = Scope::default();
+        for op in self.operations() {
+            if cluster_scopes {
+                let mut path: Vec<_> = op.name().split('/').collect();
+                path.pop();
+                root.insert(&path, op);
+            } else {
+                root.operations.push(op);
+            }
+        }
+        root.write(&mut dot, "", 1);
+
+        for op in self.operations() {
+            for input in op.inputs() {
+                let producer = input.producer();
+                writeln!(
+                    dot,
+                    "  {} -> {} [label={}];",
+                    quote(producer.operation().name()),
+                    quote(op.name()),
+                    quote(&edge_label(self, producer)?)
+                )
+                .unwrap();
+            }
+            for control in op.control_inputs() {
+                writeln!(
+                    dot,
+                    "  {} -> {} [style=dashed];",
+                    quote(control.name()),
+                    quote(op.name())
+                )
+                .unwrap();
+            }
+        }
+
+        dot.push_str("}\n");
+        Ok(dot)
+    }
+}
@@ -0,0 +1,436 @@
+//! Eager execution API, which allows running single operations without
+//! building graph and session.
+//!
+//! ```rust
+//! # use rustflow::eager::{Context, TensorHandle};
+//! # use rustflow::{Tensor, TensorType};
+//! let ctx = Context::new().unwrap();
+//! let x = Tensor::from_slice(&[2], &[1.0f32, 2.0]).unwrap();
+//! let y = Tensor::from_slice(&[2], &[3.0f32, 4.0]).unwrap();
+//! let x = TensorHandle::new(&ctx, x.as_ref()).unwrap();
+//! let y = TensorHandle::new(&ctx, y.as_ref()).unwrap();
+//!
+//! let z = ctx
+//!     .new_op("Add")
+//!     .unwrap()
+//!     .add_input(&x)
+//!     .unwrap()
+//!     .add_input(&y)
+//!     .unwrap()
+//!     .set_attr_type("T", f32::TF_TYPE)
+//!     .unwrap()
+//!     .execute(1)
+//!     .unwrap();
+//!
+//! let z = z[0].resolve().unwrap().downcast::<f32>().unwrap();
+//! assert_eq!(&[4.0, 6.0], &z[..]);
+//! ```
+
+use crate::device::DeviceList;
+use crate::{AnyTensor, DeviceInfo, Result, Status};
+use std::ffi::{CStr, CString};
+use std::marker::PhantomData;
+use std::os::raw::c_void;
+use std::ptr;
+use tf;
+
+/// Eager C API bindings, which are not provided by `tensorflow-sys`.
+/// Symbols are part of tensorflow shared library anyway.
+#[allow(non_camel_case_types)]
+mod sys {
+    use std::os::raw::{c_char, c_float, c_int, c_uchar, c_void};
+    use tf::{TF_DataType, TF_DeviceList, TF_Status, TF_Tensor};
+
+    #[repr(C)]
+    pub struct TFE_ContextOptions {
+        _unused: [u8; 0],
+    }
+
+    #[repr(C)]
+    pub struct TFE_Context {
+        _unused: [u8; 0],
+    }
+
+    #[repr(C)]
+    pub struct TFE_TensorHandle {
+        _unused: [u8; 0],
+    }
+
+    #[repr(C)]
+    pub struct TFE_Op {
+        _unused: [u8; 0],
+    }
+
+    extern "C" {
+        pub fn TFE_NewContextOptions() -> *mut TFE_ContextOptions;
+        pub fn TFE_DeleteContextOptions(options: *mut TFE_ContextOptions);
+        pub fn TFE_NewContext(
+            opts: *const TFE_ContextOptions,
+            status: *mut TF_Status,
+        ) -> *mut TFE_Context;
+        pub fn TFE_DeleteContext(ctx: *mut TFE_Context);
+        pub fn TFE_ContextListDevices(
+            ctx: *mut TFE_Context,
+            status: *mut TF_Status,
+        ) -> *mut TF_DeviceList;
+
+        pub fn TFE_NewTensorHandle(
+            t: *mut TF_Tensor,
+            status: *mut TF_Status,
+        ) -> *mut TFE_TensorHandle;
+        pub fn TFE_DeleteTensorHandle(h: *mut TFE_TensorHandle);
+        pub fn TFE_TensorHandleDataType(h: *mut TFE_TensorHandle) -> TF_DataType;
+        pub fn TFE_TensorHandleNumDims(h: *mut TFE_TensorHandle, status: *mut TF_Status) -> c_int;
+        pub fn TFE_TensorHandleDim(
+            h: *mut TFE_TensorHandle,
+            dim_index: c_int,
+            status: *mut TF_Status,
+        ) -> i64;
+        pub fn TFE_TensorHandleDeviceName(
+            h: *mut TFE_TensorHandle,
+            status: *mut TF_Status,
+        ) -> *const c_char;
+        pub fn TFE_TensorHandleResolve(
+            h: *mut TFE_TensorHandle,
+            status: *mut TF_Status,
+        ) -> *mut TF_Tensor;
+
+        pub fn TFE_NewOp(
+            ctx: *mut TFE_Context,
+            op_or_function_name: *const c_char,
+            status: *mut TF_Status,
+        ) -> *mut TFE_Op;
+        pub fn TFE_DeleteOp(op: *mut TFE_Op);
+        pub fn TFE_OpSetDevice(op: *mut TFE_Op, device_name: *const c_char, status: *mut TF_Status);
+        pub fn TFE_OpAddInput(
+            op: *mut TFE_Op,
+            input: *mut TFE_TensorHandle,
+            status: *mut TF_Status,
+        );
+        pub fn TFE_OpSetAttrString(
+            op: *mut TFE_Op,
+            attr_name: *const c_char,
+            value: *const c_void,
+            length: usize,
+        );
+        pub fn TFE_OpSetAttrInt(op: *mut TFE_Op, attr_name: *const c_char, value: i64);
+        pub fn TFE_OpSetAttrIntList(
+            op: *mut TFE_Op,
+            attr_name: *const c_char,
+            values: *const i64,
+            num_values: c_int,
+        );
+        pub fn TFE_OpSetAttrFloat(op: *mut TFE_Op, attr_name: *const c_char, value: c_float);
+        pub fn TFE_OpSetAttrBool(op: *mut TFE_Op, attr_name: *const c_char, value: c_uchar);
+        pub fn TFE_OpSetAttrType(op: *mut TFE_Op, attr_name: *const c_char, value: TF_DataType);
+        pub fn TFE_OpSetAttrTypeList(
+            op: *mut TFE_Op,
+            attr_name: *const c_char,
+            values: *const TF_DataType,
+            num_values: c_int,
+        );
+        pub fn TFE_OpSetAttrShape(
+            op: *mut TFE_Op,
+            attr_name: *const c_char,
+            dims: *const i64,
+            num_dims: c_int,
+            status: *mut TF_Status,
+        );
+        pub fn TFE_Execute(
+            op: *mut TFE_Op,
+            retvals: *mut *mut TFE_TensorHandle,
+            num_retvals: *mut c_int,
+            status: *mut TF_Status,
+        );
+    }
+}
+
+/// Thin wrapper over tensorflow eager context, which owns devices
+/// operations are executed on
+pub struct Context(*mut sys::TFE_Context);
+
+impl Context {
+    /// Creates new eager context with default options
+    pub fn new() -> Result<Self> {
+        let mut status = Status::new();
+        let context = unsafe {
+            let options = sys::TFE_NewContextOptions();
+            let context = sys::TFE_NewContext(options, status.get());
+            sys::TFE_DeleteContextOptions(options);
+            context
+        };
+
+        status.to_result()?;
+        Ok(Context(context))
+    }
+
+    /// Returns all devices available for this context
+    ///
+    /// ```rust
+    /// # use rustflow::eager::Context;
+    /// let ctx = Context::new().unwrap();
+    /// let devices = ctx.devices().unwrap();
+    /// assert!(devices.iter().any(|device| device.device_type == "CPU"));
+    /// ```
+    pub fn devices(&self) -> Result<Vec<DeviceInfo>> {
+        let mut status = Status::new();
+        let list = unsafe { sys::TFE_ContextListDevices(self.0, status.get()) };
+        status.to_result()?;
+
+        unsafe { DeviceList::new(list) }.devices()
+    }
+
+    /// Starts building operation of given type to be executed in
+    /// this context
+    pub fn new_op(&self, op_type: &str) -> Result<EagerOp> {
+        let op_type = CString::new(op_type)?;
+        let mut status = Status::new();
+        let op = unsafe { sys::TFE_NewOp(self.0, op_type.as_ptr(), status.get()) };
+        status.to_result()?;
+
+        Ok(EagerOp {
+            op,
+            _phantom: PhantomData,
+        })
+    }
+}
+
+impl Drop for Context {
+    fn drop(&mut self) {
+        unsafe { sys::TFE_DeleteContext(self.0) }
+    }
+}
+
+/// Thin wrapper over tensorflow eager tensor handle. Tensor handles are
+/// using context devices, so artificial lifetime is added to ensure they
+/// don't outlive the context.
+pub struct TensorHandle<'a> {
+    handle: *mut sys::TFE_TensorHandle,
+    _phantom: PhantomData<&'a Context>,
+}
+
+impl<'a> TensorHandle<'a> {
+    /// Creates tensor handle with value of given tensor. Tensor data is
+    /// copied, as tensor may be still modified through its owner.
+    pub fn new(_ctx: &'a Context, tensor: &AnyTensor) -> Result<Self> {
+        // Handle shares data buffer with tensor it is created from, which
+        // is private copy released by tensorflow together with handle
+        let tensor = tensor.deep_copy()?;
+        let mut status = Status::new();
+        let handle = unsafe { sys::TFE_NewTensorHandle(tensor.tensor, status.get()) };
+        status.to_result()?;
+
+        Ok(TensorHandle {
+            handle,
+            _phantom: PhantomData,
+        })
+    }
+
+    /// Returns data type of tensor elements
+    pub fn dtype(&self) -> tf::TF_DataType {
+        unsafe { sys::TFE_TensorHandleDataType(self.handle) }
+    }
+
+    /// Returns shape of tensor
+    ///
+    /// ```rust
+    /// # use rustflow::eager::{Context, TensorHandle};
+    /// # use rustflow::Tensor;
+    /// let ctx = Context::new().unwrap();
+    /// let tensor = Tensor::from_slice(&[2, 1], &[1, 2]).unwrap();
+    /// let handle = TensorHandle::new(&ctx, tensor.as_ref()).unwrap();
+    /// assert_eq!(vec![2, 1], handle.shape().unwrap());
+    /// ```
+    pub fn shape(&self) -> Result<Vec<i64>> {
+        let mut status = Status::new();
+        let dims = unsafe { sys::TFE_TensorHandleNumDims(self.handle, status.get()) };
+        status.to_result()?;
+
+        (0..dims)
+            .map(|dim| {
+                let size = unsafe { sys::TFE_TensorHandleDim(self.handle, dim, status.get()) };
+                status.to_result()?;
+                Ok(size)
+            })
+            .collect()
+    }
+
+    /// Returns name of device tensor is placed on
+    pub fn device_name(&self) -> Result<String> {
+        let mut status = Status::new();
+        let name = unsafe { sys::TFE_TensorHandleDeviceName(self.handle, status.get()) };
+        status.to_result()?;
+
+        Ok(unsafe { CStr::from_ptr(name) }
+            .to_string_lossy()
+            .into_owned())
+    }
+
+    /// Copies tensor value to host memory, waiting for all operations
+    /// computing it to finish. Returned tensor never shares data with the
+    /// handle, so every resolved tensor may be modified independently.
+    pub fn resolve(&self) -> Result<AnyTensor> {
+        let mut status = Status::new();
+        let tensor = unsafe { sys::TFE_TensorHandleResolve(self.handle, status.get()) };
+        status.to_result()?;
+
+        // Host tensors are resolved to tensor sharing buffer with handle
+        let shared = unsafe { AnyTensor::from_ptr(tensor) };
+        shared.deep_copy()
+    }
+}
+
+impl<'a> Drop for TensorHandle<'a> {
+    fn drop(&mut self) {
+        unsafe { sys::TFE_DeleteTensorHandle(self.handle) }
+    }
+}
+
+/// Single operation to be executed eagerly, bound to its context
+pub struct EagerOp<'a> {
+    op: *mut sys::TFE_Op,
+    _phantom: PhantomData<&'a Context>,
+}
+
+impl<'a> EagerOp<'a> {
+    /// Adds operation input
+    pub fn add_input(self, input: &TensorHandle<'a>) -> Result<Self> {
+        let mut status = Status::new();
+        unsafe { sys::TFE_OpAddInput(self.op, input.handle, status.get()) };
+        status.to_result()?;
+        Ok(self)
+    }
+
+    /// Sets device operation should be executed on, like `/device:CPU:0`
+    pub fn set_device(self, device: &str) -> Result<Self> {
+        let device = CString::new(device)?;
+        let mut status = Status::new();
+        unsafe { sys::TFE_OpSetDevice(self.op, device.as_ptr(), status.get()) };
+        status.to_result()?;
+        Ok(self)
+    }
+
+    /// Sets string attribute value
+    pub fn set_attr_string(self, name: &str, value: &str) -> Result<Self> {
+        let name = CString::new(name)?;
+        unsafe {
+            sys::TFE_OpSetAttrString(
+                self.op,
+                name.as_ptr(),
+                value.as_ptr() as *const c_void,
+                value.len(),
+            )
+        };
+        Ok(self)
+    }
+
+    /// Sets integer attribute value
+    pub fn set_attr_int(self, name: &str, value: i64) -> Result<Self> {
+        let name = CString::new(name)?;
+        unsafe { sys::TFE_OpSetAttrInt(self.op, name.as_ptr(), value) };
+        Ok(self)
+    }
+
+    /// Sets integer list attribute value
+    pub fn set_attr_int_list(self, name: &str, values: &[i64]) -> Result<Self> {
+        let name = CString::new(name)?;
+        unsafe {
+            sys::TFE_OpSetAttrIntList(self.op, name.as_ptr(), values.as_ptr(), values.len() as i32)
+        };
+        Ok(self)
+    }
+
+    /// Sets float attribute value
+    pub fn set_attr_float(self, name: &str, value: f32) -> Result<Self> {
+        let name = CString::new(name)?;
+        unsafe { sys::TFE_OpSetAttrFloat(self.op, name.as_ptr(), value) };
+        Ok(self)
+    }
+
+    /// Sets boolean attribute value
+    pub fn set_attr_bool(self, name: &str, value: bool) -> Result<Self> {
+        let name = CString::new(name)?;
+        unsafe { sys::TFE_OpSetAttrBool(self.op, name.as_ptr(), value as u8) };
+        Ok(self)
+    }
+
+    /// Sets data type attribute value
+    pub fn set_attr_type(self, name: &str, value: tf::TF_DataType) -> Result<Self> {
+        let name = CString::new(name)?;
+        unsafe { sys::TFE_OpSetAttrType(self.op, name.as_ptr(), value) };
+        Ok(self)
+    }
+
+    /// Sets data type list attribute value
+    pub fn set_attr_type_list(self, name: &str, values: &[tf::TF_DataType]) -> Result<Self> {
+        let name = CString::new(name)?;
+        unsafe {
+            sys::TFE_OpSetAttrTypeList(self.op, name.as_ptr(), values.as_ptr(), values.len() as i32)
+        };
+        Ok(self)
+    }
+
+    /// Sets shape attribute value. Unknown dimensions should be set to `-1`,
+    /// and `None` means shape of unknown rank.
+    pub fn set_attr_shape(self, name: &str, shape: Option<&[i64]>) -> Result<Self> {
+        let name = CString::new(name)?;
+        let (dims, num_dims) = match shape {
+            Some(shape) => (shape.as_ptr(), shape.len() as i32),
+            None => (ptr::null(), -1),
+        };
+        let mut status = Status::new();
+        unsafe { sys::TFE_OpSetAttrShape(self.op, name.as_ptr(), dims, num_dims, status.get()) };
+        status.to_result()?;
+        Ok(self)
+    }
+
+    /// Executes operation, returning handles to its outputs. `num_outputs`
+    /// is the number of outputs operation produces.
+    ///
+    /// ```rust
+    /// # use rustflow::eager::{Context, TensorHandle};
+    /// # use rustflow::{Tensor, TensorType};
+    /// let ctx = Context::new().unwrap();
+    /// let x = Tensor::from_slice(&[3], &[3, 1, 2]).unwrap();
+    /// let x = TensorHandle::new(&ctx, x.as_ref()).unwrap();
+    /// let outputs = ctx
+    ///     .new_op("Unique")
+    ///     .unwrap()
+    ///     .add_input(&x)
+    ///     .unwrap()
+    ///     .set_attr_type("T", i32::TF_TYPE)
+    ///     .unwrap()
+    ///     .execute(2)
+    ///     .unwrap();
+    /// assert_eq!(2, outputs.len());
+    /// ```
+    pub fn execute(self, num_outputs: usize) -> Result<Vec<TensorHandle<'a>>> {
+        let mut retvals = vec![ptr::null_mut(); num_outputs];
+        let mut num_retvals = num_outputs as i32;
+        let mut status = Status::new();
+        unsafe {
+            sys::TFE_Execute(
+                self.op,
+                retvals.as_mut_ptr(),
+                &mut num_retvals,
+                status.get(),
+            )
+        };
+        status.to_result()?;
+
+        retvals.truncate(num_retvals as usize);
+        Ok(retvals
+            .into_iter()
+            .map(|handle| TensorHandle {
+                handle,
+                _phantom: PhantomData,
+            })
+            .collect())
+    }
+}
+
+impl<'a> Drop for EagerOp<'a> {
+    fn drop(&mut self) {
+        unsafe { sys::TFE_DeleteOp(self.op) }
+    }
+}
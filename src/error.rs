@@ -47,6 +47,11 @@ impl Status {
             Ok(())
         }
     }
+
+    /// Returns error, if any, marked as raised by given library function
+    pub fn to_result_in(&self, function: &'static str) -> Result<(), TFError> {
+        self.to_result().map_err(|err| err.in_function(function))
+    }
 }
 
 impl Drop for Status {
@@ -57,7 +62,7 @@ impl Drop for Status {
 
 macro_rules! def_code {
     ($($rval:ident : $cval:ident),*) => {
-        #[derive(Debug, PartialEq, Eq)]
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
         pub enum TFCode {
             $($rval),*
         }
@@ -92,30 +97,89 @@ def_code! {
     Unauthenticated: TF_UNAUTHENTICATED
 }
 
-/// Wrapped tensorflow error
-#[derive(Debug, Fail, PartialEq, Eq)]
+/// Wrapped tensorflow error. It's displayed together with its context,
+/// like ``Session::run failed at node `z` (InvalidArgument): message``.
+#[derive(Debug, Clone, Fail, PartialEq, Eq)]
 pub struct TFError {
     code: TFCode,
     message: String,
+    node: Option<String>,
+    function: Option<&'static str>,
+}
+
+/// Returns name of node referenced by tensorflow error message, like
+/// `z` in `[[{{node z}}]]`, or in `[[Node: z = Add[...]]]` in older
+/// tensorflow versions
+fn node_name(message: &str) -> Option<String> {
+    const MARKERS: &[(&str, &[char])] = &[("{{node ", &['}']), ("[[Node: ", &[' ', ']'])];
+    MARKERS.iter().find_map(|(marker, terminators)| {
+        let start = message.find(marker)? + marker.len();
+        let name = &message[start..];
+        let name = &name[..name.find(*terminators).unwrap_or(name.len())];
+        Some(name.to_owned()).filter(|name| !name.is_empty())
+    })
 }
 
 impl TFError {
     pub(crate) fn from_status(status: &Status) -> Option<Self> {
+        let message = status.message();
         Some(TFError {
             code: TFCode::from_status(status)?,
-            message: status.message(),
+            node: node_name(&message),
+            message,
+            function: None,
         })
     }
+
+    /// Marks error as raised by given library function, like `Session::run`
+    pub(crate) fn in_function(self, function: &'static str) -> Self {
+        TFError {
+            function: Some(function),
+            ..self
+        }
+    }
+
+    /// Returns tensorflow error code
+    pub fn code(&self) -> TFCode {
+        self.code
+    }
+
+    /// Returns error message, as reported by tensorflow
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Returns name of graph node which caused error, if tensorflow
+    /// reported it
+    pub fn node(&self) -> Option<&str> {
+        self.node.as_deref()
+    }
+
+    /// Returns name of library function which failed, like `Session::run`,
+    /// if it's known
+    pub fn function(&self) -> Option<&str> {
+        self.function
+    }
 }
 
 impl fmt::Display for TFError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.message)
+        match self.function {
+            Some(function) => write!(f, "{} failed", function)?,
+            None => write!(f, "{:?}", self.code)?,
+        }
+        if let Some(node) = &self.node {
+            write!(f, " at node `{}`", node)?;
+        }
+        if self.function.is_some() {
+            write!(f, " ({:?})", self.code)?;
+        }
+        write!(f, ": {}", self.message)
     }
 }
 
 /// Any Error produced by library
-#[derive(Debug, Fail, PartialEq, Eq)]
+#[derive(Debug, Clone, Fail, PartialEq, Eq)]
 pub enum Error {
     #[fail(display = "Tensorflow error: {}", _0)]
     TFError(TFError),
@@ -128,6 +192,75 @@ pub enum Error {
 
     #[fail(display = "TF object creation failed")]
     ObjectCreationFailure,
+
+    #[fail(display = "String {:?} contains nul byte", _0)]
+    NulString(String),
+
+    #[fail(display = "Run pool is already shut down")]
+    PoolShutdown,
+
+    #[fail(display = "Session run panicked")]
+    RunPanicked,
+
+    #[fail(display = "Invalid batch input: {}", _0)]
+    InvalidBatchInput(String),
+
+    #[fail(display = "Batcher is already shut down")]
+    BatcherShutdown,
+
+    #[fail(display = "Operation {:?} not found in graph", _0)]
+    OperationNotFound(String),
+
+    #[fail(display = "Unsupported data type {:?}", _0)]
+    UnsupportedType(tf::TF_DataType),
+
+    #[fail(display = "Invalid control flow: {}", _0)]
+    InvalidControlFlow(String),
+
+    #[fail(display = "Invalid protobuf message: {}", _0)]
+    InvalidProtobuf(String),
+
+    #[fail(display = "Unknown operations: {:?}", _0)]
+    UnknownOps(Vec<String>),
+
+    #[fail(display = "Invalid graph: {}", _0)]
+    InvalidGraph(String),
+
+    #[fail(display = "I/O error: {}", _0)]
+    Io(String),
+
+    #[fail(display = "Invalid npy file: {}", _0)]
+    InvalidNpy(String),
+
+    #[fail(display = "Invalid safetensors file: {}", _0)]
+    InvalidSafetensors(String),
+
+    #[fail(display = "Invalid JSON tensor: {}", _0)]
+    InvalidJson(String),
+
+    #[fail(display = "Invalid checkpoint: {}", _0)]
+    InvalidCheckpoint(String),
+
+    #[fail(display = "Variable {:?} not found in checkpoint", _0)]
+    VariableNotFound(String),
+
+    #[fail(display = "Expected tensor of type {:?}, found {:?}", expected, found)]
+    TypeMismatch {
+        expected: tf::TF_DataType,
+        found: tf::TF_DataType,
+    },
+}
+
+impl From<std::ffi::NulError> for Error {
+    fn from(err: std::ffi::NulError) -> Self {
+        Error::NulString(String::from_utf8_lossy(&err.into_vec()).into_owned())
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Error::Io(err.to_string())
+    }
 }
 
 impl From<TFError> for Error {
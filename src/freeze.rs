@@ -0,0 +1,177 @@
+//! Freezing variables into constants, so graph can be used without
+//! restoring checkpoint
+
+use crate::graph::OwnedGraph;
+use crate::graph_def::{GraphDef, InputRef, NodeDef};
+use crate::op_def::AttrValue;
+use crate::{Error, Graph, Operation, Output, Result, Session, Tensor, TensorType};
+use std::collections::{HashMap, HashSet};
+
+/// Returns whether operation is a reference variable, which output is
+/// variable value
+fn is_ref_variable(op: Operation) -> bool {
+    op.op_type() == "VariableV2" || op.op_type() == "Variable"
+}
+
+/// Returns resource variable handle used by operation, if any
+fn resource_variable(op: Operation) -> Option<Operation> {
+    op.inputs()
+        .map(|input| input.producer().operation())
+        .find(|producer| producer.op_type() == "VarHandleOp")
+}
+
+/// Creates graph computing `outputs`, with all variables they depend on
+/// replaced by constants with variables values taken from `session`.
+/// Reference variables (`VariableV2`) are replaced directly, resource
+/// variables (`VarHandleOp`) are replaced together with operations using
+/// them - `ReadVariableOp` becomes `Identity`, and `ResourceGather` becomes
+/// `GatherV2`. Other operations using resource variables are not supported,
+/// and `Error::InvalidGraph` naming them is returned. Resource variable
+/// values are read by `ReadVariableOp` operation of graph, which is also
+/// the case for variables created by python API. Operations not needed to
+/// compute outputs, like initializers or savers, are not included.
+///
+/// Session has to be created for `graph`, with variables initialized.
+pub fn freeze(graph: &Graph, session: &Session, outputs: &[Output]) -> Result<OwnedGraph> {
+    let mut needed = HashSet::new();
+    let mut ref_variables = vec![];
+    let mut reads = vec![];
+    let mut gathers = vec![];
+    let mut stack: Vec<_> = outputs.iter().map(Output::operation).collect();
+    while let Some(op) = stack.pop() {
+        if !needed.insert(op.name().to_owned()) {
+            continue;
+        }
+
+        if is_ref_variable(op) {
+            ref_variables.push(op);
+        } else if let Some(handle) = resource_variable(op) {
+            match op.op_type() {
+                "ReadVariableOp" => reads.push(op),
+                "ResourceGather" => gathers.push(op),
+                op_type => {
+                    return Err(Error::InvalidGraph(format!(
+                        "{} ({}) uses resource variable {}, which is not supported by freeze",
+                        op.name(),
+                        op_type,
+                        handle.name()
+                    )))
+                }
+            }
+        }
+        stack.extend(op.inputs().map(|input| input.producer().operation()));
+        stack.extend(op.control_inputs());
+    }
+
+    // Resource variable may be read by many operations - its value is
+    // taken from the first one. Variables which are only gathered are read
+    // by any other `ReadVariableOp` of graph.
+    let mut handles = HashMap::new();
+    for read in &reads {
+        let handle = resource_variable(*read).unwrap();
+        handles.entry(handle.name().to_owned()).or_insert(*read);
+    }
+    for gather in &gathers {
+        let handle = resource_variable(*gather).unwrap();
+        if handles.contains_key(handle.name()) {
+            continue;
+        }
+
+        let read = graph
+            .operations()
+            .find(|op| op.op_type() == "ReadVariableOp" && resource_variable(*op) == Some(handle))
+            .ok_or_else(|| {
+                Error::InvalidGraph(format!(
+                    "resource variable {} used by {} is never read by ReadVariableOp",
+                    handle.name(),
+                    gather.name()
+                ))
+            })?;
+        handles.insert(handle.name().to_owned(), read);
+    }
+
+    let mut variables: Vec<(String, Output)> = ref_variables
+        .iter()
+        .map(|op| (op.name().to_owned(), op.output(0).unwrap()))
+        .collect();
+    variables.extend(
+        handles
+            .iter()
+            .map(|(handle, read)| (handle.clone(), read.output(0).unwrap())),
+    );
+    let fetches: Vec<_> = variables.iter().map(|(_, output)| *output).collect();
+    let values = session.run(&[], &fetches, &[])?;
+
+    // Constants are built in separate graph, so their definitions can be
+    // copied from it. Gathers get constant axis, as `GatherV2` takes it
+    // as an input.
+    let constants = Graph::new();
+    for ((name, _), value) in variables.iter().zip(&values) {
+        constants
+            .new_operation("Const", name)?
+            .set_attr_tensor("value", value)?
+            .set_attr_type("dtype", value.dtype())?
+            .finish()?;
+    }
+    let axis = Tensor::from_slice(&[], &[0i32])?;
+    for gather in &gathers {
+        constants
+            .new_operation("Const", &format!("{}/axis", gather.name()))?
+            .set_attr_tensor("value", axis.as_ref())?
+            .set_attr_type("dtype", i32::TF_TYPE)?
+            .finish()?;
+    }
+    let constants: HashMap<_, _> = GraphDef::from_graph(&constants)?
+        .nodes
+        .into_iter()
+        .map(|node| (node.name.clone(), node))
+        .collect();
+    let reads: HashSet<_> = reads.iter().map(|op| op.name().to_owned()).collect();
+    let gathers: HashSet<_> = gathers.iter().map(|op| op.name().to_owned()).collect();
+
+    let mut graph_def = GraphDef::from_graph(graph)?;
+    graph_def.nodes.retain(|node| needed.contains(&node.name));
+    for node in &mut graph_def.nodes {
+        if let Some(constant) = constants.get(&node.name) {
+            node.op = constant.op.clone();
+            node.attrs = constant.attrs.clone();
+            node.inputs
+                .retain(|input| matches!(InputRef::parse(input), InputRef::Control(_)));
+        } else if reads.contains(&node.name) {
+            let dtype = required_attr(node, "dtype")?;
+            node.op = "Identity".to_owned();
+            node.attrs.clear();
+            node.set_attr("T", &dtype);
+        } else if gathers.contains(&node.name) {
+            let dtype = required_attr(node, "dtype")?;
+            let indices_type = required_attr(node, "Tindices")?;
+            let batch_dims = node.attr("batch_dims")?;
+            node.op = "GatherV2".to_owned();
+            node.attrs.clear();
+            node.set_attr("Tparams", &dtype);
+            node.set_attr("Tindices", &indices_type);
+            node.set_attr("Taxis", &AttrValue::Type(i32::TF_TYPE));
+            if let Some(batch_dims) = batch_dims {
+                node.set_attr("batch_dims", &batch_dims);
+            }
+            // Axis goes after params and indices, but before control inputs
+            let pos = node
+                .inputs
+                .iter()
+                .position(|input| input.starts_with('^'))
+                .unwrap_or(node.inputs.len());
+            node.inputs.insert(pos, format!("{}/axis", node.name));
+        }
+    }
+    for gather in &gathers {
+        let axis = &constants[&format!("{}/axis", gather)];
+        graph_def.nodes.push(axis.clone());
+    }
+
+    graph_def.import("freeze")
+}
+
+fn required_attr(node: &NodeDef, name: &str) -> Result<AttrValue> {
+    node.attr(name)?
+        .ok_or_else(|| Error::InvalidGraph(format!("{} has no {} attribute", node.name, name)))
+}
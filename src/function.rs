@@ -0,0 +1,153 @@
+use crate::buffer::OwnedBuffer;
+use crate::{Graph, Operation, Output, Result, Status};
+use std::collections::HashSet;
+use std::ffi::{CStr, CString};
+use std::os::raw::c_void;
+use std::ptr;
+use tf;
+
+/// Thin wrapper over tensorflow function - reusable sub-graph, which can
+/// be copied into graphs, and then called like any other operation, with
+/// function name as operation type.
+pub struct Function(*mut tf::TF_Function);
+
+// Function is immutable once created
+unsafe impl Send for Function {}
+unsafe impl Sync for Function {}
+
+impl Function {
+    /// Creates function from part of `graph` computing `outputs` from
+    /// `inputs`. Function body consists of all operations needed to compute
+    /// outputs, excluding operations producing inputs, which are typically
+    /// placeholders.
+    ///
+    /// ```rust
+    /// # use rustflow::{Function, Graph};
+    /// let proto = include_str!("../tests/data/addition.pb");
+    /// let graph = Graph::from_protobuff(proto).unwrap();
+    /// let x = graph.operation_by_name("x").unwrap().outputs().next().unwrap();
+    /// let y = graph.operation_by_name("y").unwrap().outputs().next().unwrap();
+    /// let z = graph.operation_by_name("z").unwrap().outputs().next().unwrap();
+    ///
+    /// let function = Function::from_graph(&graph, "add", &[x, y], &[z]).unwrap();
+    /// assert_eq!("add", function.name());
+    /// ```
+    pub fn from_graph(
+        graph: &Graph,
+        name: &str,
+        inputs: &[Output],
+        outputs: &[Output],
+    ) -> Result<Self> {
+        let body = Self::body(inputs, outputs);
+        let body: Vec<_> = body
+            .iter()
+            .map(|op| op.operation as *const tf::TF_Operation)
+            .collect();
+        let inputs: Vec<_> = inputs.iter().map(|input| input.output).collect();
+        let outputs: Vec<_> = outputs.iter().map(|output| output.output).collect();
+        let name = CString::new(name)?;
+
+        let mut status = Status::new();
+        let function = unsafe {
+            tf::TF_GraphToFunction(
+                graph.get_ptr(),
+                name.as_ptr(),
+                0,
+                body.len() as i32,
+                body.as_ptr(),
+                inputs.len() as i32,
+                inputs.as_ptr(),
+                outputs.len() as i32,
+                outputs.as_ptr(),
+                ptr::null(),
+                ptr::null(),
+                ptr::null(),
+                status.get(),
+            )
+        };
+        status.to_result()?;
+
+        Ok(Function(function))
+    }
+
+    /// Collects operations needed to compute `outputs`, walking graph back
+    /// from them, and stopping at `inputs` producers
+    fn body<'a>(inputs: &[Output<'a>], outputs: &[Output<'a>]) -> Vec<Operation<'a>> {
+        let mut visited: HashSet<_> = inputs.iter().map(Output::operation).collect();
+        let mut stack: Vec<_> = outputs.iter().map(Output::operation).collect();
+        let mut body = vec![];
+
+        while let Some(op) = stack.pop() {
+            if !visited.insert(op) {
+                continue;
+            }
+
+            stack.extend(op.inputs().map(|input| input.producer().operation()));
+            stack.extend(op.control_inputs());
+            body.push(op);
+        }
+
+        body
+    }
+
+    /// Imports function from serialized `FunctionDef` protobuf
+    ///
+    /// ```rust
+    /// # use rustflow::{Function, Graph};
+    /// # let proto = include_str!("../tests/data/addition.pb");
+    /// # let graph = Graph::from_protobuff(proto).unwrap();
+    /// # let x = graph.operation_by_name("x").unwrap().outputs().next().unwrap();
+    /// # let y = graph.operation_by_name("y").unwrap().outputs().next().unwrap();
+    /// # let z = graph.operation_by_name("z").unwrap().outputs().next().unwrap();
+    /// let function = Function::from_graph(&graph, "add", &[x, y], &[z]).unwrap();
+    /// let proto = function.to_function_def().unwrap();
+    /// let imported = Function::from_function_def(&proto).unwrap();
+    /// assert_eq!("add", imported.name());
+    ///
+    /// Function::from_function_def(b"invalid").map(|_| ()).unwrap_err();
+    /// ```
+    pub fn from_function_def(proto: &[u8]) -> Result<Self> {
+        let mut status = Status::new();
+        let function = unsafe {
+            tf::TF_FunctionImportFunctionDef(
+                proto.as_ptr() as *const c_void,
+                proto.len(),
+                status.get(),
+            )
+        };
+        status.to_result()?;
+
+        Ok(Function(function))
+    }
+
+    /// Serializes function into `FunctionDef` protobuf
+    pub fn to_function_def(&self) -> Result<Vec<u8>> {
+        let mut buffer = OwnedBuffer::new();
+        let mut status = Status::new();
+        unsafe { tf::TF_FunctionToFunctionDef(self.0, buffer.get(), status.get()) };
+        status.to_result()?;
+
+        Ok(buffer.as_bytes().to_vec())
+    }
+
+    /// Returns function name, which is also type of operations calling it
+    pub fn name(&self) -> &str {
+        unsafe { CStr::from_ptr(tf::TF_FunctionName(self.0)).to_str() }.unwrap_or("")
+    }
+
+    /// Function is unsafe, because callee has to ensure, that `function`
+    /// is valid TF_Function object which is not owned by anything else
+    pub(crate) unsafe fn from_ptr(function: *mut tf::TF_Function) -> Self {
+        Function(function)
+    }
+
+    pub(crate) fn get_ptr(&self) -> *const tf::TF_Function {
+        self.0
+    }
+}
+
+impl Drop for Function {
+    fn drop(&mut self) {
+        unsafe { tf::TF_DeleteFunction(self.0) }
+    }
+}
@@ -1,11 +1,35 @@
-use crate::{Buffer, Operation, Result, Status, StrBuffer};
+use crate::buffer::OwnedBuffer;
+use crate::graph_def::{GraphDef, InputRef, NodeDef};
+use crate::op_def::AttrValue;
+use crate::{
+    Buffer, Error, Function, Operation, OperationBuilder, Output, Result, Status, StrBuffer,
+};
+use std::collections::{HashMap, HashSet};
 use std::{ffi, iter, ops};
 use tf;
 
 /// Thin wrapper over tensorflow graph
 pub struct Graph(*mut tf::TF_Graph);
 
+// All tensorflow graph functions are guarded by graph internal mutex,
+// and operations once added to graph are never changed, so graph can
+// be safely shared between threads.
+unsafe impl Send for Graph {}
+unsafe impl Sync for Graph {}
+
 impl Graph {
+    /// Creates new empty graph
+    ///
+    /// ```rust
+    /// # use rustflow::Graph;
+    /// let graph = Graph::new();
+    /// assert_eq!(0, graph.operations().count());
+    /// ```
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new() -> OwnedGraph {
+        OwnedGraph(Self(unsafe { tf::TF_NewGraph() }))
+    }
+
     /// Loads data from tensorflow serialized protobuf string, for example
     /// model builded via Python API
     ///
@@ -19,20 +43,61 @@ impl Graph {
     /// Graph::from_protobuff(proto).map(|_| ()).unwrap_err();
     /// ```
     pub fn from_protobuff(data: &str) -> Result<OwnedGraph> {
-        let buffer = StrBuffer::new(data);
+        Self::import(StrBuffer::new(data).buffer(), "Graph::from_protobuff")
+    }
+
+    /// Loads graph from serialized `GraphDef` protobuf given as bytes
+    ///
+    /// ```rust
+    /// # use rustflow::Graph;
+    /// let proto = include_bytes!("../tests/data/addition.pb");
+    /// let graph = Graph::from_protobuff_bytes(proto).unwrap();
+    /// assert!(graph.operation_by_name("z").is_some());
+    /// ```
+    pub fn from_protobuff_bytes(data: &[u8]) -> Result<OwnedGraph> {
+        Self::import_bytes(data, "Graph::from_protobuff_bytes")
+    }
+
+    /// Imports graph from serialized `GraphDef`, with errors marked as
+    /// raised by `function`
+    pub(crate) fn import_bytes(data: &[u8], function: &'static str) -> Result<OwnedGraph> {
+        Self::import(OwnedBuffer::from_bytes(data).buffer(), function)
+    }
+
+    /// Imports graph, with errors marked as raised by `function`
+    fn import(buffer: &tf::TF_Buffer, function: &'static str) -> Result<OwnedGraph> {
         let graph = unsafe { tf::TF_NewGraph() };
-        let graph = Self(graph);
+        let graph = OwnedGraph(Self(graph));
         let mut status = Status::new();
 
         unsafe {
             let import_options = tf::TF_NewImportGraphDefOptions();
 
-            tf::TF_GraphImportGraphDef(graph.0, buffer.buffer(), import_options, status.get());
+            tf::TF_GraphImportGraphDef(graph.0 .0, buffer, import_options, status.get());
             tf::TF_DeleteImportGraphDefOptions(import_options);
-
-            status.to_result()?;
-            Ok(OwnedGraph(graph))
         }
+
+        status.to_result_in(function)?;
+        Ok(graph)
+    }
+
+    /// Serializes graph into `GraphDef` protobuf
+    ///
+    /// ```rust
+    /// # use rustflow::Graph;
+    /// let proto = include_str!("../tests/data/addition.pb");
+    /// let graph = Graph::from_protobuff(proto).unwrap();
+    /// let serialized = graph.to_protobuff().unwrap();
+    /// let imported = Graph::from_protobuff_bytes(&serialized).unwrap();
+    /// assert_eq!(4, imported.operations().count());
+    /// ```
+    pub fn to_protobuff(&self) -> Result<Vec<u8>> {
+        let mut buffer = OwnedBuffer::new();
+        let mut status = Status::new();
+        unsafe { tf::TF_GraphToGraphDef(self.0, buffer.get(), status.get()) };
+        status.to_result()?;
+
+        Ok(buffer.as_bytes().to_vec())
     }
 
     /// Returns operation with given name in graph
@@ -56,6 +121,381 @@ impl Graph {
         Some(operation)
     }
 
+    /// Returns operation with given name, or `Error::OperationNotFound`
+    pub(crate) fn find_operation(&self, name: &str) -> Result<Operation> {
+        self.operation_by_name(name)
+            .ok_or_else(|| Error::OperationNotFound(name.to_owned()))
+    }
+
+    /// Returns `idx` output of operation with given name, or
+    /// `Error::OperationNotFound`
+    pub(crate) fn find_output(&self, name: &str, idx: usize) -> Result<Output> {
+        self.find_operation(name)?
+            .output(idx)
+            .ok_or_else(|| Error::OperationNotFound(format!("{}:{}", name, idx)))
+    }
+
+    /// Starts building new operation of given type in graph. Operation is
+    /// added to graph when `OperationBuilder::finish` is called.
+    ///
+    /// ```rust
+    /// # use rustflow::{Graph, TensorType};
+    /// let graph = Graph::new();
+    /// let op = graph
+    ///     .new_operation("Placeholder", "x")
+    ///     .unwrap()
+    ///     .set_attr_type("dtype", f32::TF_TYPE)
+    ///     .unwrap()
+    ///     .finish()
+    ///     .unwrap();
+    /// assert_eq!("x", op.name());
+    /// ```
+    pub fn new_operation<'a>(&'a self, op_type: &str, name: &str) -> Result<OperationBuilder<'a>> {
+        let op_type = ffi::CString::new(op_type)?;
+        let name = ffi::CString::new(name)?;
+        unsafe {
+            let description = tf::TF_NewOperation(self.0, op_type.as_ptr(), name.as_ptr());
+            Ok(OperationBuilder::new(description))
+        }
+    }
+
+    /// Copies function into graph, so it can be called by operations of
+    /// type equal to function name. Optional `gradient` is function
+    /// computing gradient of copied one.
+    ///
+    /// ```rust
+    /// # use rustflow::{Function, Graph, TensorType};
+    /// # let proto = include_str!("../tests/data/addition.pb");
+    /// # let graph = Graph::from_protobuff(proto).unwrap();
+    /// # let x = graph.operation_by_name("x").unwrap().outputs().next().unwrap();
+    /// # let y = graph.operation_by_name("y").unwrap().outputs().next().unwrap();
+    /// # let z = graph.operation_by_name("z").unwrap().outputs().next().unwrap();
+    /// let function = Function::from_graph(&graph, "add", &[x, y], &[z]).unwrap();
+    ///
+    /// let other = Graph::new();
+    /// other.copy_function(&function, None).unwrap();
+    /// let a = other
+    ///     .new_operation("Placeholder", "a")
+    ///     .unwrap()
+    ///     .set_attr_type("dtype", i32::TF_TYPE)
+    ///     .unwrap()
+    ///     .finish()
+    ///     .unwrap();
+    /// let a = a.outputs().next().unwrap();
+    /// let call = other
+    ///     .new_operation("add", "call")
+    ///     .unwrap()
+    ///     .add_input(a)
+    ///     .add_input(a)
+    ///     .finish()
+    ///     .unwrap();
+    /// assert_eq!(1, call.outputs().count());
+    /// ```
+    pub fn copy_function(&self, function: &Function, gradient: Option<&Function>) -> Result<()> {
+        let gradient = gradient.map_or(std::ptr::null(), Function::get_ptr);
+        let mut status = Status::new();
+        unsafe { tf::TF_GraphCopyFunction(self.0, function.get_ptr(), gradient, status.get()) };
+        Ok(status.to_result()?)
+    }
+
+    /// Returns copies of all functions registered in graph
+    ///
+    /// ```rust
+    /// # use rustflow::{Function, Graph};
+    /// # let proto = include_str!("../tests/data/addition.pb");
+    /// # let graph = Graph::from_protobuff(proto).unwrap();
+    /// # let x = graph.operation_by_name("x").unwrap().outputs().next().unwrap();
+    /// # let y = graph.operation_by_name("y").unwrap().outputs().next().unwrap();
+    /// # let z = graph.operation_by_name("z").unwrap().outputs().next().unwrap();
+    /// let function = Function::from_graph(&graph, "add", &[x, y], &[z]).unwrap();
+    /// let other = Graph::new();
+    /// other.copy_function(&function, None).unwrap();
+    ///
+    /// let functions = other.functions().unwrap();
+    /// assert_eq!("add", functions[0].name());
+    /// ```
+    pub fn functions(&self) -> Result<Vec<Function>> {
+        let count = unsafe { tf::TF_GraphNumFunctions(self.0) };
+        let mut functions = vec![std::ptr::null_mut(); count as usize];
+        let mut status = Status::new();
+        let count = unsafe {
+            tf::TF_GraphGetFunctions(self.0, functions.as_mut_ptr(), count, status.get())
+        };
+        status.to_result()?;
+
+        Ok(functions
+            .into_iter()
+            .take(count as usize)
+            .map(|function| unsafe { Function::from_ptr(function) })
+            .collect())
+    }
+
+    /// Adds operations computing partial derivatives of sum of `ys` with
+    /// respect to `xs`, returning outputs of computed gradients, one for every
+    /// `x`. `dx` are initial gradients for `ys` - if not given, gradients of
+    /// ones are used.
+    ///
+    /// ```rust
+    /// # use rustflow::Graph;
+    /// let proto = include_str!("../tests/data/addition.pb");
+    /// let graph = Graph::from_protobuff(proto).unwrap();
+    /// let x = graph.operation_by_name("x").unwrap().outputs().next().unwrap();
+    /// let z = graph.operation_by_name("z").unwrap().outputs().next().unwrap();
+    ///
+    /// let grads = graph.add_gradients(&[z], &[x], None).unwrap();
+    /// assert_eq!(1, grads.len());
+    /// ```
+    pub fn add_gradients<'a>(
+        &'a self,
+        ys: &[Output<'a>],
+        xs: &[Output<'a>],
+        dx: Option<&[Output<'a>]>,
+    ) -> Result<Vec<Output<'a>>> {
+        self.gradients(None, ys, xs, dx)
+    }
+
+    /// Adds gradients operations the same way as `Graph::add_gradients`, but
+    /// all added operations are named with given prefix. Prefix has to be
+    /// unique in graph.
+    ///
+    /// ```rust
+    /// # use rustflow::Graph;
+    /// let proto = include_str!("../tests/data/addition.pb");
+    /// let graph = Graph::from_protobuff(proto).unwrap();
+    /// let x = graph.operation_by_name("x").unwrap().outputs().next().unwrap();
+    /// let z = graph.operation_by_name("z").unwrap().outputs().next().unwrap();
+    ///
+    /// let grads = graph.add_gradients_with_prefix("grads", &[z], &[x], None).unwrap();
+    /// assert!(grads[0].operation().name().starts_with("grads/"));
+    /// ```
+    pub fn add_gradients_with_prefix<'a>(
+        &'a self,
+        prefix: &str,
+        ys: &[Output<'a>],
+        xs: &[Output<'a>],
+        dx: Option<&[Output<'a>]>,
+    ) -> Result<Vec<Output<'a>>> {
+        self.gradients(Some(prefix), ys, xs, dx)
+    }
+
+    fn gradients<'a>(
+        &'a self,
+        prefix: Option<&str>,
+        ys: &[Output<'a>],
+        xs: &[Output<'a>],
+        dx: Option<&[Output<'a>]>,
+    ) -> Result<Vec<Output<'a>>> {
+        let prefix = prefix.map(ffi::CString::new).transpose()?;
+        let prefix_ptr = prefix
+            .as_ref()
+            .map_or(std::ptr::null(), |prefix| prefix.as_ptr());
+        let mut ys: Vec<_> = ys.iter().map(|y| y.output).collect();
+        let mut xs: Vec<_> = xs.iter().map(|x| x.output).collect();
+        let mut dx: Option<Vec<_>> = dx.map(|dx| dx.iter().map(|dx| dx.output).collect());
+        let dx_ptr = dx
+            .as_mut()
+            .map_or(std::ptr::null_mut(), |dx| dx.as_mut_ptr());
+        let mut dy = vec![
+            tf::TF_Output {
+                oper: std::ptr::null_mut(),
+                index: 0
+            };
+            xs.len()
+        ];
+
+        let mut status = Status::new();
+        unsafe {
+            tf::TF_AddGradientsWithPrefix(
+                self.0,
+                prefix_ptr,
+                ys.as_mut_ptr(),
+                ys.len() as i32,
+                xs.as_mut_ptr(),
+                xs.len() as i32,
+                dx_ptr,
+                status.get(),
+                dy.as_mut_ptr(),
+            )
+        };
+        status.to_result()?;
+
+        Ok(dy
+            .into_iter()
+            .map(|dy| unsafe { Output::new(dy.oper, dy.index) })
+            .collect())
+    }
+
+    /// Returns statically known shape of tensors produced by given output.
+    /// `None` is returned if even rank is unknown, and unknown dimensions
+    /// are `-1`.
+    ///
+    /// ```rust
+    /// # use rustflow::{Graph, TensorType};
+    /// let graph = Graph::new();
+    /// let x = graph
+    ///     .new_operation("Placeholder", "x")
+    ///     .unwrap()
+    ///     .set_attr_type("dtype", f32::TF_TYPE)
+    ///     .unwrap()
+    ///     .set_attr_shape("shape", Some(&[-1, 3]))
+    ///     .unwrap()
+    ///     .finish()
+    ///     .unwrap();
+    /// let shape = graph.tensor_shape(x.output(0).unwrap()).unwrap();
+    /// assert_eq!(Some(vec![-1, 3]), shape);
+    /// ```
+    pub fn tensor_shape(&self, output: Output) -> Result<Option<Vec<i64>>> {
+        let mut status = Status::new();
+        let num_dims = unsafe { tf::TF_GraphGetTensorNumDims(self.0, output.output, status.get()) };
+        status.to_result()?;
+        if num_dims < 0 {
+            return Ok(None);
+        }
+
+        let mut dims = vec![0; num_dims as usize];
+        unsafe {
+            tf::TF_GraphGetTensorShape(
+                self.0,
+                output.output,
+                dims.as_mut_ptr(),
+                num_dims,
+                status.get(),
+            )
+        };
+        status.to_result()?;
+        Ok(Some(dims))
+    }
+
+    /// Creates new graph containing only operations needed to compute
+    /// `fetches`. Graph is walked back from `fetches` through data and control
+    /// edges, stopping at `feeds`. Operations producing `feeds` are replaced
+    /// with placeholders named after them (outputs other than first get
+    /// `_{index}` suffix, and further `_{n}` suffix if such operation
+    /// already exists), so values can be fed to extracted graph. Feeds not
+    /// needed to compute `fetches` are skipped.
+    ///
+    /// ```rust
+    /// # use rustflow::Graph;
+    /// let proto = include_str!("../tests/data/addition.pb");
+    /// let graph = Graph::from_protobuff(proto).unwrap();
+    /// let z = graph.operation_by_name("z").unwrap().output(0).unwrap();
+    ///
+    /// let extracted = graph.extract_subgraph(&[z], &[]).unwrap();
+    /// assert_eq!(3, extracted.operations().count());
+    /// assert!(extracted.operation_by_name("init").is_none());
+    /// ```
+    pub fn extract_subgraph(&self, fetches: &[Output], feeds: &[Output]) -> Result<OwnedGraph> {
+        let fed: HashSet<_> = feeds.iter().map(Output::operation).collect();
+        let mut needed = HashSet::new();
+        let mut stack: Vec<_> = fetches.iter().map(Output::operation).collect();
+        while let Some(op) = stack.pop() {
+            if fed.contains(&op) || !needed.insert(op.name().to_owned()) {
+                continue;
+            }
+
+            stack.extend(op.inputs().map(|input| input.producer().operation()));
+            stack.extend(op.control_inputs());
+        }
+
+        let feeds: HashMap<_, _> = feeds
+            .iter()
+            .map(|feed| {
+                let input = InputRef::Data {
+                    node: feed.operation().name().to_owned(),
+                    index: feed.output.index,
+                };
+                (input, *feed)
+            })
+            .collect();
+        let fed: HashSet<_> = fed.iter().map(|op| op.name()).collect();
+
+        let mut graph_def = GraphDef::from_graph(self)?;
+        let mut names: HashSet<_> = graph_def
+            .nodes
+            .iter()
+            .map(|node| node.name.clone())
+            .collect();
+        // Placeholders are created only for feeds actually reached from
+        // fetches, in order of first use
+        let mut placeholders: HashMap<InputRef, String> = HashMap::new();
+        let mut placeholder_nodes = vec![];
+        let mut placeholder = |input: &InputRef| -> Result<Option<String>> {
+            let feed = match feeds.get(input) {
+                Some(feed) => feed,
+                None => return Ok(None),
+            };
+            if let Some(name) = placeholders.get(input) {
+                return Ok(Some(name.clone()));
+            }
+
+            let op = feed.operation();
+            let index = feed.output.index;
+            let name = if index == 0 {
+                op.name().to_owned()
+            } else {
+                // Suffixed name may be already taken by other operation
+                let base = format!("{}_{}", op.name(), index);
+                let mut name = base.clone();
+                let mut suffix = 0;
+                while names.contains(&name) {
+                    suffix += 1;
+                    name = format!("{}_{}", base, suffix);
+                }
+                name
+            };
+            names.insert(name.clone());
+
+            let dtype = feed
+                .dtype()
+                .ok_or_else(|| Error::InvalidGraph(format!("feed {} has unknown type", input)))?;
+            let mut node = NodeDef::new(&name, "Placeholder");
+            node.set_attr("dtype", &AttrValue::Type(dtype));
+            if let Some(shape) = self.tensor_shape(*feed)? {
+                node.set_attr("shape", &AttrValue::Shape(Some(shape)));
+            }
+            placeholder_nodes.push(node);
+            placeholders.insert(input.clone(), name.clone());
+            Ok(Some(name))
+        };
+
+        let mut nodes = vec![];
+        for mut node in graph_def.nodes.drain(..) {
+            if !needed.contains(&node.name) {
+                continue;
+            }
+
+            let mut inputs = vec![];
+            for input in &node.inputs {
+                let input = InputRef::parse(input);
+                if !fed.contains(input.node()) {
+                    inputs.push(input.to_string());
+                } else if let Some(name) = placeholder(&input)? {
+                    inputs.push(name);
+                } else if let InputRef::Data { .. } = input {
+                    return Err(Error::InvalidGraph(format!(
+                        "operation {} needs {}, which is not fed",
+                        node.name, input
+                    )));
+                }
+            }
+            node.inputs = inputs;
+            nodes.push(node);
+        }
+
+        // Fed outputs may be fetched directly
+        for fetch in fetches {
+            placeholder(&InputRef::Data {
+                node: fetch.operation().name().to_owned(),
+                index: fetch.output.index,
+            })?;
+        }
+
+        placeholder_nodes.extend(nodes);
+        graph_def.nodes = placeholder_nodes;
+
+        graph_def.import("Graph::extract_subgraph")
+    }
+
     /// Returns iterator over all graph operations
     ///
     /// ```rust
@@ -79,6 +519,13 @@ impl Graph {
         })
     }
 
+    /// Creates graph handle from raw pointer. Graph is not released when
+    /// handle is dropped - it's callee responsibility to ensure graph outlives
+    /// the handle.
+    pub(crate) unsafe fn from_ptr(graph: *mut tf::TF_Graph) -> Self {
+        Self(graph)
+    }
+
     /// Returns internal graph pointer. This is unsafe, because
     /// if callee have to make sure he would not corrupt the
     /// object under pointer.
@@ -0,0 +1,227 @@
+//! Editable representation of serialized `GraphDef`, used by graph
+//! transformations which are easier to express on protobuf level, like
+//! removing or rewiring operations
+
+use crate::graph::OwnedGraph;
+use crate::op_def::AttrValue;
+use crate::wire::{Reader, Writer};
+use crate::{Graph, Result};
+use std::fmt;
+
+/// Reference to node output, as used by `NodeDef` inputs
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum InputRef {
+    /// Data input, `node:index` (or just `node` for first output)
+    Data { node: String, index: i32 },
+    /// Control input, `^node`
+    Control(String),
+}
+
+impl InputRef {
+    /// Parses input as given in `NodeDef`
+    ///
+    /// ```rust
+    /// # use rustflow::transform::InputRef;
+    /// let input = InputRef::parse("split:1");
+    /// assert_eq!(InputRef::Data { node: "split".to_owned(), index: 1 }, input);
+    /// assert_eq!("split:1", input.to_string());
+    /// assert_eq!(InputRef::Control("init".to_owned()), InputRef::parse("^init"));
+    /// ```
+    pub fn parse(input: &str) -> Self {
+        if let Some(node) = input.strip_prefix('^') {
+            return InputRef::Control(node.to_owned());
+        }
+
+        match input.rfind(':') {
+            Some(pos) => match input[pos + 1..].parse() {
+                Ok(index) => InputRef::Data {
+                    node: input[..pos].to_owned(),
+                    index,
+                },
+                Err(_) => InputRef::Data {
+                    node: input.to_owned(),
+                    index: 0,
+                },
+            },
+            None => InputRef::Data {
+                node: input.to_owned(),
+                index: 0,
+            },
+        }
+    }
+
+    /// Returns name of referenced node
+    pub fn node(&self) -> &str {
+        match self {
+            InputRef::Data { node, .. } => node,
+            InputRef::Control(node) => node,
+        }
+    }
+}
+
+impl fmt::Display for InputRef {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InputRef::Data { node, index: 0 } => write!(f, "{}", node),
+            InputRef::Data { node, index } => write!(f, "{}:{}", node, index),
+            InputRef::Control(node) => write!(f, "^{}", node),
+        }
+    }
+}
+
+/// Definition of single graph operation (`NodeDef` protobuf message)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NodeDef {
+    /// Operation name
+    pub name: String,
+    /// Operation type
+    pub op: String,
+    /// Operation inputs, in format parsed by `InputRef`
+    pub inputs: Vec<String>,
+    /// Device operation is placed on, empty if not specified
+    pub device: String,
+    /// Attributes with serialized `AttrValue` values
+    pub(crate) attrs: Vec<(String, Vec<u8>)>,
+    /// Other fields, kept encoded
+    other: Vec<u8>,
+}
+
+impl NodeDef {
+    /// Creates definition of operation of given type, without any inputs
+    /// and attributes
+    pub fn new(name: &str, op: &str) -> Self {
+        NodeDef {
+            name: name.to_owned(),
+            op: op.to_owned(),
+            ..NodeDef::default()
+        }
+    }
+
+    pub(crate) fn decode(data: &[u8]) -> Result<Self> {
+        let mut node = NodeDef::default();
+        let mut other = Writer::new();
+        for field in Reader::new(data) {
+            match field? {
+                (1, value) => node.name = value.string()?,
+                (2, value) => node.op = value.string()?,
+                (3, value) => node.inputs.push(value.string()?),
+                (4, value) => node.device = value.string()?,
+                (5, value) => {
+                    let mut key = String::new();
+                    let mut attr = vec![];
+                    for field in Reader::new(value.bytes()?) {
+                        match field? {
+                            (1, value) => key = value.string()?,
+                            (2, value) => attr = value.bytes()?.to_vec(),
+                            _ => (),
+                        }
+                    }
+                    node.attrs.push((key, attr));
+                }
+                (field, value) => {
+                    other.value(field, value);
+                }
+            }
+        }
+        node.other = other.into_bytes();
+        Ok(node)
+    }
+
+    pub(crate) fn encode(&self) -> Vec<u8> {
+        let mut writer = Writer::new();
+        writer.string(1, &self.name).string(2, &self.op);
+        for input in &self.inputs {
+            writer.string(3, input);
+        }
+        if !self.device.is_empty() {
+            writer.string(4, &self.device);
+        }
+        for (key, value) in &self.attrs {
+            let mut entry = Writer::new();
+            entry.string(1, key).bytes(2, value);
+            writer.bytes(5, &entry.into_bytes());
+        }
+
+        let mut data = writer.into_bytes();
+        data.extend_from_slice(&self.other);
+        data
+    }
+
+    /// Returns names of all attributes set on operation
+    pub fn attr_names(&self) -> impl Iterator<Item = &str> {
+        self.attrs.iter().map(|(key, _)| &key[..])
+    }
+
+    /// Returns value of attribute with given name
+    pub fn attr(&self, name: &str) -> Result<Option<AttrValue>> {
+        self.attrs
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| AttrValue::decode(value))
+            .transpose()
+    }
+
+    /// Sets attribute value
+    pub fn set_attr(&mut self, name: &str, value: &AttrValue) {
+        let value = value.encode();
+        match self.attrs.iter_mut().find(|(key, _)| key == name) {
+            Some((_, attr)) => *attr = value,
+            None => self.attrs.push((name.to_owned(), value)),
+        }
+    }
+
+    /// Removes attribute with given name
+    pub fn remove_attr(&mut self, name: &str) {
+        self.attrs.retain(|(key, _)| key != name);
+    }
+}
+
+/// Single `GraphDef` message. Fields other than nodes, like function
+/// library, are kept encoded.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GraphDef {
+    /// Graph operations
+    pub nodes: Vec<NodeDef>,
+    other: Vec<u8>,
+}
+
+impl GraphDef {
+    /// Serializes graph, and decodes it for edition
+    pub fn from_graph(graph: &Graph) -> Result<Self> {
+        Self::decode(&graph.to_protobuff()?)
+    }
+
+    /// Decodes serialized `GraphDef` protobuf
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        let mut graph_def = GraphDef::default();
+        let mut other = Writer::new();
+        for field in Reader::new(data) {
+            match field? {
+                (1, value) => graph_def.nodes.push(NodeDef::decode(value.bytes()?)?),
+                (field, value) => {
+                    other.value(field, value);
+                }
+            }
+        }
+        graph_def.other = other.into_bytes();
+        Ok(graph_def)
+    }
+
+    /// Encodes definition as `GraphDef` protobuf
+    pub fn encode(&self) -> Vec<u8> {
+        let mut writer = Writer::new();
+        for node in &self.nodes {
+            writer.bytes(1, &node.encode());
+        }
+
+        let mut data = writer.into_bytes();
+        data.extend_from_slice(&self.other);
+        data
+    }
+
+    /// Creates new graph from this definition, with errors marked as raised
+    /// by `function`
+    pub fn import(&self, function: &'static str) -> Result<OwnedGraph> {
+        Graph::import_bytes(&self.encode(), function)
+    }
+}
@@ -1,13 +1,19 @@
+use crate::{Operation, Output};
 use std::marker::PhantomData;
 use tf;
 
 /// Thin wrapper over tensorflow input object. TF_Input keeps TF_Operation
 /// object internally, so artificial lifetime is added.
+#[derive(Clone, Copy)]
 pub struct Input<'a> {
     pub(crate) input: tf::TF_Input,
     _phantom: PhantomData<&'a tf::TF_Operation>,
 }
 
+// Input is just an immutable reference to operation, see `Operation`
+unsafe impl<'a> Send for Input<'a> {}
+unsafe impl<'a> Sync for Input<'a> {}
+
 impl<'a> Input<'a> {
     /// Function is unsafe, because callee has to ensure, that:
     /// 1) operation outlives created Input
@@ -23,4 +29,26 @@ impl<'a> Input<'a> {
             _phantom: PhantomData,
         }
     }
+
+    /// Returns operation this input belongs to
+    pub fn operation(&self) -> Operation<'a> {
+        unsafe { Operation::new(self.input.oper) }
+    }
+
+    /// Returns output producing value for this input
+    ///
+    /// ```rust
+    /// # use rustflow::Graph;
+    /// # let proto = include_str!("../tests/data/addition.pb");
+    /// # let graph = Graph::from_protobuff(proto).unwrap();
+    /// let z = graph.operation_by_name("z").unwrap();
+    /// let producer = z.inputs().next().unwrap().producer();
+    /// assert_eq!("x", producer.operation().name());
+    /// ```
+    pub fn producer(&self) -> Output<'a> {
+        unsafe {
+            let output = tf::TF_OperationInput(self.input);
+            Output::new(output.oper, output.index)
+        }
+    }
 }
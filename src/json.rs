@@ -0,0 +1,184 @@
+//! Converting tensors from and to (possibly nested) JSON arrays, as used by
+//! `rustflow run` command. Available with `json` feature.
+//!
+//! ```rust
+//! # use rustflow::{AnyTensor, TensorType};
+//! let json = serde_json::json!([[1, 2], [3, 4]]);
+//! let tensor = AnyTensor::from_json(&json, i32::TF_TYPE).unwrap();
+//! assert_eq!(&[2, 2], tensor.shape());
+//! assert_eq!(json, tensor.to_json().unwrap());
+//! ```
+
+use crate::tensor_type::data_type_name;
+use crate::{AnyTensor, Error, Result, Tensor, TensorType};
+use serde_json::Value;
+use std::convert::{TryFrom, TryInto};
+use tf;
+
+fn invalid(message: impl Into<String>) -> Error {
+    Error::InvalidJson(message.into())
+}
+
+/// Collects scalar values of nested JSON arrays, checking if all arrays on
+/// the same level have the same length
+fn flatten<'a>(value: &'a Value, shape: &[i64], values: &mut Vec<&'a Value>) -> Result<()> {
+    match (value, shape.split_first()) {
+        (Value::Array(items), Some((&dim, shape))) if items.len() as i64 == dim => {
+            for item in items {
+                flatten(item, shape, values)?;
+            }
+        }
+        (Value::Array(_), _) | (_, Some(_)) => {
+            return Err(invalid("JSON arrays have uneven lengths"))
+        }
+        (value, None) => values.push(value),
+    }
+    Ok(())
+}
+
+fn typed<T, F>(shape: &[i64], values: &[&Value], convert: F) -> Result<AnyTensor>
+where
+    T: TensorType + Copy + 'static,
+    F: Fn(&Value) -> Option<T>,
+{
+    let data = values
+        .iter()
+        .map(|value| {
+            convert(value).ok_or_else(|| {
+                invalid(format!(
+                    "{} is not valid {} value",
+                    value,
+                    data_type_name(T::TF_TYPE)
+                ))
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(Tensor::from_slice(shape, &data)?.into())
+}
+
+fn int<T: TryFrom<i64>>(value: &Value) -> Option<T> {
+    T::try_from(value.as_i64()?).ok()
+}
+
+fn uint<T: TryFrom<u64>>(value: &Value) -> Option<T> {
+    T::try_from(value.as_u64()?).ok()
+}
+
+/// Decodes tensor data of fixed size elements
+fn elements<T, const N: usize>(data: &[u8], decode: fn([u8; N]) -> T) -> Vec<Value>
+where
+    T: Into<Value>,
+{
+    data.chunks_exact(N)
+        .map(|bytes| decode(bytes.try_into().unwrap()).into())
+        .collect()
+}
+
+/// Builds nested JSON arrays of given shape
+fn nest(shape: &[i64], values: &[Value]) -> Value {
+    match shape.split_first() {
+        Some((&dim, shape)) => {
+            let size = shape.iter().product::<i64>() as usize;
+            Value::Array(
+                (0..dim as usize)
+                    .map(|idx| nest(shape, &values[idx * size..(idx + 1) * size]))
+                    .collect(),
+            )
+        }
+        None => values.first().cloned().unwrap_or(Value::Null),
+    }
+}
+
+impl AnyTensor {
+    /// Creates tensor of given type from JSON value. Shape of tensor is
+    /// given by nesting of JSON arrays, which have to be of even lengths,
+    /// and scalar JSON value makes scalar tensor. Values out of range of
+    /// integer types are rejected.
+    ///
+    /// ```rust
+    /// # use rustflow::{AnyTensor, Error, TensorType};
+    /// # use serde_json::json;
+    /// let tensor = AnyTensor::from_json(&json!(["a", "bc"]), tf::TF_STRING).unwrap();
+    /// assert_eq!(vec![b"a".to_vec(), b"bc".to_vec()], tensor.strings().unwrap());
+    ///
+    /// let err = AnyTensor::from_json(&json!([[1], [2, 3]]), i32::TF_TYPE).unwrap_err();
+    /// assert!(matches!(err, Error::InvalidJson(_)));
+    /// ```
+    pub fn from_json(value: &Value, dtype: tf::TF_DataType) -> Result<Self> {
+        let mut shape = vec![];
+        let mut item = value;
+        while let Value::Array(items) = item {
+            shape.push(items.len() as i64);
+            match items.first() {
+                Some(first) => item = first,
+                None => break,
+            }
+        }
+        let mut values = vec![];
+        flatten(value, &shape, &mut values)?;
+
+        match dtype {
+            tf::TF_FLOAT => typed(&shape, &values, |v| v.as_f64().map(|v| v as f32)),
+            tf::TF_DOUBLE => typed(&shape, &values, Value::as_f64),
+            tf::TF_INT8 => typed(&shape, &values, int::<i8>),
+            tf::TF_INT16 => typed(&shape, &values, int::<i16>),
+            tf::TF_INT32 => typed(&shape, &values, int::<i32>),
+            tf::TF_INT64 => typed(&shape, &values, Value::as_i64),
+            tf::TF_UINT8 => typed(&shape, &values, uint::<u8>),
+            tf::TF_UINT16 => typed(&shape, &values, uint::<u16>),
+            tf::TF_UINT32 => typed(&shape, &values, uint::<u32>),
+            tf::TF_UINT64 => typed(&shape, &values, Value::as_u64),
+            tf::TF_BOOL => {
+                let data = values
+                    .iter()
+                    .map(|value| {
+                        value
+                            .as_bool()
+                            .map(u8::from)
+                            .ok_or_else(|| invalid(format!("{} is not valid bool value", value)))
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                AnyTensor::from_bytes(dtype, &shape, &data)
+            }
+            tf::TF_STRING => {
+                let data = values
+                    .iter()
+                    .map(|value| {
+                        value
+                            .as_str()
+                            .ok_or_else(|| invalid(format!("{} is not valid string value", value)))
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                AnyTensor::from_strings(&shape, &data)
+            }
+            dtype => Err(Error::UnsupportedType(dtype)),
+        }
+    }
+
+    /// Converts tensor to JSON value, nesting arrays as tensor shape does.
+    /// Strings which are not valid UTF-8 have invalid sequences replaced
+    /// with replacement character.
+    pub fn to_json(&self) -> Result<Value> {
+        let data = self.as_bytes();
+        let values = match self.dtype() {
+            tf::TF_FLOAT => elements(data, f32::from_ne_bytes),
+            tf::TF_DOUBLE => elements(data, f64::from_ne_bytes),
+            tf::TF_INT8 => elements(data, i8::from_ne_bytes),
+            tf::TF_INT16 => elements(data, i16::from_ne_bytes),
+            tf::TF_INT32 => elements(data, i32::from_ne_bytes),
+            tf::TF_INT64 => elements(data, i64::from_ne_bytes),
+            tf::TF_UINT8 => elements(data, u8::from_ne_bytes),
+            tf::TF_UINT16 => elements(data, u16::from_ne_bytes),
+            tf::TF_UINT32 => elements(data, u32::from_ne_bytes),
+            tf::TF_UINT64 => elements(data, u64::from_ne_bytes),
+            tf::TF_BOOL => data.iter().map(|value| Value::Bool(*value != 0)).collect(),
+            tf::TF_STRING => self
+                .strings()?
+                .iter()
+                .map(|value| Value::String(String::from_utf8_lossy(value).into_owned()))
+                .collect(),
+            dtype => return Err(Error::UnsupportedType(dtype)),
+        };
+        Ok(nest(self.shape(), &values))
+    }
+}
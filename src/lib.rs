@@ -1,26 +1,57 @@
 use std;
 use tf;
 
+pub mod batcher;
+pub mod benchmark;
 pub mod buffer;
+pub mod checkpoint;
+mod control_flow;
+mod crc;
+pub mod device;
+mod dot;
+pub mod eager;
 pub mod error;
+pub mod freeze;
+pub mod function;
 pub mod graph;
+mod graph_def;
 pub mod input;
+#[cfg(feature = "json")]
+pub mod json;
+pub mod library;
+pub mod npy;
+pub mod op_def;
 pub mod operation;
 pub mod output;
+#[cfg(feature = "proto")]
+pub mod proto;
+#[cfg(feature = "async")]
+pub mod run_pool;
+#[cfg(feature = "safetensors")]
+pub mod safetensors;
 pub mod session;
+pub mod session_pool;
+mod sstable;
 pub mod tensor;
+mod tensor_proto;
 pub mod tensor_type;
+pub mod train;
+pub mod transform;
+mod wire;
 
 pub(crate) use error::Status;
 pub use error::{Error, TFError};
 
 use buffer::{Buffer, StrBuffer};
+pub use device::DeviceInfo;
+pub use function::Function;
 pub use graph::Graph;
 pub use input::Input;
-pub use operation::Operation;
+pub use library::Library;
+pub use operation::{Operation, OperationBuilder};
 pub use output::Output;
 pub use session::Session;
-pub use tensor::Tensor;
+pub use tensor::{AnyTensor, Tensor};
 pub use tensor_type::TensorType;
 
 type Result<T> = std::result::Result<T, Error>;
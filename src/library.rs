@@ -0,0 +1,56 @@
+//! Loading libraries with custom operations and kernels
+
+use crate::buffer::buffer_data;
+use crate::op_def::{decode_op_list, OpDef};
+use crate::{Result, Status};
+use std::ffi::CString;
+use tf;
+
+/// Dynamically loaded library registering custom operations. Operations
+/// are registered globally, so they can be used in any graph after library
+/// is loaded. Pluggable device libraries are not supported, as they require
+/// tensorflow 2.5, newer than the one this crate binds.
+pub struct Library {
+    handle: *mut tf::TF_Library,
+    ops: Vec<OpDef>,
+}
+
+// Library handle is never modified after load
+unsafe impl Send for Library {}
+unsafe impl Sync for Library {}
+
+impl Library {
+    /// Loads library from shared object under given path, registering
+    /// operations and kernels defined in it
+    ///
+    /// ```rust
+    /// # use rustflow::library::Library;
+    /// Library::load("not_existing.so").map(|_| ()).unwrap_err();
+    /// ```
+    pub fn load(path: &str) -> Result<Self> {
+        let path = CString::new(path)?;
+        let mut status = Status::new();
+        let handle = unsafe { tf::TF_LoadLibrary(path.as_ptr(), status.get()) };
+        status.to_result()?;
+
+        let mut library = Library {
+            handle,
+            ops: vec![],
+        };
+        let buffer = unsafe { tf::TF_GetOpList(library.handle) };
+        library.ops = decode_op_list(unsafe { buffer_data(&buffer) })?;
+
+        Ok(library)
+    }
+
+    /// Returns definitions of operations registered by library
+    pub fn ops(&self) -> &[OpDef] {
+        &self.ops
+    }
+}
+
+impl Drop for Library {
+    fn drop(&mut self) {
+        unsafe { tf::TF_DeleteLibraryHandle(self.handle) }
+    }
+}
@@ -0,0 +1,440 @@
+//! Reading and writing tensors in NumPy `.npy` format, and `.npz` archives
+//! of them
+//!
+//! ```rust
+//! # use rustflow::{AnyTensor, Tensor};
+//! let tensor = Tensor::from_slice(&[2, 2], &[1.0f32, 2.0, 3.0, 4.0]).unwrap();
+//! let mut npy = vec![];
+//! tensor.write_npy(&mut npy).unwrap();
+//!
+//! let read = Tensor::<f32>::read_npy(&npy[..]).unwrap();
+//! assert_eq!(&[2, 2], read.shape());
+//! assert_eq!(&[1.0, 2.0, 3.0, 4.0], &read[..]);
+//! ```
+
+use crate::crc::crc32;
+use crate::{AnyTensor, Error, Result, Tensor, TensorType};
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::io::{Read, Write};
+use tf;
+
+const MAGIC: &[u8] = b"\x93NUMPY";
+
+/// Data types with their NumPy type codes (without byte order)
+const DATA_TYPES: &[(tf::TF_DataType, &str)] = &[
+    (tf::TF_FLOAT, "f4"),
+    (tf::TF_DOUBLE, "f8"),
+    (tf::TF_HALF, "f2"),
+    (tf::TF_INT8, "i1"),
+    (tf::TF_INT16, "i2"),
+    (tf::TF_INT32, "i4"),
+    (tf::TF_INT64, "i8"),
+    (tf::TF_UINT8, "u1"),
+    (tf::TF_UINT16, "u2"),
+    (tf::TF_UINT32, "u4"),
+    (tf::TF_UINT64, "u8"),
+    (tf::TF_BOOL, "b1"),
+    (tf::TF_COMPLEX64, "c8"),
+    (tf::TF_COMPLEX128, "c16"),
+];
+
+const NATIVE_ORDER: char = if cfg!(target_endian = "little") {
+    '<'
+} else {
+    '>'
+};
+
+fn invalid(message: impl Into<String>) -> Error {
+    Error::InvalidNpy(message.into())
+}
+
+/// Returns value of given key in header dictionary, like
+/// `{'descr': '<f4', 'fortran_order': False, 'shape': (2, 3), }`
+fn header_value<'a>(header: &'a str, key: &str) -> Result<&'a str> {
+    let pattern = format!("'{}':", key);
+    let start = header
+        .find(&pattern)
+        .ok_or_else(|| invalid(format!("header has no {} key", key)))?
+        + pattern.len();
+    let value = header[start..].trim_start();
+    let end = if value.starts_with('(') {
+        value.find(')').map(|end| end + 1)
+    } else {
+        value.find(&[',', '}'][..])
+    };
+    Ok(value[..end.unwrap_or(value.len())].trim())
+}
+
+/// Parses type description, like `<f4`, returning data type and whether
+/// its byte order is native
+fn parse_descr(descr: &str) -> Result<(tf::TF_DataType, bool)> {
+    let unsupported = || invalid(format!("unsupported type {}", descr));
+    let mut chars = descr.chars();
+    let order = chars.next().ok_or_else(unsupported)?;
+    let code = chars.as_str();
+    let dtype = DATA_TYPES
+        .iter()
+        .find(|(_, known)| *known == code)
+        .map(|(dtype, _)| *dtype)
+        .ok_or_else(unsupported)?;
+    match order {
+        '|' | '=' => Ok((dtype, true)),
+        '<' | '>' => Ok((dtype, order == NATIVE_ORDER)),
+        _ => Err(unsupported()),
+    }
+}
+
+/// Converts array in column-major order to row-major one
+fn transpose(data: &[u8], shape: &[i64], elem_size: usize) -> Vec<u8> {
+    let shape: Vec<_> = shape.iter().map(|dim| *dim as usize).collect();
+    let mut strides = vec![1; shape.len()];
+    for axis in 1..shape.len() {
+        strides[axis] = strides[axis - 1] * shape[axis - 1];
+    }
+
+    let mut result = vec![0; data.len()];
+    for (idx, elem) in result.chunks_mut(elem_size).enumerate() {
+        // Offset of element in column-major order, given its row-major index
+        let mut rest = idx;
+        let mut offset = 0;
+        for (dim, stride) in shape.iter().zip(&strides).rev() {
+            offset += (rest % dim) * stride;
+            rest /= dim;
+        }
+        elem.copy_from_slice(&data[offset * elem_size..(offset + 1) * elem_size]);
+    }
+    result
+}
+
+impl AnyTensor {
+    /// Reads tensor from `.npy` data. Tensor type is taken from data, so it
+    /// has to be one of numeric types supported by tensorflow. Arrays in
+    /// Fortran order are transposed to tensorflow row-major order.
+    ///
+    /// ```rust
+    /// # use rustflow::{AnyTensor, TensorType};
+    /// let mut npy = b"\x93NUMPY\x01\x00\x46\x00".to_vec();
+    /// npy.extend_from_slice(b"{'descr': '<i2', 'fortran_order': True, 'shape': (2, 2), }");
+    /// npy.resize(10 + 0x46 - 1, b' ');
+    /// npy.push(b'\n');
+    /// npy.extend_from_slice(&[1, 0, 2, 0, 3, 0, 4, 0]);
+    ///
+    /// let tensor = AnyTensor::read_npy(&npy[..]).unwrap();
+    /// assert_eq!(i16::TF_TYPE, tensor.dtype());
+    /// let tensor = tensor.downcast::<i16>().unwrap();
+    /// assert_eq!(&[1, 3, 2, 4], &tensor[..]);
+    /// ```
+    pub fn read_npy(mut reader: impl Read) -> Result<Self> {
+        let mut preamble = [0; 8];
+        reader.read_exact(&mut preamble)?;
+        if &preamble[..6] != MAGIC {
+            return Err(invalid("invalid magic string"));
+        }
+        let header_len = match preamble[6] {
+            1 => {
+                let mut len = [0; 2];
+                reader.read_exact(&mut len)?;
+                u16::from_le_bytes(len) as usize
+            }
+            2 | 3 => {
+                let mut len = [0; 4];
+                reader.read_exact(&mut len)?;
+                u32::from_le_bytes(len) as usize
+            }
+            version => return Err(invalid(format!("unsupported version {}", version))),
+        };
+        let mut header = vec![0; header_len];
+        reader.read_exact(&mut header)?;
+        let header = String::from_utf8(header).map_err(|_| invalid("header is not UTF-8"))?;
+
+        let (dtype, native) = parse_descr(header_value(&header, "descr")?.trim_matches('\''))?;
+        let fortran_order = match header_value(&header, "fortran_order")? {
+            "False" => false,
+            "True" => true,
+            value => return Err(invalid(format!("invalid fortran_order {}", value))),
+        };
+        let shape = header_value(&header, "shape")?
+            .trim_matches(|c| c == '(' || c == ')')
+            .split(',')
+            .map(str::trim)
+            .filter(|dim| !dim.is_empty())
+            .map(|dim| {
+                dim.parse()
+                    .map_err(|_| invalid(format!("invalid dimension {}", dim)))
+            })
+            .collect::<Result<Vec<i64>>>()?;
+
+        let mut data = vec![];
+        reader.read_to_end(&mut data)?;
+        let elem_size = elem_size(dtype);
+        if !native {
+            // Complex numbers are pairs of floats, swapped separately
+            let part_size = match dtype {
+                tf::TF_COMPLEX64 | tf::TF_COMPLEX128 => elem_size / 2,
+                _ => elem_size,
+            };
+            for part in data.chunks_mut(part_size) {
+                part.reverse();
+            }
+        }
+        let len = shape.iter().product::<i64>() as usize;
+        if fortran_order && shape.len() > 1 && data.len() == len * elem_size {
+            data = transpose(&data, &shape, elem_size);
+        }
+
+        AnyTensor::from_bytes(dtype, &shape, &data)
+    }
+
+    /// Writes tensor in `.npy` format, in row-major order. Only numeric
+    /// tensors can be written.
+    pub fn write_npy(&self, mut writer: impl Write) -> Result<()> {
+        let dtype = self.dtype();
+        let code = DATA_TYPES
+            .iter()
+            .find(|(known, _)| *known == dtype)
+            .map(|(_, code)| code)
+            .ok_or(Error::UnsupportedType(dtype))?;
+        let order = if elem_size(dtype) == 1 {
+            '|'
+        } else {
+            NATIVE_ORDER
+        };
+        let shape = match self.shape() {
+            [dim] => format!("({},)", dim),
+            shape => {
+                let dims: Vec<_> = shape.iter().map(i64::to_string).collect();
+                format!("({})", dims.join(", "))
+            }
+        };
+        let mut header = format!(
+            "{{'descr': '{}{}', 'fortran_order': False, 'shape': {}, }}",
+            order, code, shape
+        );
+
+        // Header is padded, so data is aligned to 64 bytes
+        let preamble_len = if header.len() + 11 > u16::MAX as usize {
+            12
+        } else {
+            10
+        };
+        let padding = 63 - (preamble_len + header.len()) % 64;
+        header.push_str(&" ".repeat(padding));
+        header.push('\n');
+
+        writer.write_all(MAGIC)?;
+        if preamble_len == 10 {
+            writer.write_all(&[1, 0])?;
+            writer.write_all(&(header.len() as u16).to_le_bytes())?;
+        } else {
+            writer.write_all(&[2, 0])?;
+            writer.write_all(&(header.len() as u32).to_le_bytes())?;
+        }
+        writer.write_all(header.as_bytes())?;
+        writer.write_all(self.as_bytes())?;
+        Ok(())
+    }
+}
+
+fn elem_size(dtype: tf::TF_DataType) -> usize {
+    unsafe { tf::TF_DataTypeSize(dtype) }
+}
+
+impl<T: TensorType> Tensor<T> {
+    /// Reads tensor from `.npy` data, as `AnyTensor::read_npy` does. If
+    /// data is not of type `T`, `Error::TypeMismatch` is returned.
+    pub fn read_npy(reader: impl Read) -> Result<Self> {
+        AnyTensor::read_npy(reader)?
+            .downcast()
+            .map_err(|tensor| Error::TypeMismatch {
+                expected: T::TF_TYPE,
+                found: tensor.dtype(),
+            })
+    }
+}
+
+impl<T> Tensor<T> {
+    /// Writes tensor in `.npy` format, as `AnyTensor::write_npy` does
+    pub fn write_npy(&self, writer: impl Write) -> Result<()> {
+        self.as_ref().write_npy(writer)
+    }
+}
+
+const LOCAL_HEADER: u32 = 0x0403_4b50;
+const CENTRAL_HEADER: u32 = 0x0201_4b50;
+const END_OF_DIRECTORY: u32 = 0x0605_4b50;
+
+fn u16_at(data: &[u8], offset: usize) -> Result<u16> {
+    data.get(offset..offset + 2)
+        .map(|bytes| u16::from_le_bytes(bytes.try_into().unwrap()))
+        .ok_or_else(|| invalid("truncated npz archive"))
+}
+
+fn u32_at(data: &[u8], offset: usize) -> Result<u32> {
+    data.get(offset..offset + 4)
+        .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()))
+        .ok_or_else(|| invalid("truncated npz archive"))
+}
+
+/// Reads entry sizes and local header offset from zip64 extra field, for
+/// fields which are saturated in central directory
+fn zip64_values(extra: &[u8], values: &mut [u64]) -> Result<()> {
+    let mut pos = 0;
+    while pos + 4 <= extra.len() {
+        let id = u16_at(extra, pos)?;
+        let len = u16_at(extra, pos + 2)? as usize;
+        if id == 1 {
+            let mut field = pos + 4;
+            for value in values.iter_mut().filter(|value| **value == 0xffff_ffff) {
+                let bytes = extra
+                    .get(field..field + 8)
+                    .ok_or_else(|| invalid("truncated zip64 field"))?;
+                *value = u64::from_le_bytes(bytes.try_into().unwrap());
+                field += 8;
+            }
+            return Ok(());
+        }
+        pos += 4 + len;
+    }
+    Ok(())
+}
+
+/// Reads all arrays from `.npz` archive, as created by `numpy.savez`. Keys
+/// are array names, without `.npy` extension. Only uncompressed archives
+/// are supported.
+///
+/// ```rust
+/// # use rustflow::{AnyTensor, Tensor};
+/// # use rustflow::npy::{read_npz, write_npz};
+/// let x: AnyTensor = Tensor::from_slice(&[2], &[1i32, 2]).unwrap().into();
+/// let y: AnyTensor = Tensor::from_slice(&[], &[0.5f64]).unwrap().into();
+/// let mut npz = vec![];
+/// write_npz(&mut npz, &[("x", &x), ("y", &y)]).unwrap();
+///
+/// let arrays = read_npz(&npz[..]).unwrap();
+/// assert_eq!(2, arrays.len());
+/// assert_eq!(x.as_bytes(), arrays["x"].as_bytes());
+/// ```
+pub fn read_npz(mut reader: impl Read) -> Result<HashMap<String, AnyTensor>> {
+    let mut data = vec![];
+    reader.read_to_end(&mut data)?;
+
+    // End of central directory record is at the end of archive, followed
+    // only by comment of at most 64KiB
+    let min_end = data.len().saturating_sub(22 + 0xffff);
+    let end = (min_end..data.len().saturating_sub(21))
+        .rev()
+        .find(|pos| u32_at(&data, *pos).ok() == Some(END_OF_DIRECTORY))
+        .ok_or_else(|| invalid("npz archive has no central directory"))?;
+    let entries = u16_at(&data, end + 10)?;
+    let mut pos = u32_at(&data, end + 16)? as usize;
+
+    let mut arrays = HashMap::new();
+    for _ in 0..entries {
+        if u32_at(&data, pos)? != CENTRAL_HEADER {
+            return Err(invalid("invalid central directory entry"));
+        }
+        let compression = u16_at(&data, pos + 10)?;
+        let crc = u32_at(&data, pos + 16)?;
+        let mut values = [
+            u64::from(u32_at(&data, pos + 24)?),
+            u64::from(u32_at(&data, pos + 20)?),
+            u64::from(u32_at(&data, pos + 42)?),
+        ];
+        let name_len = u16_at(&data, pos + 28)? as usize;
+        let extra_len = u16_at(&data, pos + 30)? as usize;
+        let comment_len = u16_at(&data, pos + 32)? as usize;
+        let name = data
+            .get(pos + 46..pos + 46 + name_len)
+            .ok_or_else(|| invalid("truncated npz archive"))?;
+        let name = String::from_utf8_lossy(name).into_owned();
+        let extra = data
+            .get(pos + 46 + name_len..pos + 46 + name_len + extra_len)
+            .ok_or_else(|| invalid("truncated npz archive"))?;
+        zip64_values(extra, &mut values)?;
+        pos += 46 + name_len + extra_len + comment_len;
+
+        if compression != 0 {
+            return Err(invalid(format!(
+                "{} is compressed, only uncompressed archives are supported",
+                name
+            )));
+        }
+        let [size, _, offset] = values;
+        let offset = offset as usize;
+        if u32_at(&data, offset)? != LOCAL_HEADER {
+            return Err(invalid(format!("invalid local header of {}", name)));
+        }
+        let start = offset
+            + 30
+            + u16_at(&data, offset + 26)? as usize
+            + u16_at(&data, offset + 28)? as usize;
+        let content = data
+            .get(start..start + size as usize)
+            .ok_or_else(|| invalid(format!("{} is truncated", name)))?;
+        if crc32(content) != crc {
+            return Err(invalid(format!("checksum mismatch of {}", name)));
+        }
+
+        let name = name.strip_suffix(".npy").unwrap_or(&name).to_owned();
+        arrays.insert(name, AnyTensor::read_npy(content)?);
+    }
+    Ok(arrays)
+}
+
+/// Writes tensors as uncompressed `.npz` archive, which can be read with
+/// `numpy.load`
+pub fn write_npz(mut writer: impl Write, tensors: &[(&str, &AnyTensor)]) -> Result<()> {
+    let mut offset = 0usize;
+    let mut directory = vec![];
+    for (name, tensor) in tensors {
+        let mut content = vec![];
+        tensor.write_npy(&mut content)?;
+        let name = format!("{}.npy", name);
+        let crc = crc32(&content);
+        if content.len() > u32::MAX as usize {
+            return Err(invalid(format!("{} is too large for npz archive", name)));
+        }
+
+        // Fields common for local header and central directory entry:
+        // version needed, flags, compression, time, date, crc, sizes, name
+        // and extra field lengths
+        let mut common = vec![];
+        common.extend_from_slice(&20u16.to_le_bytes());
+        common.extend_from_slice(&[0; 8]);
+        common.extend_from_slice(&crc.to_le_bytes());
+        common.extend_from_slice(&(content.len() as u32).to_le_bytes());
+        common.extend_from_slice(&(content.len() as u32).to_le_bytes());
+        common.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        common.extend_from_slice(&[0; 2]);
+
+        writer.write_all(&LOCAL_HEADER.to_le_bytes())?;
+        writer.write_all(&common)?;
+        writer.write_all(name.as_bytes())?;
+        writer.write_all(&content)?;
+
+        // Version made by, common fields, comment length, disk number,
+        // attributes and local header offset
+        directory.extend_from_slice(&CENTRAL_HEADER.to_le_bytes());
+        directory.extend_from_slice(&20u16.to_le_bytes());
+        directory.extend_from_slice(&common);
+        directory.extend_from_slice(&[0; 10]);
+        directory.extend_from_slice(&(offset as u32).to_le_bytes());
+        directory.extend_from_slice(name.as_bytes());
+
+        offset += 30 + name.len() + content.len();
+        if offset > u32::MAX as usize {
+            return Err(invalid("archive is too large"));
+        }
+    }
+
+    writer.write_all(&directory)?;
+    writer.write_all(&END_OF_DIRECTORY.to_le_bytes())?;
+    writer.write_all(&[0; 4])?;
+    writer.write_all(&(tensors.len() as u16).to_le_bytes())?;
+    writer.write_all(&(tensors.len() as u16).to_le_bytes())?;
+    writer.write_all(&(directory.len() as u32).to_le_bytes())?;
+    writer.write_all(&(offset as u32).to_le_bytes())?;
+    writer.write_all(&[0; 2])?;
+    Ok(())
+}
@@ -0,0 +1,436 @@
+//! Definitions of operations registered in tensorflow, decoded from
+//! `OpDef` protobuf messages
+
+use crate::buffer::buffer_data;
+use crate::tensor_type::data_type_from_raw;
+use crate::wire::{Reader, Writer};
+use crate::{Error, Graph, Result};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use tf;
+
+/// Value of operation attribute
+#[derive(Debug, Clone, PartialEq)]
+pub enum AttrValue {
+    String(Vec<u8>),
+    Int(i64),
+    Float(f32),
+    Bool(bool),
+    Type(tf::TF_DataType),
+    /// Shape dimensions, `None` if rank is unknown. Unknown dimensions
+    /// are `-1`.
+    Shape(Option<Vec<i64>>),
+    /// Serialized `TensorProto`
+    Tensor(Vec<u8>),
+    /// Name of function
+    Func(String),
+    /// Name of attribute of enclosing function, which value should be used
+    Placeholder(String),
+    List(Vec<AttrValue>),
+}
+
+impl AttrValue {
+    /// Decodes value from serialized `AttrValue` protobuf
+    pub(crate) fn decode(data: &[u8]) -> Result<Self> {
+        let mut result = AttrValue::List(vec![]);
+        for field in Reader::new(data) {
+            result = match field? {
+                (1, value) => AttrValue::List(Self::decode_list(value.bytes()?)?),
+                (2, value) => AttrValue::String(value.bytes()?.to_vec()),
+                (3, value) => AttrValue::Int(value.varint()? as i64),
+                (4, value) => AttrValue::Float(f32::from_bits(value.fixed32()?)),
+                (5, value) => AttrValue::Bool(value.varint()? != 0),
+                (6, value) => AttrValue::Type(decode_type(value.varint()?)?),
+                (7, value) => AttrValue::Shape(decode_shape(value.bytes()?)?),
+                (8, value) => AttrValue::Tensor(value.bytes()?.to_vec()),
+                (9, value) => AttrValue::Placeholder(value.string()?),
+                (10, value) => AttrValue::Func(decode_func_name(value.bytes()?)?),
+                _ => continue,
+            };
+        }
+        Ok(result)
+    }
+
+    /// Encodes value as `AttrValue` protobuf
+    pub(crate) fn encode(&self) -> Vec<u8> {
+        let mut writer = Writer::new();
+        match self {
+            AttrValue::List(values) => {
+                let mut list = Writer::new();
+                for value in values {
+                    value.encode_item(&mut list, true);
+                }
+                writer.bytes(1, &list.into_bytes());
+            }
+            AttrValue::Placeholder(name) => {
+                writer.string(9, name);
+            }
+            value => value.encode_item(&mut writer, false),
+        };
+        writer.into_bytes()
+    }
+
+    /// Encodes single value, either directly in `AttrValue`, or as element
+    /// of its `ListValue`. Field numbers are the same in both, except for
+    /// functions.
+    fn encode_item(&self, writer: &mut Writer, in_list: bool) {
+        match self {
+            AttrValue::String(value) => writer.bytes(2, value),
+            AttrValue::Int(value) => writer.varint(3, *value as u64),
+            AttrValue::Float(value) => writer.fixed32(4, value.to_bits()),
+            AttrValue::Bool(value) => writer.varint(5, *value as u64),
+            AttrValue::Type(dtype) => writer.varint(6, *dtype as u64),
+            AttrValue::Shape(shape) => writer.bytes(7, &encode_shape(shape.as_deref())),
+            AttrValue::Tensor(tensor) => writer.bytes(8, tensor),
+            AttrValue::Func(name) => {
+                let mut func = Writer::new();
+                func.string(1, name);
+                writer.bytes(if in_list { 9 } else { 10 }, &func.into_bytes())
+            }
+            // Nested lists and placeholders are not valid list elements
+            AttrValue::Placeholder(_) | AttrValue::List(_) => writer,
+        };
+    }
+
+    fn decode_list(data: &[u8]) -> Result<Vec<Self>> {
+        let mut list = vec![];
+        for field in Reader::new(data) {
+            match field? {
+                (2, value) => list.push(AttrValue::String(value.bytes()?.to_vec())),
+                (3, value) => list.extend(
+                    value
+                        .packed_varints()?
+                        .into_iter()
+                        .map(|value| AttrValue::Int(value as i64)),
+                ),
+                (4, value) => list.extend(
+                    value
+                        .packed_fixed32()?
+                        .into_iter()
+                        .map(|value| AttrValue::Float(f32::from_bits(value))),
+                ),
+                (5, value) => list.extend(
+                    value
+                        .packed_varints()?
+                        .into_iter()
+                        .map(|value| AttrValue::Bool(value != 0)),
+                ),
+                (6, value) => {
+                    for value in value.packed_varints()? {
+                        list.push(AttrValue::Type(decode_type(value)?));
+                    }
+                }
+                (7, value) => list.push(AttrValue::Shape(decode_shape(value.bytes()?)?)),
+                (8, value) => list.push(AttrValue::Tensor(value.bytes()?.to_vec())),
+                (9, value) => list.push(AttrValue::Func(decode_func_name(value.bytes()?)?)),
+                _ => (),
+            }
+        }
+        Ok(list)
+    }
+}
+
+fn decode_type(value: u64) -> Result<tf::TF_DataType> {
+    data_type_from_raw(value as i32)
+        .ok_or_else(|| Error::InvalidProtobuf(format!("unknown data type {}", value)))
+}
+
+/// Decodes `TensorShapeProto`
+pub(crate) fn decode_shape(data: &[u8]) -> Result<Option<Vec<i64>>> {
+    let mut dims = vec![];
+    for field in Reader::new(data) {
+        match field? {
+            (2, value) => {
+                let mut size = 0;
+                for field in Reader::new(value.bytes()?) {
+                    if let (1, value) = field? {
+                        size = value.varint()? as i64;
+                    }
+                }
+                dims.push(size);
+            }
+            (3, value) if value.varint()? != 0 => return Ok(None),
+            _ => (),
+        }
+    }
+    Ok(Some(dims))
+}
+
+/// Encodes `TensorShapeProto`, `None` meaning unknown rank
+pub(crate) fn encode_shape(shape: Option<&[i64]>) -> Vec<u8> {
+    let mut writer = Writer::new();
+    match shape {
+        Some(shape) => {
+            for size in shape {
+                let mut dim = Writer::new();
+                dim.varint(1, *size as u64);
+                writer.bytes(2, &dim.into_bytes());
+            }
+        }
+        None => {
+            writer.varint(3, 1);
+        }
+    }
+    writer.into_bytes()
+}
+
+/// Decodes name from `NameAttrList`
+fn decode_func_name(data: &[u8]) -> Result<String> {
+    for field in Reader::new(data) {
+        if let (1, value) = field? {
+            return value.string();
+        }
+    }
+    Ok(String::new())
+}
+
+/// Definition of operation input or output
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ArgDef {
+    /// Argument name
+    pub name: String,
+    /// Human readable argument description
+    pub description: String,
+    /// Argument data type, if it's fixed by definition
+    pub dtype: Option<tf::TF_DataType>,
+    /// Name of attribute giving argument data type, if it's not fixed
+    pub type_attr: String,
+    /// Name of attribute giving number of tensors, if argument is
+    /// a sequence of tensors of the same type
+    pub number_attr: String,
+    /// Name of attribute giving list of types, if argument is
+    /// a sequence of tensors of different types
+    pub type_list_attr: String,
+    /// True if argument is reference, like input of `Assign`
+    pub is_ref: bool,
+}
+
+impl ArgDef {
+    fn decode(data: &[u8]) -> Result<Self> {
+        let mut arg = ArgDef::default();
+        for field in Reader::new(data) {
+            match field? {
+                (1, value) => arg.name = value.string()?,
+                (2, value) => arg.description = value.string()?,
+                (3, value) => arg.dtype = data_type_from_raw(value.varint()? as i32),
+                (4, value) => arg.type_attr = value.string()?,
+                (5, value) => arg.number_attr = value.string()?,
+                (6, value) => arg.type_list_attr = value.string()?,
+                (16, value) => arg.is_ref = value.varint()? != 0,
+                _ => (),
+            }
+        }
+        Ok(arg)
+    }
+}
+
+/// Definition of operation attribute
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct AttrDef {
+    /// Attribute name
+    pub name: String,
+    /// Attribute type, like `int`, `type` or `list(shape)`
+    pub attr_type: String,
+    /// Human readable attribute description
+    pub description: String,
+    /// Value used if attribute is not set explicitly
+    pub default_value: Option<AttrValue>,
+    /// Minimal value of `int` attribute, or minimal length of list attribute
+    pub minimum: Option<i64>,
+    /// List of values attribute can be set to, if restricted
+    pub allowed_values: Option<AttrValue>,
+}
+
+impl AttrDef {
+    fn decode(data: &[u8]) -> Result<Self> {
+        let mut attr = AttrDef::default();
+        let mut has_minimum = false;
+        let mut minimum = 0;
+        for field in Reader::new(data) {
+            match field? {
+                (1, value) => attr.name = value.string()?,
+                (2, value) => attr.attr_type = value.string()?,
+                (3, value) => attr.default_value = Some(AttrValue::decode(value.bytes()?)?),
+                (4, value) => attr.description = value.string()?,
+                (5, value) => has_minimum = value.varint()? != 0,
+                (6, value) => minimum = value.varint()? as i64,
+                (7, value) => attr.allowed_values = Some(AttrValue::decode(value.bytes()?)?),
+                _ => (),
+            }
+        }
+        if has_minimum {
+            attr.minimum = Some(minimum);
+        }
+        Ok(attr)
+    }
+}
+
+/// Definition of operation type
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct OpDef {
+    /// Operation type name, like `Add`
+    pub name: String,
+    /// Operation inputs
+    pub input_args: Vec<ArgDef>,
+    /// Operation outputs
+    pub output_args: Vec<ArgDef>,
+    /// Operation attributes
+    pub attrs: Vec<AttrDef>,
+    /// One line operation description
+    pub summary: String,
+    /// Full operation description
+    pub description: String,
+    /// True if operation has side effects, or its results are not
+    /// determined by its inputs
+    pub is_stateful: bool,
+}
+
+impl OpDef {
+    /// Decodes definition from serialized `OpDef` protobuf
+    pub(crate) fn decode(data: &[u8]) -> Result<Self> {
+        let mut op = OpDef::default();
+        for field in Reader::new(data) {
+            match field? {
+                (1, value) => op.name = value.string()?,
+                (2, value) => op.input_args.push(ArgDef::decode(value.bytes()?)?),
+                (3, value) => op.output_args.push(ArgDef::decode(value.bytes()?)?),
+                (4, value) => op.attrs.push(AttrDef::decode(value.bytes()?)?),
+                (5, value) => op.summary = value.string()?,
+                (6, value) => op.description = value.string()?,
+                (17, value) => op.is_stateful = value.varint()? != 0,
+                _ => (),
+            }
+        }
+        Ok(op)
+    }
+
+    /// Returns attribute definition with given name
+    pub fn attr(&self, name: &str) -> Option<&AttrDef> {
+        self.attrs.iter().find(|attr| attr.name == name)
+    }
+}
+
+/// Decodes serialized `OpList` protobuf
+pub(crate) fn decode_op_list(data: &[u8]) -> Result<Vec<OpDef>> {
+    Reader::new(data)
+        .filter_map(|field| match field {
+            Ok((1, value)) => Some(value.bytes().and_then(OpDef::decode)),
+            Ok(_) => None,
+            Err(err) => Some(Err(err)),
+        })
+        .collect()
+}
+
+/// Snapshot of all operations registered in tensorflow, including ones
+/// registered by loaded libraries
+pub struct OpRegistry {
+    ops: HashMap<String, OpDef>,
+}
+
+/// Returns registry of currently registered operations
+///
+/// ```rust
+/// # use rustflow::op_def::ops_registry;
+/// let registry = ops_registry().unwrap();
+/// let add = registry.get("Add").unwrap();
+/// assert_eq!(2, add.input_args.len());
+/// assert!(registry.get("NotExistingOp").is_none());
+/// ```
+pub fn ops_registry() -> Result<OpRegistry> {
+    let buffer = unsafe { tf::TF_GetAllOpList() };
+    let ops = decode_op_list(unsafe { buffer_data(&*buffer) });
+    unsafe { tf::TF_DeleteBuffer(buffer) };
+
+    Ok(OpRegistry {
+        ops: ops?.into_iter().map(|op| (op.name.clone(), op)).collect(),
+    })
+}
+
+impl OpRegistry {
+    /// Returns definition of operation of given type
+    pub fn get(&self, op_type: &str) -> Option<&OpDef> {
+        self.ops.get(op_type)
+    }
+
+    /// Returns iterator over all registered operations definitions
+    pub fn ops(&self) -> impl Iterator<Item = &OpDef> {
+        self.ops.values()
+    }
+
+    /// Checks if all operations in graph are registered operations or calls
+    /// of graph functions, returning `Error::UnknownOps` listing types of
+    /// unknown ones otherwise
+    ///
+    /// ```rust
+    /// # use rustflow::Graph;
+    /// # use rustflow::op_def::ops_registry;
+    /// let proto = include_str!("../tests/data/addition.pb");
+    /// let graph = Graph::from_protobuff(proto).unwrap();
+    /// ops_registry().unwrap().check_graph(&graph).unwrap();
+    /// ```
+    pub fn check_graph(&self, graph: &Graph) -> Result<()> {
+        let functions: HashSet<_> = graph
+            .functions()?
+            .iter()
+            .map(|function| function.name().to_owned())
+            .collect();
+        let unknown = graph
+            .operations()
+            .map(|op| op.op_type().to_owned())
+            .filter(|op_type| !self.ops.contains_key(op_type) && !functions.contains(op_type));
+        Self::unknown_ops(unknown)
+    }
+
+    /// Checks operations in serialized `GraphDef` the same way as
+    /// `OpRegistry::check_graph`, so unknown operations can be reported
+    /// before graph is imported
+    pub fn check_graph_def(&self, graph_def: &[u8]) -> Result<()> {
+        let mut op_types = vec![];
+        let mut functions = HashSet::new();
+        for field in Reader::new(graph_def) {
+            match field? {
+                (1, node) => op_types.push(decode_node_op(node.bytes()?)?),
+                (2, library) => functions.extend(decode_function_names(library.bytes()?)?),
+                _ => (),
+            }
+        }
+
+        let unknown = op_types
+            .into_iter()
+            .filter(|op_type| !self.ops.contains_key(op_type) && !functions.contains(op_type));
+        Self::unknown_ops(unknown)
+    }
+
+    fn unknown_ops(op_types: impl Iterator<Item = String>) -> Result<()> {
+        let unknown: BTreeSet<_> = op_types.collect();
+        if unknown.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::UnknownOps(unknown.into_iter().collect()))
+        }
+    }
+}
+
+/// Decodes operation type from `NodeDef`
+fn decode_node_op(data: &[u8]) -> Result<String> {
+    for field in Reader::new(data) {
+        if let (2, value) = field? {
+            return value.string();
+        }
+    }
+    Ok(String::new())
+}
+
+/// Decodes names of functions defined in `FunctionDefLibrary`
+fn decode_function_names(data: &[u8]) -> Result<Vec<String>> {
+    let mut names = vec![];
+    for field in Reader::new(data) {
+        if let (1, function) = field? {
+            for field in Reader::new(function.bytes()?) {
+                if let (1, signature) = field? {
+                    names.push(OpDef::decode(signature.bytes()?)?.name);
+                }
+            }
+        }
+    }
+    Ok(names)
+}
@@ -1,16 +1,22 @@
-use crate::{Input, Output};
-use std::marker::PhantomData;
+use crate::{AnyTensor, Input, Output, Result, Status};
+use std::{ffi::CString, hash, marker::PhantomData, os::raw::c_void, ptr};
 use tf;
 
 /// Thin wrapper over tensroflow operation pointer. TF_Operation
 /// objects are not managed by its own, instead they are managed by
 /// their partentss, so thats its why addtitional artificial lifetime
 /// is added
+#[derive(Clone, Copy)]
 pub struct Operation<'a> {
     pub(crate) operation: *mut tf::TF_Operation,
     _phantom: PhantomData<&'a tf::TF_Operation>,
 }
 
+// Operations are immutable once created, and they live as long as their
+// graph, which is `Sync`
+unsafe impl<'a> Send for Operation<'a> {}
+unsafe impl<'a> Sync for Operation<'a> {}
+
 impl<'a> Operation<'a> {
     /// This is unsafe, because its calee who has to ensure, that
     /// operation is valid TF_Operation object which outlives 'a
@@ -50,7 +56,16 @@ impl<'a> Operation<'a> {
     /// # let proto = include_str!("../tests/data/addition.pb");
     /// # let graph = Graph::from_protobuff(proto).unwrap();
     /// let op = graph.operation_by_name("x").unwrap();
-    /// op.op_type();
+    /// assert_eq!("", op.device());
+    ///
+    /// let op = graph
+    ///     .new_operation("NoOp", "placed")
+    ///     .unwrap()
+    ///     .set_device("/device:CPU:0")
+    ///     .unwrap()
+    ///     .finish()
+    ///     .unwrap();
+    /// assert_eq!("/device:CPU:0", op.device());
     /// ```
     pub fn device(&self) -> &str {
         unsafe { std::ffi::CStr::from_ptr(tf::TF_OperationDevice(self.operation)).to_str() }
@@ -71,6 +86,25 @@ impl<'a> Operation<'a> {
             .map(move |idx| unsafe { Output::new(op, idx) })
     }
 
+    /// Returns output with given index
+    ///
+    /// ```rust
+    /// # use rustflow::Graph;
+    /// # let proto = include_str!("../tests/data/addition.pb");
+    /// # let graph = Graph::from_protobuff(proto).unwrap();
+    /// let op = graph.operation_by_name("x").unwrap();
+    /// assert!(op.output(0).is_some());
+    /// assert!(op.output(1).is_none());
+    /// ```
+    pub fn output(&self, idx: usize) -> Option<Output<'a>> {
+        let cnt = unsafe { tf::TF_OperationNumOutputs(self.operation) };
+        if idx >= cnt as usize {
+            return None;
+        }
+
+        Some(unsafe { Output::new(self.operation, idx as i32) })
+    }
+
     /// ```rust
     /// # use rustflow::Graph;
     /// # let proto = include_str!("../tests/data/addition.pb");
@@ -84,4 +118,231 @@ impl<'a> Operation<'a> {
         (0..cnt)
             .map(move |idx| unsafe { Input::new(op, idx) })
     }
+
+    /// Returns operations which have to be executed before this one
+    ///
+    /// ```rust
+    /// # use rustflow::Graph;
+    /// # let proto = include_str!("../tests/data/addition.pb");
+    /// # let graph = Graph::from_protobuff(proto).unwrap();
+    /// let op = graph.operation_by_name("z").unwrap();
+    /// assert!(op.control_inputs().is_empty());
+    /// ```
+    pub fn control_inputs(&self) -> Vec<Operation<'a>> {
+        unsafe {
+            let cnt = tf::TF_OperationNumControlInputs(self.operation);
+            let mut inputs = vec![ptr::null_mut(); cnt as usize];
+            let cnt = tf::TF_OperationGetControlInputs(self.operation, inputs.as_mut_ptr(), cnt);
+            inputs.truncate(cnt as usize);
+            inputs.into_iter().map(|op| Operation::new(op)).collect()
+        }
+    }
+}
+
+// Operations are identified by their tensorflow object
+impl<'a> PartialEq for Operation<'a> {
+    fn eq(&self, other: &Self) -> bool {
+        self.operation == other.operation
+    }
+}
+
+impl<'a> Eq for Operation<'a> {}
+
+impl<'a> hash::Hash for Operation<'a> {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.operation.hash(state)
+    }
+}
+
+/// Thin wrapper over tensorflow operation description, used to add new
+/// operations to graph. Description is bound to graph it is created for,
+/// so artificial lifetime is added.
+///
+/// Tensorflow doesn't provide any way to release operation description
+/// other than finishing it, so builder dropped without calling `finish`
+/// leaks its description.
+pub struct OperationBuilder<'a> {
+    description: *mut tf::TF_OperationDescription,
+    _phantom: PhantomData<&'a tf::TF_Graph>,
+}
+
+impl<'a> OperationBuilder<'a> {
+    /// This is unsafe, because its calee who has to ensure, that
+    /// description is valid, unfinished TF_OperationDescription object
+    /// created for graph which outlives 'a
+    pub(crate) unsafe fn new(description: *mut tf::TF_OperationDescription) -> Self {
+        OperationBuilder {
+            description,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Adds operation input
+    pub fn add_input(self, input: Output<'a>) -> Self {
+        unsafe { tf::TF_AddInput(self.description, input.output) };
+        self
+    }
+
+    /// Adds list of outputs as single operation input, for operations
+    /// taking variable number of inputs, like `AddN`
+    pub fn add_input_list(self, inputs: &[Output<'a>]) -> Self {
+        let inputs: Vec<_> = inputs.iter().map(|input| input.output).collect();
+        unsafe { tf::TF_AddInputList(self.description, inputs.as_ptr(), inputs.len() as i32) };
+        self
+    }
+
+    /// Adds operation which has to be executed before this one
+    pub fn add_control_input(self, operation: Operation<'a>) -> Self {
+        unsafe { tf::TF_AddControlInput(self.description, operation.operation) };
+        self
+    }
+
+    /// Sets device operation should be placed on, like `/device:CPU:0`
+    pub fn set_device(self, device: &str) -> Result<Self> {
+        let device = CString::new(device)?;
+        unsafe { tf::TF_SetDevice(self.description, device.as_ptr()) };
+        Ok(self)
+    }
+
+    /// Sets string attribute value
+    pub fn set_attr_string(self, name: &str, value: &str) -> Result<Self> {
+        let name = CString::new(name)?;
+        unsafe {
+            tf::TF_SetAttrString(
+                self.description,
+                name.as_ptr(),
+                value.as_ptr() as *const c_void,
+                value.len(),
+            )
+        };
+        Ok(self)
+    }
+
+    /// Sets integer attribute value
+    pub fn set_attr_int(self, name: &str, value: i64) -> Result<Self> {
+        let name = CString::new(name)?;
+        unsafe { tf::TF_SetAttrInt(self.description, name.as_ptr(), value) };
+        Ok(self)
+    }
+
+    /// Sets integer list attribute value
+    pub fn set_attr_int_list(self, name: &str, values: &[i64]) -> Result<Self> {
+        let name = CString::new(name)?;
+        unsafe {
+            tf::TF_SetAttrIntList(
+                self.description,
+                name.as_ptr(),
+                values.as_ptr(),
+                values.len() as i32,
+            )
+        };
+        Ok(self)
+    }
+
+    /// Sets float attribute value
+    pub fn set_attr_float(self, name: &str, value: f32) -> Result<Self> {
+        let name = CString::new(name)?;
+        unsafe { tf::TF_SetAttrFloat(self.description, name.as_ptr(), value) };
+        Ok(self)
+    }
+
+    /// Sets boolean attribute value
+    pub fn set_attr_bool(self, name: &str, value: bool) -> Result<Self> {
+        let name = CString::new(name)?;
+        unsafe { tf::TF_SetAttrBool(self.description, name.as_ptr(), value as u8) };
+        Ok(self)
+    }
+
+    /// Sets data type attribute value
+    pub fn set_attr_type(self, name: &str, value: tf::TF_DataType) -> Result<Self> {
+        let name = CString::new(name)?;
+        unsafe { tf::TF_SetAttrType(self.description, name.as_ptr(), value) };
+        Ok(self)
+    }
+
+    /// Sets data type list attribute value
+    pub fn set_attr_type_list(self, name: &str, values: &[tf::TF_DataType]) -> Result<Self> {
+        let name = CString::new(name)?;
+        unsafe {
+            tf::TF_SetAttrTypeList(
+                self.description,
+                name.as_ptr(),
+                values.as_ptr(),
+                values.len() as i32,
+            )
+        };
+        Ok(self)
+    }
+
+    /// Sets shape attribute value. Unknown dimensions should be set to `-1`,
+    /// and `None` means shape of unknown rank.
+    pub fn set_attr_shape(self, name: &str, shape: Option<&[i64]>) -> Result<Self> {
+        let name = CString::new(name)?;
+        let (dims, num_dims) = match shape {
+            Some(shape) => (shape.as_ptr(), shape.len() as i32),
+            None => (ptr::null(), -1),
+        };
+        unsafe { tf::TF_SetAttrShape(self.description, name.as_ptr(), dims, num_dims) };
+        Ok(self)
+    }
+
+    /// Sets function attribute value, like `f` attribute of `PartitionedCall`.
+    /// Function has to be registered in graph.
+    pub fn set_attr_func_name(self, name: &str, function: &str) -> Result<Self> {
+        let name = CString::new(name)?;
+        unsafe {
+            tf::TF_SetAttrFuncName(
+                self.description,
+                name.as_ptr(),
+                function.as_ptr() as *const _,
+                function.len(),
+            )
+        };
+        Ok(self)
+    }
+
+    /// Sets tensor attribute value, like value of `Const` operation.
+    /// Tensor is copied into operation.
+    pub fn set_attr_tensor(self, name: &str, value: &AnyTensor) -> Result<Self> {
+        let name = CString::new(name)?;
+        let mut status = Status::new();
+        unsafe {
+            tf::TF_SetAttrTensor(self.description, name.as_ptr(), value.tensor, status.get())
+        };
+        status.to_result()?;
+        Ok(self)
+    }
+
+    /// Adds operation to graph
+    ///
+    /// ```rust
+    /// # use rustflow::{Graph, Tensor, TensorType};
+    /// let graph = Graph::new();
+    /// let value = Tensor::from_slice(&[2], &[1.0f32, 2.0]).unwrap();
+    /// let c = graph
+    ///     .new_operation("Const", "c")
+    ///     .unwrap()
+    ///     .set_attr_tensor("value", value.as_ref())
+    ///     .unwrap()
+    ///     .set_attr_type("dtype", f32::TF_TYPE)
+    ///     .unwrap()
+    ///     .finish()
+    ///     .unwrap();
+    /// let neg = graph
+    ///     .new_operation("Neg", "neg")
+    ///     .unwrap()
+    ///     .add_input(c.outputs().next().unwrap())
+    ///     .finish()
+    ///     .unwrap();
+    /// assert_eq!(1, neg.inputs().count());
+    ///
+    /// graph.new_operation("Neg", "invalid").unwrap().finish().map(|_| ()).unwrap_err();
+    /// ```
+    pub fn finish(self) -> Result<Operation<'a>> {
+        let mut status = Status::new();
+        let operation = unsafe { tf::TF_FinishOperation(self.description, status.get()) };
+        status.to_result()?;
+
+        Ok(unsafe { Operation::new(operation) })
+    }
 }
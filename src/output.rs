@@ -1,13 +1,28 @@
+use crate::tensor_type::data_type_from_raw;
+use crate::Operation;
 use std::marker::PhantomData;
+use std::os::raw::c_int;
 use tf;
 
+extern "C" {
+    // Output type is declared as raw value, as it may be reference type,
+    // which is not valid `TF_DataType` value
+    #[link_name = "TF_OperationOutputType"]
+    fn operation_output_type(oper_out: tf::TF_Output) -> c_int;
+}
+
 /// Thin wrapper over tensorflow output object. TF_Output keeps TF_Operation
 /// object internally, so artificial lifetime is added.
+#[derive(Clone, Copy)]
 pub struct Output<'a> {
     pub(crate) output: tf::TF_Output,
     _phantom: PhantomData<&'a tf::TF_Operation>,
 }
 
+// Output is just an immutable reference to operation, see `Operation`
+unsafe impl<'a> Send for Output<'a> {}
+unsafe impl<'a> Sync for Output<'a> {}
+
 impl<'a> Output<'a> {
     /// Function is unsafe, because callee has to ensure, that:
     /// 1) operation outlives created Output
@@ -23,4 +38,36 @@ impl<'a> Output<'a> {
             _phantom: PhantomData,
         }
     }
+
+    /// Returns data type of tensors produced by this output. For reference
+    /// outputs (like variables) type of referenced value is returned.
+    ///
+    /// ```rust
+    /// # use rustflow::{Graph, TensorType};
+    /// # let proto = include_str!("../tests/data/addition.pb");
+    /// # let graph = Graph::from_protobuff(proto).unwrap();
+    /// let x = graph.operation_by_name("x").unwrap().outputs().next().unwrap();
+    /// assert_eq!(Some(i32::TF_TYPE), x.dtype());
+    /// ```
+    pub fn dtype(&self) -> Option<tf::TF_DataType> {
+        data_type_from_raw(unsafe { operation_output_type(self.output) })
+    }
+
+    /// Returns index of this output among operation outputs
+    ///
+    /// ```rust
+    /// # use rustflow::Graph;
+    /// # let proto = include_str!("../tests/data/addition.pb");
+    /// # let graph = Graph::from_protobuff(proto).unwrap();
+    /// let z = graph.operation_by_name("z").unwrap();
+    /// assert_eq!(0, z.output(0).unwrap().index());
+    /// ```
+    pub fn index(&self) -> usize {
+        self.output.index as usize
+    }
+
+    /// Returns operation this output belongs to
+    pub fn operation(&self) -> Operation<'a> {
+        unsafe { Operation::new(self.output.oper) }
+    }
 }
@@ -0,0 +1,114 @@
+//! Protobuf messages used by tensorflow, generated from `.proto` files
+//! vendored in `proto` directory, and their conversions to library types.
+//! Available with `proto` feature.
+
+use crate::graph::OwnedGraph;
+use crate::session::SessionBuilder;
+use crate::{AnyTensor, Error, Graph, Result};
+use prost::Message;
+use std::convert::TryFrom;
+
+include!(concat!(env!("OUT_DIR"), "/tensorflow.rs"));
+
+impl From<prost::DecodeError> for Error {
+    fn from(err: prost::DecodeError) -> Self {
+        Error::InvalidProtobuf(err.to_string())
+    }
+}
+
+impl Graph {
+    /// Converts graph into `GraphDef` message
+    ///
+    /// ```rust
+    /// # use rustflow::Graph;
+    /// let proto = include_str!("../tests/data/addition.pb");
+    /// let graph = Graph::from_protobuff(proto).unwrap();
+    /// let graph_def = graph.to_graph_def().unwrap();
+    /// assert!(graph_def.node.iter().any(|node| node.name == "z"));
+    /// ```
+    pub fn to_graph_def(&self) -> Result<GraphDef> {
+        Ok(GraphDef::decode(&self.to_protobuff()?[..])?)
+    }
+
+    /// Loads graph from `GraphDef` message
+    ///
+    /// ```rust
+    /// # use rustflow::Graph;
+    /// # use rustflow::proto::GraphDef;
+    /// Graph::from_graph_def(&GraphDef::default()).unwrap();
+    /// ```
+    pub fn from_graph_def(graph_def: &GraphDef) -> Result<OwnedGraph> {
+        Graph::import_bytes(&graph_def.encode_to_vec(), "Graph::from_graph_def")
+    }
+}
+
+impl<'a> SessionBuilder<'a> {
+    /// Sets session configuration from `ConfigProto` message
+    ///
+    /// ```rust
+    /// # use rustflow::Graph;
+    /// # use rustflow::proto::ConfigProto;
+    /// # use rustflow::session::SessionBuilder;
+    /// # let proto = include_str!("../tests/data/addition.pb");
+    /// # let graph = Graph::from_protobuff(proto).unwrap();
+    /// let config = ConfigProto {
+    ///     intra_op_parallelism_threads: 2,
+    ///     ..ConfigProto::default()
+    /// };
+    /// let session = SessionBuilder::with_graph(&graph)
+    ///     .unwrap()
+    ///     .set_config_proto(&config)
+    ///     .unwrap()
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    pub fn set_config_proto(self, config: &ConfigProto) -> Result<Self> {
+        self.set_config(&config.encode_to_vec())
+    }
+}
+
+impl From<&[i64]> for TensorShapeProto {
+    fn from(shape: &[i64]) -> Self {
+        TensorShapeProto {
+            dim: shape
+                .iter()
+                .map(|size| tensor_shape_proto::Dim {
+                    size: *size,
+                    name: String::new(),
+                })
+                .collect(),
+            unknown_rank: false,
+        }
+    }
+}
+
+impl TryFrom<&AnyTensor> for TensorProto {
+    type Error = Error;
+
+    /// Converts tensor into message, with numeric values stored in
+    /// `tensor_content`, and strings in `string_val`
+    fn try_from(tensor: &AnyTensor) -> Result<Self> {
+        let mut proto = TensorProto {
+            dtype: tensor.dtype() as i32,
+            tensor_shape: Some(tensor.shape().into()),
+            ..TensorProto::default()
+        };
+        if tensor.dtype() == tf::TF_STRING {
+            proto.string_val = tensor.strings()?;
+        } else {
+            proto.tensor_content = tensor.as_bytes().to_vec();
+        }
+        Ok(proto)
+    }
+}
+
+impl TryFrom<&TensorProto> for AnyTensor {
+    type Error = Error;
+
+    /// Converts message into tensor. Values are read the same way as values
+    /// of `Const` operations, so they may be stored either in
+    /// `tensor_content` or in typed field, like `float_val`.
+    fn try_from(proto: &TensorProto) -> Result<Self> {
+        AnyTensor::from_tensor_proto(&proto.encode_to_vec())
+    }
+}
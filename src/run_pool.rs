@@ -0,0 +1,228 @@
+use crate::{AnyTensor, Error, Result};
+use std::collections::VecDeque;
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::Pin;
+use std::sync::{Arc, Condvar, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::thread::{self, JoinHandle};
+
+type RunFn = Box<dyn FnOnce() -> Result<Vec<AnyTensor>> + Send>;
+
+/// Dedicated, bounded thread pool for running sessions without blocking
+/// async executor threads. It limits both number of threads performing
+/// blocking runs, and number of runs in flight (queued or running) - futures
+/// exceeding this limit are waiting until some of in flight runs is finished,
+/// so callers are backpressured instead of growing pool queue unboundedly.
+///
+/// Dropping future cancels its run only if it is not started yet. Runs
+/// already passed to tensorflow are not interrupted, as its C API provides
+/// no way of cancelling them - they finish (keeping their in flight slots),
+/// and their results are discarded. Runs which must not take too long
+/// should bound their time within graph itself.
+pub struct RunPool {
+    shared: Arc<PoolShared>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+struct PoolShared {
+    state: Mutex<PoolState>,
+    job_ready: Condvar,
+    max_in_flight: usize,
+}
+
+struct PoolState {
+    jobs: VecDeque<Job>,
+    in_flight: usize,
+    // Futures waiting for free in flight slot
+    waiting: Vec<Waker>,
+    shutdown: bool,
+}
+
+struct Job {
+    run: RunFn,
+    slot: Arc<Mutex<Slot>>,
+}
+
+/// Place for run result shared between future and pool thread
+#[derive(Default)]
+struct Slot {
+    result: Option<Result<Vec<AnyTensor>>>,
+    waker: Option<Waker>,
+    cancelled: bool,
+}
+
+impl RunPool {
+    /// Creates pool with `threads` worker threads, allowing at most
+    /// `max_in_flight` runs to be queued or running at once
+    ///
+    /// ```rust
+    /// # use rustflow::run_pool::RunPool;
+    /// let pool = RunPool::new(4, 16);
+    /// ```
+    pub fn new(threads: usize, max_in_flight: usize) -> Self {
+        let shared = Arc::new(PoolShared {
+            state: Mutex::new(PoolState {
+                jobs: VecDeque::new(),
+                in_flight: 0,
+                waiting: vec![],
+                shutdown: false,
+            }),
+            job_ready: Condvar::new(),
+            max_in_flight: max_in_flight.max(1),
+        });
+
+        let workers = (0..threads.max(1))
+            .map(|_| {
+                let shared = shared.clone();
+                thread::spawn(move || shared.work())
+            })
+            .collect();
+
+        RunPool { shared, workers }
+    }
+
+    /// Creates future performing given run on pool thread. Run is not
+    /// queued until future is polled for the first time.
+    pub(crate) fn spawn(
+        &self,
+        run: impl FnOnce() -> Result<Vec<AnyTensor>> + Send + 'static,
+    ) -> RunFuture {
+        RunFuture {
+            pool: self.shared.clone(),
+            run: Some(Box::new(run)),
+            slot: Arc::new(Mutex::new(Slot::default())),
+        }
+    }
+
+    /// Creates future resolving to given result without running anything
+    pub(crate) fn ready(&self, result: Result<Vec<AnyTensor>>) -> RunFuture {
+        let slot = Slot {
+            result: Some(result),
+            ..Slot::default()
+        };
+        RunFuture {
+            pool: self.shared.clone(),
+            run: None,
+            slot: Arc::new(Mutex::new(slot)),
+        }
+    }
+}
+
+impl Drop for RunPool {
+    fn drop(&mut self) {
+        self.shared.state.lock().unwrap().shutdown = true;
+        self.shared.job_ready.notify_all();
+
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+
+        // Futures still waiting for slot have to be notified, that pool
+        // is gone
+        let waiting = std::mem::take(&mut self.shared.state.lock().unwrap().waiting);
+        waiting.into_iter().for_each(Waker::wake);
+    }
+}
+
+impl PoolShared {
+    fn work(&self) {
+        loop {
+            let job = {
+                let mut state = self.state.lock().unwrap();
+                loop {
+                    if let Some(job) = state.jobs.pop_front() {
+                        break job;
+                    }
+                    if state.shutdown {
+                        return;
+                    }
+                    state = self.job_ready.wait(state).unwrap();
+                }
+            };
+
+            // Slot is released even if run panics
+            let _in_flight = InFlight(self);
+            let cancelled = job.slot.lock().unwrap().cancelled;
+            if !cancelled {
+                // Panicking run must not hang its future, nor take worker
+                // thread down
+                let result = panic::catch_unwind(AssertUnwindSafe(job.run))
+                    .unwrap_or(Err(Error::RunPanicked));
+                let mut slot = job.slot.lock().unwrap();
+                slot.result = Some(result);
+                if let Some(waker) = slot.waker.take() {
+                    waker.wake();
+                }
+            }
+        }
+    }
+}
+
+/// Guard releasing in flight slot taken by job, when job is finished
+struct InFlight<'a>(&'a PoolShared);
+
+impl<'a> Drop for InFlight<'a> {
+    fn drop(&mut self) {
+        let waiting = {
+            let mut state = self.0.state.lock().unwrap();
+            state.in_flight -= 1;
+            std::mem::take(&mut state.waiting)
+        };
+        // All waiting futures are woken, as some of them may be
+        // already dropped, and they would never pass the slot further
+        waiting.into_iter().for_each(Waker::wake);
+    }
+}
+
+/// Future resolving to session run result, returned by
+/// `Session::run_async`
+pub struct RunFuture {
+    pool: Arc<PoolShared>,
+    // Run not yet submitted to pool
+    run: Option<RunFn>,
+    slot: Arc<Mutex<Slot>>,
+}
+
+impl Future for RunFuture {
+    type Output = Result<Vec<AnyTensor>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let this = self.get_mut();
+
+        if this.run.is_some() {
+            let mut state = this.pool.state.lock().unwrap();
+            if state.shutdown {
+                return Poll::Ready(Err(Error::PoolShutdown));
+            }
+
+            if state.in_flight >= this.pool.max_in_flight {
+                state.waiting.push(cx.waker().clone());
+                return Poll::Pending;
+            }
+
+            state.in_flight += 1;
+            let job = Job {
+                run: this.run.take().unwrap(),
+                slot: this.slot.clone(),
+            };
+            state.jobs.push_back(job);
+            this.pool.job_ready.notify_one();
+        }
+
+        let mut slot = this.slot.lock().unwrap();
+        match slot.result.take() {
+            Some(result) => Poll::Ready(result),
+            None => {
+                slot.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl Drop for RunFuture {
+    fn drop(&mut self) {
+        self.slot.lock().unwrap().cancelled = true;
+    }
+}
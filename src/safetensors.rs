@@ -0,0 +1,243 @@
+//! Reading and writing named tensors in
+//! [safetensors](https://github.com/huggingface/safetensors) format.
+//! Available with `safetensors` feature.
+//!
+//! ```rust
+//! # use rustflow::safetensors::{read_safetensors, write_safetensors};
+//! # use rustflow::{AnyTensor, Tensor};
+//! # use std::fs::File;
+//! let weights: AnyTensor = Tensor::from_slice(&[2], &[1.0f32, 2.0]).unwrap().into();
+//! let path = std::env::temp_dir().join("rustflow_doc.safetensors");
+//! write_safetensors(File::create(&path).unwrap(), &[("weights", &weights)]).unwrap();
+//!
+//! let tensors = read_safetensors(&path).unwrap();
+//! assert_eq!(weights.as_bytes(), tensors["weights"].as_bytes());
+//! ```
+
+use crate::{AnyTensor, Error, Result, TensorType};
+use memmap2::{MmapMut, MmapOptions};
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Write;
+use std::os::raw::c_void;
+use std::path::Path;
+use std::sync::Arc;
+use tf;
+
+/// Data types with their safetensors names
+const DATA_TYPES: &[(tf::TF_DataType, &str)] = &[
+    (tf::TF_BOOL, "BOOL"),
+    (u8::TF_TYPE, "U8"),
+    (i8::TF_TYPE, "I8"),
+    (i16::TF_TYPE, "I16"),
+    (u16::TF_TYPE, "U16"),
+    (tf::TF_HALF, "F16"),
+    (tf::TF_BFLOAT16, "BF16"),
+    (i32::TF_TYPE, "I32"),
+    (u32::TF_TYPE, "U32"),
+    (f32::TF_TYPE, "F32"),
+    (f64::TF_TYPE, "F64"),
+    (i64::TF_TYPE, "I64"),
+    (u64::TF_TYPE, "U64"),
+    (tf::TF_COMPLEX64, "C64"),
+];
+
+/// Alignment of data, which tensorflow can use without copying
+const ALIGNMENT: usize = 64;
+
+const METADATA_KEY: &str = "__metadata__";
+
+fn invalid(message: impl Into<String>) -> Error {
+    Error::InvalidSafetensors(message.into())
+}
+
+fn elem_size(dtype: tf::TF_DataType) -> usize {
+    unsafe { tf::TF_DataTypeSize(dtype) }
+}
+
+/// Tensor entry of file header
+struct Entry {
+    name: String,
+    dtype: tf::TF_DataType,
+    shape: Vec<i64>,
+    begin: usize,
+    end: usize,
+}
+
+fn parse_entry(name: &str, info: &Value) -> Result<Entry> {
+    let invalid_entry = |field: &str| invalid(format!("invalid {} of {}", field, name));
+
+    let dtype_name = info["dtype"]
+        .as_str()
+        .ok_or_else(|| invalid_entry("dtype"))?;
+    let dtype = DATA_TYPES
+        .iter()
+        .find(|(_, known)| *known == dtype_name)
+        .map(|(dtype, _)| *dtype)
+        .ok_or_else(|| invalid(format!("unsupported dtype {} of {}", dtype_name, name)))?;
+    let shape = info["shape"]
+        .as_array()
+        .ok_or_else(|| invalid_entry("shape"))?
+        .iter()
+        .map(|size| size.as_i64().filter(|size| *size >= 0))
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(|| invalid_entry("shape"))?;
+    let offsets = info["data_offsets"]
+        .as_array()
+        .filter(|offsets| offsets.len() == 2)
+        .and_then(|offsets| Some((offsets[0].as_u64()?, offsets[1].as_u64()?)))
+        .ok_or_else(|| invalid_entry("data_offsets"))?;
+
+    let (begin, end) = (offsets.0 as usize, offsets.1 as usize);
+    let len = shape.iter().product::<i64>() as usize;
+    if end < begin || end - begin != len * elem_size(dtype) {
+        return Err(invalid(format!(
+            "data size of {} doesn't match its shape",
+            name
+        )));
+    }
+
+    Ok(Entry {
+        name: name.to_owned(),
+        dtype,
+        shape,
+        begin,
+        end,
+    })
+}
+
+/// Drops reference to mapped file, when tensorflow releases tensor
+unsafe extern "C" fn release_mmap(_data: *mut c_void, _len: usize, mmap: *mut c_void) {
+    drop(Box::from_raw(mmap as *mut Arc<MmapMut>));
+}
+
+/// Creates tensor pointing directly into mapped file starting at `base`,
+/// if its data is aligned for tensorflow, or copying data otherwise
+fn map_tensor(mmap: &Arc<MmapMut>, base: *mut u8, entry: &Entry) -> Result<AnyTensor> {
+    let data = unsafe { base.add(entry.begin) };
+    let len = entry.end - entry.begin;
+    if len == 0 || data as usize % ALIGNMENT != 0 {
+        return AnyTensor::from_bytes(entry.dtype, &entry.shape, &mmap[entry.begin..entry.end]);
+    }
+
+    let owner = Box::into_raw(Box::new(mmap.clone()));
+    let tensor = unsafe {
+        tf::TF_NewTensor(
+            entry.dtype,
+            entry.shape.as_ptr(),
+            entry.shape.len() as i32,
+            data as *mut c_void,
+            len,
+            Some(release_mmap),
+            owner as *mut c_void,
+        )
+    };
+
+    // On failure tensorflow calls deallocator itself
+    if tensor.is_null() {
+        return Err(Error::ObjectCreationFailure);
+    }
+
+    Ok(unsafe { AnyTensor::from_ptr(tensor) })
+}
+
+/// Reads all tensors from safetensors file, by their names. File is
+/// memory mapped, and tensors with data aligned to 64 bytes share memory
+/// with the mapping instead of being copied. Format doesn't allow padding
+/// between tensors, so usually only the first tensor is aligned this way -
+/// `write_safetensors` aligns the first tensor only, and following ones to
+/// their element sizes, so they are copied. Mapping is private, so
+/// modifying tensors doesn't change the file. Metadata stored in file is
+/// ignored.
+pub fn read_safetensors(path: impl AsRef<Path>) -> Result<HashMap<String, AnyTensor>> {
+    let file = File::open(path)?;
+    if file.metadata()?.len() < 8 {
+        return Err(invalid("file is too short"));
+    }
+    let mut mmap = unsafe { MmapOptions::new().map_copy(&file)? };
+
+    let mut header_len = [0; 8];
+    header_len.copy_from_slice(&mmap[..8]);
+    let header_len = u64::from_le_bytes(header_len) as usize;
+    let header = mmap
+        .get(8..8usize.saturating_add(header_len))
+        .ok_or_else(|| invalid("header exceeds file size"))?;
+    let header: Map<String, Value> =
+        serde_json::from_slice(header).map_err(|err| invalid(err.to_string()))?;
+
+    let mut entries = header
+        .iter()
+        .filter(|(name, _)| *name != METADATA_KEY)
+        .map(|(name, info)| parse_entry(name, info))
+        .collect::<Result<Vec<_>>>()?;
+
+    // Mutable tensors must not share data
+    let data_start = 8 + header_len;
+    let data_len = mmap.len() - data_start;
+    entries.sort_by_key(|entry| (entry.begin, entry.end));
+    let mut data_end = 0;
+    for entry in &entries {
+        if entry.begin < data_end || entry.end > data_len {
+            return Err(invalid(format!("data of {} is out of bounds", entry.name)));
+        }
+        data_end = entry.end.max(data_end);
+    }
+
+    let base = mmap.as_mut_ptr();
+    let mmap = Arc::new(mmap);
+    entries
+        .into_iter()
+        .map(|mut entry| {
+            entry.begin += data_start;
+            entry.end += data_start;
+            Ok((entry.name.clone(), map_tensor(&mmap, base, &entry)?))
+        })
+        .collect()
+}
+
+/// Writes named tensors in safetensors format. Tensor data starts at
+/// offset aligned to 64 bytes, and tensors are ordered by element size, so
+/// every tensor data is aligned at least to its element size. Strings and
+/// types without safetensors counterpart are not supported.
+pub fn write_safetensors(mut writer: impl Write, tensors: &[(&str, &AnyTensor)]) -> Result<()> {
+    let mut tensors = tensors
+        .iter()
+        .map(|(name, tensor)| {
+            let dtype_name = DATA_TYPES
+                .iter()
+                .find(|(dtype, _)| *dtype == tensor.dtype())
+                .map(|(_, name)| *name)
+                .ok_or_else(|| Error::UnsupportedType(tensor.dtype()))?;
+            Ok((*name, *tensor, dtype_name))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    tensors.sort_by_key(|(name, tensor, _)| (std::cmp::Reverse(elem_size(tensor.dtype())), *name));
+
+    let mut header = Map::new();
+    let mut offset = 0;
+    for (name, tensor, dtype_name) in &tensors {
+        let len = tensor.as_bytes().len();
+        let info = json!({
+            "dtype": dtype_name,
+            "shape": tensor.shape(),
+            "data_offsets": [offset, offset + len],
+        });
+        if header.insert((*name).to_owned(), info).is_some() {
+            return Err(invalid(format!("duplicated tensor name {}", name)));
+        }
+        offset += len;
+    }
+
+    let mut header =
+        serde_json::to_vec(&Value::Object(header)).map_err(|err| invalid(err.to_string()))?;
+    let padding = (ALIGNMENT - (8 + header.len()) % ALIGNMENT) % ALIGNMENT;
+    header.resize(header.len() + padding, b' ');
+
+    writer.write_all(&(header.len() as u64).to_le_bytes())?;
+    writer.write_all(&header)?;
+    for (_, tensor, _) in tensors {
+        writer.write_all(tensor.as_bytes())?;
+    }
+    Ok(())
+}
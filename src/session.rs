@@ -1,37 +1,320 @@
-use crate::{Error, Graph, Result, Status};
+use crate::device::DeviceList;
+use crate::graph::OwnedGraph;
+#[cfg(feature = "async")]
+use crate::run_pool::{RunFuture, RunPool};
+use crate::wire::Writer;
+use crate::{AnyTensor, DeviceInfo, Error, Graph, Operation, Output, Result, Status};
+use std::{mem, ptr, sync::Arc};
 use tf;
 
-/// Thin wrapper over tensorflow session
-pub struct Session(*mut tf::TF_Session);
+/// Keeps graph used by session alive for whole session lifetime. Graph
+/// can be either borrowed, so lifetime of session is bound to the graph
+/// lifetime, or shared, so session keeps graph alive by itself.
+#[derive(Clone)]
+enum GraphRef<'a> {
+    Borrowed(&'a Graph),
+    Shared(Arc<OwnedGraph>),
+}
+
+impl<'a> GraphRef<'a> {
+    fn graph(&self) -> &Graph {
+        match self {
+            GraphRef::Borrowed(graph) => graph,
+            GraphRef::Shared(graph) => graph,
+        }
+    }
+}
+
+/// Thin wrapper over tensorflow session. Session never outlives its
+/// graph - it is either borrowing it (and then lifetime of session is
+/// bound to graph), or sharing it (and then session is `'static`).
+pub struct Session<'a> {
+    session: *mut tf::TF_Session,
+    graph: GraphRef<'a>,
+}
+
+// Tensorflow guarantees `TF_SessionRun` to be safe for concurrent calls on
+// single session object - it is the way sessions are used in TF serving.
+// All other operations on session (closing and deleting) are consuming
+// it, so they can never race with run calls. Graph is either borrowed or
+// shared via `Arc`, and it is `Sync` itself.
+unsafe impl<'a> Send for Session<'a> {}
+unsafe impl<'a> Sync for Session<'a> {}
 
-/// Represents session which is already closed
-pub struct ClosedSession(*mut tf::TF_Session);
+/// Represents session which is already closed. It still keeps graph
+/// alive, as tensorflow accesses it while deleting session.
+pub struct ClosedSession<'a> {
+    session: *mut tf::TF_Session,
+    graph: GraphRef<'a>,
+}
+
+// Closed session can only be deleted, which is consuming operation
+unsafe impl<'a> Send for ClosedSession<'a> {}
+unsafe impl<'a> Sync for ClosedSession<'a> {}
+
+impl<'a> Session<'a> {
+    /// Returns graph this session is running
+    ///
+    /// ```rust
+    /// # use rustflow::Graph;
+    /// # use rustflow::session::SessionBuilder;
+    /// let proto = include_str!("../tests/data/addition.pb");
+    /// let graph = Graph::from_protobuff(proto).unwrap();
+    /// let session = SessionBuilder::with_graph(&graph)
+    ///     .unwrap()
+    ///     .build()
+    ///     .unwrap();
+    /// assert!(session.graph().operation_by_name("z").is_some());
+    /// ```
+    pub fn graph(&self) -> &Graph {
+        self.graph.graph()
+    }
+
+    /// Runs graph in session. `feeds` are tensors to be put as given
+    /// outputs values, `fetches` are outputs which values should be
+    /// returned (in the same order), and `targets` are operations to be
+    /// run, but which outputs are not returned.
+    ///
+    /// Session can be run from many threads at once.
+    ///
+    /// ```rust
+    /// # use rustflow::{Graph, Tensor};
+    /// # use rustflow::session::SessionBuilder;
+    /// let proto = include_str!("../tests/data/addition.pb");
+    /// let graph = Graph::from_protobuff(proto).unwrap();
+    /// let session = SessionBuilder::with_graph(&graph)
+    ///     .unwrap()
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let x = graph.operation_by_name("x").unwrap().outputs().next().unwrap();
+    /// let y = graph.operation_by_name("y").unwrap().outputs().next().unwrap();
+    /// let z = graph.operation_by_name("z").unwrap().outputs().next().unwrap();
+    /// let x_val = Tensor::from_slice(&[2], &[1, 2]).unwrap();
+    /// let y_val = Tensor::from_slice(&[2], &[3, 4]).unwrap();
+    ///
+    /// let result = session
+    ///     .run(&[(x, x_val.as_ref()), (y, y_val.as_ref())], &[z], &[])
+    ///     .unwrap();
+    /// let z_val = result.into_iter().next().unwrap().downcast::<i32>().unwrap();
+    /// assert_eq!(&[4, 6], &z_val[..]);
+    /// ```
+    pub fn run(
+        &self,
+        feeds: &[(Output, &AnyTensor)],
+        fetches: &[Output],
+        targets: &[Operation],
+    ) -> Result<Vec<AnyTensor>> {
+        let inputs: Vec<_> = feeds.iter().map(|(output, _)| output.output).collect();
+        let input_values: Vec<_> = feeds.iter().map(|(_, tensor)| tensor.tensor).collect();
+        let outputs: Vec<_> = fetches.iter().map(|output| output.output).collect();
+        let targets: Vec<_> = targets
+            .iter()
+            .map(|op| op.operation as *const tf::TF_Operation)
+            .collect();
+
+        unsafe { self.run_raw(&inputs, &input_values, &outputs, &targets, "Session::run") }
+    }
+
+    /// Runs session on raw tensorflow objects. This is unsafe, because callee
+    /// has to ensure, that all outputs and operations belongs to session graph,
+    /// and all input values are valid tensors. Errors are marked as raised by
+    /// `function`, public function running session.
+    pub(crate) unsafe fn run_raw(
+        &self,
+        inputs: &[tf::TF_Output],
+        input_values: &[*mut tf::TF_Tensor],
+        outputs: &[tf::TF_Output],
+        targets: &[*const tf::TF_Operation],
+        function: &'static str,
+    ) -> Result<Vec<AnyTensor>> {
+        let mut output_values = vec![ptr::null_mut(); outputs.len()];
+        let mut status = Status::new();
+        tf::TF_SessionRun(
+            self.session,
+            ptr::null(),
+            inputs.as_ptr(),
+            input_values.as_ptr(),
+            inputs.len() as i32,
+            outputs.as_ptr(),
+            output_values.as_mut_ptr(),
+            outputs.len() as i32,
+            targets.as_ptr(),
+            targets.len() as i32,
+            ptr::null_mut(),
+            status.get(),
+        );
+
+        status.to_result_in(function)?;
+        // On success tensorflow gives up ownership of all fetched tensors
+        Ok(output_values
+            .into_iter()
+            .map(|tensor| AnyTensor::from_ptr(tensor))
+            .collect())
+    }
+
+    /// Returns all devices available for this session
+    ///
+    /// ```rust
+    /// # use rustflow::Graph;
+    /// # use rustflow::session::SessionBuilder;
+    /// # let proto = include_str!("../tests/data/addition.pb");
+    /// # let graph = Graph::from_protobuff(proto).unwrap();
+    /// # let session = SessionBuilder::with_graph(&graph).unwrap().build().unwrap();
+    /// let devices = session.devices().unwrap();
+    /// assert!(devices.iter().any(|device| device.device_type == "CPU"));
+    /// ```
+    pub fn devices(&self) -> Result<Vec<DeviceInfo>> {
+        let mut status = Status::new();
+        let list = unsafe { tf::TF_SessionListDevices(self.session, status.get()) };
+        status.to_result()?;
+
+        unsafe { DeviceList::new(list) }.devices()
+    }
 
-impl Session {
     /// Function for closing session. It should be called
     /// only to get information about error while closing session,
     /// otherwise closing and deleting session is done automatically
     /// while session dropping
-    pub fn close(self) -> Result<ClosedSession> {
+    pub fn close(self) -> Result<ClosedSession<'a>> {
         let mut status = Status::new();
         unsafe {
-            tf::TF_CloseSession(self.0, status.get());
+            tf::TF_CloseSession(self.session, status.get());
         }
 
+        let session = self.session;
+        // Session is either deleted or moved into `ClosedSession` below,
+        // so `self` cannot be dropped, but graph reference has to be kept
+        let graph = unsafe { ptr::read(&self.graph) };
+        mem::forget(self);
+
         let result = status.to_result();
         if let Err(err) = result {
             unsafe {
-                tf::TF_DeleteSession(self.0, status.get());
+                tf::TF_DeleteSession(session, status.get());
             }
 
             Err(err.into())
         } else {
-            Ok(ClosedSession(self.0))
+            Ok(ClosedSession { session, graph })
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl Session<'static> {
+    /// Runs graph in session on given `RunPool`, without blocking current
+    /// thread. Arguments are the same as for `Session::run`, but feeds are
+    /// owned, so they can be moved to pool thread. Outputs and operations
+    /// have to belong to session graph - if some of them is missing in it,
+    /// future resolves to `Error::OperationNotFound`, and if it comes from
+    /// other graph with operation of the same name, to
+    /// `Error::InvalidGraph`.
+    ///
+    /// Dropping returned future cancels the run if it is not started yet.
+    /// Tensorflow C API doesn't provide any way to interrupt run which is
+    /// already in progress, so in such case run is completed, and its result
+    /// is discarded.
+    ///
+    /// ```rust
+    /// # use rustflow::{Graph, Tensor};
+    /// # use rustflow::run_pool::RunPool;
+    /// # use rustflow::session::SessionBuilder;
+    /// # use std::sync::Arc;
+    /// let proto = include_str!("../tests/data/addition.pb");
+    /// let graph = Arc::new(Graph::from_protobuff(proto).unwrap());
+    /// let session = SessionBuilder::with_shared_graph(graph.clone())
+    ///     .unwrap()
+    ///     .build()
+    ///     .map(Arc::new)
+    ///     .unwrap();
+    /// let pool = RunPool::new(2, 4);
+    ///
+    /// let x = graph.operation_by_name("x").unwrap().outputs().next().unwrap();
+    /// let y = graph.operation_by_name("y").unwrap().outputs().next().unwrap();
+    /// let z = graph.operation_by_name("z").unwrap().outputs().next().unwrap();
+    /// let x_val = Tensor::from_slice(&[1], &[1]).unwrap();
+    /// let y_val = Tensor::from_slice(&[1], &[2]).unwrap();
+    ///
+    /// let result = session.run_async(
+    ///     &pool,
+    ///     vec![(x, x_val.into()), (y, y_val.into())],
+    ///     &[z],
+    ///     &[],
+    /// );
+    /// let result = futures::executor::block_on(result).unwrap();
+    /// let z_val = result.into_iter().next().unwrap().downcast::<i32>().unwrap();
+    /// assert_eq!(&[3], &z_val[..]);
+    /// ```
+    pub fn run_async(
+        self: Arc<Self>,
+        pool: &RunPool,
+        feeds: Vec<(Output, AnyTensor)>,
+        fetches: &[Output],
+        targets: &[Operation],
+    ) -> RunFuture {
+        // Raw tensorflow handles are moved to pool thread together with
+        // session, which keeps graph (and so all its operations) alive
+        struct RawRun {
+            inputs: Vec<tf::TF_Output>,
+            input_values: Vec<AnyTensor>,
+            outputs: Vec<tf::TF_Output>,
+            targets: Vec<*const tf::TF_Operation>,
         }
+        unsafe impl Send for RawRun {}
+
+        // Handles are checked to belong to session graph, which keeps them
+        // valid as long as session is alive. Operations are looked up by
+        // name, and compared with found ones.
+        let graph = self.graph();
+        let check = |op: &Operation| {
+            if graph.find_operation(op.name())? == *op {
+                Ok(op.operation as *const tf::TF_Operation)
+            } else {
+                Err(Error::InvalidGraph(format!(
+                    "operation {} doesn't belong to session graph",
+                    op.name()
+                )))
+            }
+        };
+        let resolve = |output: &Output| {
+            check(&output.operation())?;
+            Ok(output.output)
+        };
+        let run = feeds
+            .into_iter()
+            .map(|(output, tensor)| Ok((resolve(&output)?, tensor)))
+            .collect::<Result<Vec<_>>>()
+            .and_then(|feeds| {
+                let (inputs, input_values) = feeds.into_iter().unzip();
+                Ok(RawRun {
+                    inputs,
+                    input_values,
+                    outputs: fetches.iter().map(resolve).collect::<Result<_>>()?,
+                    targets: targets.iter().map(check).collect::<Result<_>>()?,
+                })
+            });
+        let run = match run {
+            Ok(run) => run,
+            Err(err) => return pool.ready(Err(err)),
+        };
+
+        pool.spawn(move || {
+            let input_values: Vec<_> = run.input_values.iter().map(|t| t.tensor).collect();
+            unsafe {
+                self.run_raw(
+                    &run.inputs,
+                    &input_values,
+                    &run.outputs,
+                    &run.targets,
+                    "Session::run_async",
+                )
+            }
+        })
     }
 }
 
-impl ClosedSession {
+impl<'a> ClosedSession<'a> {
     /// Function for deleting session. It should be called
     /// only to get information about error while closing session,
     /// otherwise deleteing session is done automatically
@@ -39,28 +322,34 @@ impl ClosedSession {
     pub fn delete(self) -> Result<()> {
         let mut status = Status::new();
         unsafe {
-            tf::TF_DeleteSession(self.0, status.get());
+            tf::TF_DeleteSession(self.session, status.get());
         }
 
+        // Session is already deleted, only graph reference has to be
+        // released
+        let graph = unsafe { ptr::read(&self.graph) };
+        mem::forget(self);
+        drop(graph);
+
         Ok(status.to_result()?)
     }
 }
 
-impl Drop for Session {
+impl<'a> Drop for Session<'a> {
     fn drop(&mut self) {
         let mut status = Status::new();
         unsafe {
-            tf::TF_CloseSession(self.0, status.get());
-            tf::TF_DeleteSession(self.0, status.get());
+            tf::TF_CloseSession(self.session, status.get());
+            tf::TF_DeleteSession(self.session, status.get());
         }
     }
 }
 
-impl Drop for ClosedSession {
+impl<'a> Drop for ClosedSession<'a> {
     fn drop(&mut self) {
         let mut status = Status::new();
         unsafe {
-            tf::TF_DeleteSession(self.0, status.get());
+            tf::TF_DeleteSession(self.session, status.get());
         }
     }
 }
@@ -70,13 +359,14 @@ impl Drop for ClosedSession {
 /// which is not owned by SessionBuilder.
 pub struct SessionBuilder<'a> {
     options: *mut tf::TF_SessionOptions,
-    graph: &'a Graph,
+    graph: GraphRef<'a>,
 }
 
 /// Thin wrapper over tensorflow session option for
 /// building actual session object
 impl<'a> SessionBuilder<'a> {
-    /// Creates new session builder with associated with graph
+    /// Creates new session builder with associated with graph. Built session
+    /// borrows the graph, so it cannot outlive it.
     ///
     /// ```rust
     /// # use rustflow::Graph;
@@ -86,6 +376,10 @@ impl<'a> SessionBuilder<'a> {
     /// let builder = SessionBuilder::with_graph(&graph).unwrap();
     /// ```
     pub fn with_graph(graph: &'a Graph) -> Result<Self> {
+        Self::new(GraphRef::Borrowed(graph))
+    }
+
+    fn new(graph: GraphRef<'a>) -> Result<Self> {
         let options = unsafe { tf::TF_NewSessionOptions() };
 
         if options.is_null() {
@@ -95,6 +389,62 @@ impl<'a> SessionBuilder<'a> {
         Ok(SessionBuilder { options, graph })
     }
 
+    /// Sets session configuration from serialized `ConfigProto` protobuf
+    ///
+    /// ```rust
+    /// # use rustflow::Graph;
+    /// # use rustflow::session::SessionBuilder;
+    /// # let proto = include_str!("../tests/data/addition.pb");
+    /// # let graph = Graph::from_protobuff(proto).unwrap();
+    /// // intra_op_parallelism_threads: 1
+    /// let config = [0x10, 0x01];
+    /// let session = SessionBuilder::with_graph(&graph)
+    ///     .unwrap()
+    ///     .set_config(&config)
+    ///     .unwrap()
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    pub fn set_config(self, config: &[u8]) -> Result<Self> {
+        let mut status = Status::new();
+        unsafe {
+            tf::TF_SetConfig(
+                self.options,
+                config.as_ptr() as *const std::ffi::c_void,
+                config.len(),
+                status.get(),
+            )
+        };
+        status.to_result()?;
+        Ok(self)
+    }
+
+    /// Sets numbers of threads used for running single operation
+    /// (`intra_op_threads`) and independent operations
+    /// (`inter_op_threads`), `0` letting tensorflow choose. This replaces
+    /// configuration given with `SessionBuilder::set_config`.
+    ///
+    /// ```rust
+    /// # use rustflow::Graph;
+    /// # use rustflow::session::SessionBuilder;
+    /// # let proto = include_str!("../tests/data/addition.pb");
+    /// # let graph = Graph::from_protobuff(proto).unwrap();
+    /// let session = SessionBuilder::with_graph(&graph)
+    ///     .unwrap()
+    ///     .set_parallelism(2, 1)
+    ///     .unwrap()
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    pub fn set_parallelism(self, intra_op_threads: usize, inter_op_threads: usize) -> Result<Self> {
+        // `ConfigProto` fields
+        let mut config = Writer::new();
+        config
+            .varint(2, intra_op_threads as u64)
+            .varint(5, inter_op_threads as u64);
+        self.set_config(&config.into_bytes())
+    }
+
     /// Builds final session object
     ///
     /// ```rust
@@ -106,13 +456,39 @@ impl<'a> SessionBuilder<'a> {
     ///     .unwrap()
     ///     .build()
     ///     .unwrap();
-    pub fn build(self) -> Result<Session> {
+    pub fn build(self) -> Result<Session<'a>> {
         let mut status = Status::new();
         let session =
-            unsafe { tf::TF_NewSession(self.graph.get_ptr(), self.options, status.get()) };
+            unsafe { tf::TF_NewSession(self.graph.graph().get_ptr(), self.options, status.get()) };
 
-        status.to_result()?;
-        Ok(Session(session))
+        status.to_result_in("SessionBuilder::build")?;
+        Ok(Session {
+            session,
+            graph: self.graph.clone(),
+        })
+    }
+}
+
+impl SessionBuilder<'static> {
+    /// Creates new session builder sharing ownership of the graph. Built
+    /// session keeps graph alive, so it is not bound to any lifetime, and
+    /// many sessions can be build for single graph.
+    ///
+    /// ```rust
+    /// # use rustflow::Graph;
+    /// # use rustflow::session::SessionBuilder;
+    /// # use std::sync::Arc;
+    /// let proto = include_str!("../tests/data/addition.pb");
+    /// let graph = Arc::new(Graph::from_protobuff(proto).unwrap());
+    /// let session = SessionBuilder::with_shared_graph(graph.clone())
+    ///     .unwrap()
+    ///     .build()
+    ///     .unwrap();
+    /// drop(graph);
+    /// assert!(session.graph().operation_by_name("z").is_some());
+    /// ```
+    pub fn with_shared_graph(graph: Arc<OwnedGraph>) -> Result<Self> {
+        Self::new(GraphRef::Shared(graph))
     }
 }
 
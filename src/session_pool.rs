@@ -0,0 +1,265 @@
+use crate::error::TFCode;
+use crate::graph::OwnedGraph;
+use crate::session::SessionBuilder;
+use crate::{AnyTensor, Error, Operation, Output, Result, Session};
+use std::ops;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+
+/// Run performed on every session just after its creation, so first
+/// real run doesn't pay for allocators warm up. Operations are kept by
+/// name, as they have to be found in every created session graph.
+#[derive(Default)]
+struct WarmUp {
+    feeds: Vec<(String, usize, AnyTensor)>,
+    fetches: Vec<(String, usize)>,
+    targets: Vec<String>,
+}
+
+impl WarmUp {
+    fn is_empty(&self) -> bool {
+        self.fetches.is_empty() && self.targets.is_empty()
+    }
+
+    fn run(&self, session: &Session) -> Result<()> {
+        if self.is_empty() {
+            return Ok(());
+        }
+
+        let graph = session.graph();
+        let feeds = self
+            .feeds
+            .iter()
+            .map(|(name, idx, value)| Ok((graph.find_output(name, *idx)?, value)))
+            .collect::<Result<Vec<_>>>()?;
+        let fetches = self
+            .fetches
+            .iter()
+            .map(|(name, idx)| graph.find_output(name, *idx))
+            .collect::<Result<Vec<_>>>()?;
+        let targets = self
+            .targets
+            .iter()
+            .map(|name| graph.find_operation(name))
+            .collect::<Result<Vec<_>>>()?;
+
+        session.run(&feeds, &fetches, &targets)?;
+        Ok(())
+    }
+}
+
+/// Builder for SessionPool object
+pub struct SessionPoolBuilder {
+    graph: Arc<OwnedGraph>,
+    size: usize,
+    warm_up: WarmUp,
+}
+
+impl SessionPoolBuilder {
+    /// Creates builder for pool of `size` sessions sharing single graph
+    pub fn new(graph: Arc<OwnedGraph>, size: usize) -> Self {
+        SessionPoolBuilder {
+            graph,
+            size,
+            warm_up: WarmUp::default(),
+        }
+    }
+
+    /// Adds value fed into `idx` output of operation `name` while warming
+    /// up every created session
+    pub fn warm_up_feed(mut self, name: &str, idx: usize, value: AnyTensor) -> Self {
+        self.warm_up.feeds.push((name.to_owned(), idx, value));
+        self
+    }
+
+    /// Adds `idx` output of operation `name` to be fetched while warming
+    /// up every created session
+    pub fn warm_up_fetch(mut self, name: &str, idx: usize) -> Self {
+        self.warm_up.fetches.push((name.to_owned(), idx));
+        self
+    }
+
+    /// Adds operation `name` to be run while warming up every created
+    /// session
+    pub fn warm_up_target(mut self, name: &str) -> Self {
+        self.warm_up.targets.push(name.to_owned());
+        self
+    }
+
+    /// Builds pool, creating and warming up all its sessions
+    ///
+    /// ```rust
+    /// # use rustflow::session_pool::SessionPoolBuilder;
+    /// # use rustflow::{Graph, Tensor};
+    /// # use std::sync::Arc;
+    /// let proto = include_str!("../tests/data/addition.pb");
+    /// let graph = Arc::new(Graph::from_protobuff(proto).unwrap());
+    /// let pool = SessionPoolBuilder::new(graph, 2)
+    ///     .warm_up_feed("x", 0, Tensor::from_slice(&[1], &[1]).unwrap().into())
+    ///     .warm_up_feed("y", 0, Tensor::from_slice(&[1], &[2]).unwrap().into())
+    ///     .warm_up_fetch("z", 0)
+    ///     .build()
+    ///     .unwrap();
+    /// let session = pool.get().unwrap();
+    /// assert!(session.graph().operation_by_name("z").is_some());
+    /// ```
+    pub fn build(self) -> Result<SessionPool> {
+        let pool = SessionPool {
+            graph: self.graph,
+            warm_up: self.warm_up,
+            state: Mutex::new(PoolState {
+                idle: vec![],
+                missing: 0,
+            }),
+            available: Condvar::new(),
+        };
+
+        let sessions = (0..self.size.max(1))
+            .map(|_| pool.create_session())
+            .collect::<Result<Vec<_>>>()?;
+        pool.state.lock().unwrap().idle = sessions;
+
+        Ok(pool)
+    }
+}
+
+struct PoolState {
+    idle: Vec<Session<'static>>,
+    // Number of sessions which were discarded, and have to be rebuilt
+    missing: usize,
+}
+
+/// Pool of warmed up sessions sharing single graph. Sessions are handed out
+/// as `PooledSession` guards, which are giving sessions back to pool when
+/// dropped. Sessions which failed with `Internal` or `Unavailable` error are
+/// considered broken, and are rebuilt instead of being reused.
+pub struct SessionPool {
+    graph: Arc<OwnedGraph>,
+    warm_up: WarmUp,
+    state: Mutex<PoolState>,
+    available: Condvar,
+}
+
+impl SessionPool {
+    /// Takes session from pool, blocking until any is available
+    pub fn get(&self) -> Result<PooledSession> {
+        let mut state = self.state.lock().unwrap();
+        loop {
+            if let Some(session) = state.idle.pop() {
+                return Ok(PooledSession::new(self, session));
+            }
+
+            if state.missing > 0 {
+                state.missing -= 1;
+                drop(state);
+                return self.rebuild();
+            }
+
+            state = self.available.wait(state).unwrap();
+        }
+    }
+
+    /// Takes session from pool, if any is available without waiting
+    pub fn try_get(&self) -> Option<Result<PooledSession>> {
+        let mut state = self.state.lock().unwrap();
+        if let Some(session) = state.idle.pop() {
+            return Some(Ok(PooledSession::new(self, session)));
+        }
+
+        if state.missing > 0 {
+            state.missing -= 1;
+            drop(state);
+            return Some(self.rebuild());
+        }
+
+        None
+    }
+
+    /// Rebuilds missing session. If it fails, session is still considered
+    /// missing, so next `get` would try again
+    fn rebuild(&self) -> Result<PooledSession> {
+        match self.create_session() {
+            Ok(session) => Ok(PooledSession::new(self, session)),
+            Err(err) => {
+                self.state.lock().unwrap().missing += 1;
+                self.available.notify_one();
+                Err(err)
+            }
+        }
+    }
+
+    fn create_session(&self) -> Result<Session<'static>> {
+        let session = SessionBuilder::with_shared_graph(self.graph.clone())?.build()?;
+        self.warm_up.run(&session)?;
+        Ok(session)
+    }
+
+    fn give_back(&self, session: Option<Session<'static>>) {
+        let mut state = self.state.lock().unwrap();
+        match session {
+            Some(session) => state.idle.push(session),
+            None => state.missing += 1,
+        }
+        self.available.notify_one();
+    }
+}
+
+/// Session taken from `SessionPool`, given back to it when dropped
+pub struct PooledSession<'a> {
+    pool: &'a SessionPool,
+    session: Option<Session<'static>>,
+    healthy: AtomicBool,
+}
+
+impl<'a> PooledSession<'a> {
+    fn new(pool: &'a SessionPool, session: Session<'static>) -> Self {
+        PooledSession {
+            pool,
+            session: Some(session),
+            healthy: AtomicBool::new(true),
+        }
+    }
+
+    /// Runs session, the same way as `Session::run` does, but also checks
+    /// session health. Session failing with `Internal` or `Unavailable` error
+    /// is marked unhealthy.
+    pub fn run(
+        &self,
+        feeds: &[(Output, &AnyTensor)],
+        fetches: &[Output],
+        targets: &[Operation],
+    ) -> Result<Vec<AnyTensor>> {
+        let result = (**self).run(feeds, fetches, targets);
+
+        if let Err(Error::TFError(err)) = &result {
+            if err.code() == TFCode::Internal || err.code() == TFCode::Unavailable {
+                self.mark_unhealthy();
+            }
+        }
+
+        result
+    }
+
+    /// Marks session as unhealthy, so it would be rebuilt instead of
+    /// being given back to pool
+    pub fn mark_unhealthy(&self) {
+        self.healthy.store(false, Ordering::Relaxed);
+    }
+}
+
+impl<'a> ops::Deref for PooledSession<'a> {
+    type Target = Session<'static>;
+
+    fn deref(&self) -> &Session<'static> {
+        // Session is taken only while dropping guard
+        self.session.as_ref().unwrap()
+    }
+}
+
+impl<'a> Drop for PooledSession<'a> {
+    fn drop(&mut self) {
+        let healthy = self.healthy.load(Ordering::Relaxed);
+        let session = self.session.take().filter(|_| healthy);
+        self.pool.give_back(session);
+    }
+}
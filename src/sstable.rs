@@ -0,0 +1,116 @@
+//! Reading tables in leveldb SSTable format, as used by tensorflow for
+//! checkpoint indexes. Only uncompressed tables are supported.
+
+use crate::crc::{crc32c, unmask};
+use crate::wire::read_varint;
+use crate::{Error, Result};
+use std::convert::TryInto;
+
+const MAGIC: u64 = 0xdb47_7524_8b80_fb57;
+const FOOTER_LEN: usize = 48;
+/// Compression type and checksum following every block
+const TRAILER_LEN: usize = 5;
+
+fn invalid(message: impl Into<String>) -> Error {
+    Error::InvalidCheckpoint(message.into())
+}
+
+fn varint(data: &mut &[u8]) -> Result<usize> {
+    read_varint(data)
+        .map(|value| value as usize)
+        .map_err(|_| invalid("truncated table entry"))
+}
+
+fn u32_at(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+/// Location of block in table
+struct BlockHandle {
+    offset: usize,
+    size: usize,
+}
+
+impl BlockHandle {
+    fn decode(data: &mut &[u8]) -> Result<Self> {
+        Ok(BlockHandle {
+            offset: varint(data)?,
+            size: varint(data)?,
+        })
+    }
+}
+
+/// Returns contents of block, verifying its checksum
+fn read_block<'a>(table: &'a [u8], handle: &BlockHandle) -> Result<&'a [u8]> {
+    let end = handle
+        .offset
+        .checked_add(handle.size)
+        .filter(|end| end + TRAILER_LEN <= table.len())
+        .ok_or_else(|| invalid("block exceeds table size"))?;
+
+    if table[end] != 0 {
+        return Err(invalid("compressed blocks are not supported"));
+    }
+    // Checksum covers compression type as well
+    if unmask(u32_at(table, end + 1)) != crc32c(&table[handle.offset..=end]) {
+        return Err(invalid("block checksum mismatch"));
+    }
+    Ok(&table[handle.offset..end])
+}
+
+/// Decodes all entries of block. Keys are stored as suffixes of previous
+/// keys, so they can't be borrowed from block.
+fn block_entries(block: &[u8]) -> Result<Vec<(Vec<u8>, &[u8])>> {
+    let restarts = block
+        .len()
+        .checked_sub(4)
+        .map(|offset| u32_at(block, offset) as usize);
+    let entries_len = restarts
+        .and_then(|restarts| {
+            block
+                .len()
+                .checked_sub(restarts.saturating_mul(4).saturating_add(4))
+        })
+        .ok_or_else(|| invalid("truncated block"))?;
+
+    let mut data = &block[..entries_len];
+    let mut key = vec![];
+    let mut entries = vec![];
+    while !data.is_empty() {
+        let shared = varint(&mut data)?;
+        let unshared = varint(&mut data)?;
+        let value_len = varint(&mut data)?;
+        if shared > key.len() || unshared.saturating_add(value_len) > data.len() {
+            return Err(invalid("corrupted table entry"));
+        }
+
+        key.truncate(shared);
+        key.extend_from_slice(&data[..unshared]);
+        entries.push((key.clone(), &data[unshared..unshared + value_len]));
+        data = &data[unshared + value_len..];
+    }
+    Ok(entries)
+}
+
+/// Reads all entries of table, in order of their keys
+pub fn read_table(table: &[u8]) -> Result<Vec<(Vec<u8>, &[u8])>> {
+    let footer = table
+        .len()
+        .checked_sub(FOOTER_LEN)
+        .map(|offset| &table[offset..])
+        .ok_or_else(|| invalid("table is too short"))?;
+    if u64::from_le_bytes(footer[40..].try_into().unwrap()) != MAGIC {
+        return Err(invalid("invalid table magic number"));
+    }
+
+    let mut handles = &footer[..40];
+    let _metaindex = BlockHandle::decode(&mut handles)?;
+    let index = BlockHandle::decode(&mut handles)?;
+
+    let mut entries = vec![];
+    for (_, mut handle) in block_entries(read_block(table, &index)?)? {
+        let handle = BlockHandle::decode(&mut handle)?;
+        entries.extend(block_entries(read_block(table, &handle)?)?);
+    }
+    Ok(entries)
+}
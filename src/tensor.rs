@@ -1,19 +1,295 @@
-use crate::{Error, Result, TensorType};
-use std::{self, mem, ops, slice};
+use crate::{Error, Result, Status, TensorType};
+use std::os::raw::c_char;
+use std::{self, fmt, mem, ops, ptr, slice};
 use tf;
 
-/// Internally allocated Tensor
-pub struct Tensor<T: 'static> {
+/// Tensor with data type known only in runtime, for example tensor fetched
+/// from session run. It can be turned into typed `Tensor` with `downcast`.
+pub struct AnyTensor {
     // Unsafe code assumes, this is always valild TF_Tensor object
     pub(crate) tensor: *mut tf::TF_Tensor,
 
     // Usize would be more natural choice here, but Tensorflow
     // uses i64 internally
     shape: Vec<i64>,
+}
+
+// TF_Tensor is immutable reference counted buffer from tensorflow point
+// of view, and any mutation of its data goes through `&mut` on rust side,
+// so it is safe to share and send it between threads.
+unsafe impl Send for AnyTensor {}
+unsafe impl Sync for AnyTensor {}
+
+impl AnyTensor {
+    /// Takes ownership over tensorflow tensor. Function is unsafe, because
+    /// callee has to ensure, that `tensor` is valid TF_Tensor object not owned
+    /// by anything else.
+    pub(crate) unsafe fn from_ptr(tensor: *mut tf::TF_Tensor) -> Self {
+        let dims = tf::TF_NumDims(tensor);
+        let shape = (0..dims).map(|dim| tf::TF_Dim(tensor, dim)).collect();
+
+        AnyTensor { tensor, shape }
+    }
+
+    /// Returns shape of tensor
+    ///
+    /// ```rust
+    /// # use rustflow::{AnyTensor, Tensor};
+    /// let tensor: AnyTensor = Tensor::from_slice(&[2, 2], &[1, 2, 3, 4]).unwrap().into();
+    /// assert_eq!(&[2, 2], tensor.shape());
+    /// ```
+    pub fn shape(&self) -> &[i64] {
+        &self.shape
+    }
+
+    /// Returns data type of tensor elements
+    ///
+    /// ```rust
+    /// # use rustflow::{AnyTensor, Tensor, TensorType};
+    /// let tensor: AnyTensor = Tensor::from_slice(&[2], &[1i64, 2]).unwrap().into();
+    /// assert_eq!(i64::TF_TYPE, tensor.dtype());
+    /// ```
+    pub fn dtype(&self) -> tf::TF_DataType {
+        unsafe { tf::TF_TensorType(self.tensor) }
+    }
+
+    /// Creates tensor of given type and shape, copying its raw data. Data
+    /// length has to match shape and type, otherwise `Error::InvalidShape`
+    /// is returned.
+    ///
+    /// ```rust
+    /// # use rustflow::{AnyTensor, TensorType};
+    /// let tensor = AnyTensor::from_bytes(u8::TF_TYPE, &[2], &[1, 2]).unwrap();
+    /// assert_eq!(&[1, 2], tensor.as_bytes());
+    /// AnyTensor::from_bytes(u16::TF_TYPE, &[2], &[1, 2]).unwrap_err();
+    /// ```
+    pub fn from_bytes(dtype: tf::TF_DataType, shape: &[i64], data: &[u8]) -> Result<Self> {
+        let len: i64 = shape.iter().product();
+        let elem_size = unsafe { tf::TF_DataTypeSize(dtype) };
+        if elem_size == 0 || data.len() != elem_size * len as usize {
+            return Err(Error::InvalidShape {
+                data_len: data.len(),
+                shape: shape.to_vec(),
+            });
+        }
+
+        let tensor =
+            unsafe { tf::TF_AllocateTensor(dtype, shape.as_ptr(), shape.len() as i32, data.len()) };
+
+        if tensor.is_null() {
+            return Err(Error::ObjectCreationFailure);
+        }
+
+        let tensor = unsafe { Self::from_ptr(tensor) };
+        if !data.is_empty() {
+            unsafe {
+                let dst = tf::TF_TensorData(tensor.tensor) as *mut u8;
+                ptr::copy_nonoverlapping(data.as_ptr(), dst, data.len());
+            }
+        }
+
+        Ok(tensor)
+    }
+
+    /// Creates string tensor of given shape. Number of strings has to match
+    /// shape, otherwise `Error::InvalidShape` is returned. Tensorflow strings
+    /// are arbitrary bytes, so values don't have to be valid UTF-8.
+    ///
+    /// ```rust
+    /// # use rustflow::AnyTensor;
+    /// let tensor = AnyTensor::from_strings(&[2], &["a", "bc"]).unwrap();
+    /// assert_eq!(&[2], tensor.shape());
+    /// AnyTensor::from_strings(&[2], &["a"]).unwrap_err();
+    /// AnyTensor::from_strings(&[], &[b"\xff"]).unwrap();
+    /// ```
+    pub fn from_strings<S: AsRef<[u8]>>(shape: &[i64], values: &[S]) -> Result<Self> {
+        let len: i64 = shape.iter().product();
+        if values.len() != len as usize {
+            return Err(Error::InvalidShape {
+                data_len: values.len(),
+                shape: shape.to_vec(),
+            });
+        }
+
+        // String tensor data starts with table of offsets of every encoded
+        // string, counted from the end of the table
+        let offsets_len = values.len() * mem::size_of::<u64>();
+        let encoded_len: usize = values
+            .iter()
+            .map(|value| unsafe { tf::TF_StringEncodedSize(value.as_ref().len()) })
+            .sum();
+
+        let tensor = unsafe {
+            tf::TF_AllocateTensor(
+                tf::TF_STRING,
+                shape.as_ptr(),
+                shape.len() as i32,
+                offsets_len + encoded_len,
+            )
+        };
+
+        if tensor.is_null() {
+            return Err(Error::ObjectCreationFailure);
+        }
+
+        let tensor = unsafe { Self::from_ptr(tensor) };
+        let mut status = Status::new();
+        let mut offset = 0;
+        for (idx, value) in values.iter().enumerate() {
+            let value = value.as_ref();
+            unsafe {
+                let data = tf::TF_TensorData(tensor.tensor) as *mut u8;
+                ptr::write_unaligned((data as *mut u64).add(idx), offset as u64);
+                offset += tf::TF_StringEncode(
+                    value.as_ptr() as *const c_char,
+                    value.len(),
+                    data.add(offsets_len + offset) as *mut c_char,
+                    encoded_len - offset,
+                    status.get(),
+                );
+            }
+            status.to_result()?;
+        }
+
+        Ok(tensor)
+    }
+
+    /// Decodes values of string tensor. For tensors of other types
+    /// `Error::TypeMismatch` is returned.
+    ///
+    /// ```rust
+    /// # use rustflow::AnyTensor;
+    /// let tensor = AnyTensor::from_strings(&[2], &["a", "bc"]).unwrap();
+    /// assert_eq!(vec![b"a".to_vec(), b"bc".to_vec()], tensor.strings().unwrap());
+    /// ```
+    pub fn strings(&self) -> Result<Vec<Vec<u8>>> {
+        if self.dtype() != tf::TF_STRING {
+            return Err(Error::TypeMismatch {
+                expected: tf::TF_STRING,
+                found: self.dtype(),
+            });
+        }
+
+        let len = self.shape.iter().product::<i64>() as usize;
+        let data = self.as_bytes();
+        let offsets_len = len * mem::size_of::<u64>();
+        if data.len() < offsets_len {
+            return Err(Error::InvalidShape {
+                data_len: data.len(),
+                shape: self.shape.clone(),
+            });
+        }
+
+        let encoded = &data[offsets_len..];
+        let mut status = Status::new();
+        let mut values = Vec::with_capacity(len);
+        for idx in 0..len {
+            let offset = unsafe { ptr::read_unaligned((data.as_ptr() as *const u64).add(idx)) };
+            let offset = (offset as usize).min(encoded.len());
+            let mut value = ptr::null();
+            let mut value_len = 0;
+            unsafe {
+                tf::TF_StringDecode(
+                    encoded[offset..].as_ptr() as *const c_char,
+                    encoded.len() - offset,
+                    &mut value,
+                    &mut value_len,
+                    status.get(),
+                );
+            }
+            status.to_result()?;
+            values.push(if value_len == 0 {
+                vec![]
+            } else {
+                unsafe { slice::from_raw_parts(value as *const u8, value_len) }.to_vec()
+            });
+        }
+        Ok(values)
+    }
+
+    /// Creates copy of tensor with its own data buffer
+    pub(crate) fn deep_copy(&self) -> Result<Self> {
+        if self.dtype() == tf::TF_STRING {
+            Self::from_strings(&self.shape, &self.strings()?)
+        } else {
+            Self::from_bytes(self.dtype(), &self.shape, self.as_bytes())
+        }
+    }
+
+    /// Returns raw tensor data, in tensorflow memory layout
+    ///
+    /// ```rust
+    /// # use rustflow::{AnyTensor, Tensor};
+    /// let tensor: AnyTensor = Tensor::from_slice(&[2], &[1u16, 2]).unwrap().into();
+    /// assert_eq!(4, tensor.as_bytes().len());
+    /// ```
+    pub fn as_bytes(&self) -> &[u8] {
+        unsafe {
+            let len = tf::TF_TensorByteSize(self.tensor);
+            let data = tf::TF_TensorData(self.tensor) as *const u8;
+            if data.is_null() || len == 0 {
+                return &[];
+            }
+            slice::from_raw_parts(data, len)
+        }
+    }
+
+    /// Turns tensor into typed one. If tensor elements are not of type `T`,
+    /// tensor is given back unchanged as an error.
+    ///
+    /// ```rust
+    /// # use rustflow::{AnyTensor, Tensor};
+    /// let tensor: AnyTensor = Tensor::from_slice(&[2], &[1, 2]).unwrap().into();
+    /// let tensor = tensor.downcast::<f32>().unwrap_err();
+    /// let tensor = tensor.downcast::<i32>().unwrap();
+    /// assert_eq!(&[1, 2], &tensor[..]);
+    /// ```
+    pub fn downcast<T: TensorType>(self) -> std::result::Result<Tensor<T>, Self> {
+        if self.dtype() != T::TF_TYPE {
+            return Err(self);
+        }
+
+        let len = self.shape.iter().product::<i64>() as usize;
+        let data = unsafe {
+            let data = tf::TF_TensorData(self.tensor) as *mut T;
+            // Tensorflow doesn't guarantee non-null data pointer for empty tensors
+            let data = if data.is_null() {
+                ptr::NonNull::dangling().as_ptr()
+            } else {
+                data
+            };
+            slice::from_raw_parts_mut(data, len)
+        };
+
+        Ok(Tensor { inner: self, data })
+    }
+}
+
+impl fmt::Debug for AnyTensor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("AnyTensor")
+            .field("dtype", &self.dtype())
+            .field("shape", &self.shape)
+            .finish()
+    }
+}
+
+impl Drop for AnyTensor {
+    fn drop(&mut self) {
+        unsafe { tf::TF_DeleteTensor(self.tensor) }
+    }
+}
+
+/// Internally allocated Tensor
+pub struct Tensor<T: 'static> {
+    inner: AnyTensor,
 
     data: &'static mut [T],
 }
 
+unsafe impl<T: Send> Send for Tensor<T> {}
+unsafe impl<T: Sync> Sync for Tensor<T> {}
+
 impl<T> Tensor<T> {
     /// Returns shape of tensor
     ///
@@ -23,7 +299,7 @@ impl<T> Tensor<T> {
     /// assert_eq!(&[2, 2], Tensor::from_slice(&[2, 2], &[1, 2, 3, 4]).unwrap().shape());
     ///```
     pub fn shape(&self) -> &[i64] {
-        &self.shape
+        self.inner.shape()
     }
 }
 
@@ -46,8 +322,10 @@ impl<T: TensorType> Tensor<T> {
         let data = slice::from_raw_parts_mut(tf::TF_TensorData(tensor) as _, len as usize);
 
         Ok(Tensor {
-            tensor,
-            shape: shape.to_vec(),
+            inner: AnyTensor {
+                tensor,
+                shape: shape.to_vec(),
+            },
             data,
         })
     }
@@ -80,12 +358,6 @@ impl<T: TensorType + Copy> Tensor<T> {
     }
 }
 
-impl<T> Drop for Tensor<T> {
-    fn drop(&mut self) {
-        unsafe { tf::TF_DeleteTensor(self.tensor) }
-    }
-}
-
 impl<T> ops::Deref for Tensor<T> {
     type Target = [T];
 
@@ -99,3 +371,15 @@ impl<T> ops::DerefMut for Tensor<T> {
         self.data
     }
 }
+
+impl<T> AsRef<AnyTensor> for Tensor<T> {
+    fn as_ref(&self) -> &AnyTensor {
+        &self.inner
+    }
+}
+
+impl<T> From<Tensor<T>> for AnyTensor {
+    fn from(tensor: Tensor<T>) -> Self {
+        tensor.inner
+    }
+}
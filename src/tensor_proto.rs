@@ -0,0 +1,234 @@
+//! Serializing tensors as `TensorProto` messages, as used by values of
+//! `Const` operations and by checkpoints
+
+use crate::op_def::{decode_shape, encode_shape};
+use crate::tensor_type::data_type_from_raw;
+use crate::wire::{Reader, Value, Writer};
+use crate::{AnyTensor, Error, Result, Tensor, TensorType};
+use tf;
+
+/// Wire encoding of typed value field
+#[derive(Clone, Copy)]
+enum Encoding {
+    Varint,
+    Fixed32,
+    Fixed64,
+}
+
+/// Typed repeated fields holding values of every data type, with number
+/// of field values making single element
+const VALUE_FIELDS: &[(tf::TF_DataType, u32, Encoding, usize)] = &[
+    (tf::TF_FLOAT, 5, Encoding::Fixed32, 1),
+    (tf::TF_DOUBLE, 6, Encoding::Fixed64, 1),
+    (tf::TF_INT32, 7, Encoding::Varint, 1),
+    (tf::TF_INT16, 7, Encoding::Varint, 1),
+    (tf::TF_INT8, 7, Encoding::Varint, 1),
+    (tf::TF_UINT8, 7, Encoding::Varint, 1),
+    (tf::TF_UINT16, 7, Encoding::Varint, 1),
+    (tf::TF_QINT8, 7, Encoding::Varint, 1),
+    (tf::TF_QUINT8, 7, Encoding::Varint, 1),
+    (tf::TF_QINT16, 7, Encoding::Varint, 1),
+    (tf::TF_QUINT16, 7, Encoding::Varint, 1),
+    (tf::TF_QINT32, 7, Encoding::Varint, 1),
+    (tf::TF_COMPLEX64, 9, Encoding::Fixed32, 2),
+    (tf::TF_INT64, 10, Encoding::Varint, 1),
+    (tf::TF_BOOL, 11, Encoding::Varint, 1),
+    (tf::TF_COMPLEX128, 12, Encoding::Fixed64, 2),
+    (tf::TF_HALF, 13, Encoding::Varint, 1),
+    (tf::TF_BFLOAT16, 13, Encoding::Varint, 1),
+    (tf::TF_UINT32, 16, Encoding::Varint, 1),
+    (tf::TF_UINT64, 17, Encoding::Varint, 1),
+];
+
+const STRING_VAL: u32 = 8;
+
+fn invalid(message: impl Into<String>) -> Error {
+    Error::InvalidProtobuf(message.into())
+}
+
+fn decode_values(value: Value, encoding: Encoding) -> Result<Vec<u64>> {
+    match encoding {
+        Encoding::Varint => value.packed_varints(),
+        Encoding::Fixed32 => Ok(value.packed_fixed32()?.into_iter().map(u64::from).collect()),
+        Encoding::Fixed64 => value.packed_fixed64(),
+    }
+}
+
+/// Builds raw tensor data from values of typed field. If there are fewer
+/// values than elements, last element is repeated, and tensor without
+/// values is filled with zeros.
+fn typed_data(
+    dtype: tf::TF_DataType,
+    values: &[u64],
+    components: usize,
+    len: usize,
+) -> Result<Vec<u8>> {
+    if values.len() % components != 0 || values.len() > len * components {
+        return Err(invalid(format!(
+            "tensor of {} elements has {} values",
+            len,
+            values.len()
+        )));
+    }
+
+    let width = unsafe { tf::TF_DataTypeSize(dtype) } / components;
+    let mut data = Vec::with_capacity(len * components * width);
+    for value in values {
+        let value = if dtype == tf::TF_BOOL {
+            (*value != 0) as u64
+        } else {
+            *value
+        };
+        // Values are little endian, possibly sign extended
+        data.extend_from_slice(&value.to_le_bytes()[..width]);
+    }
+
+    let elem_size = components * width;
+    let last = if values.is_empty() {
+        vec![0; elem_size]
+    } else {
+        data[data.len() - elem_size..].to_vec()
+    };
+    while data.len() < len * elem_size {
+        data.extend_from_slice(&last);
+    }
+    Ok(data)
+}
+
+impl AnyTensor {
+    /// Encodes tensor as `TensorProto` message. Numeric values are stored
+    /// in `tensor_content`, strings in `string_val`.
+    ///
+    /// ```rust
+    /// # use rustflow::{AnyTensor, Tensor};
+    /// let tensor: AnyTensor = Tensor::from_slice(&[2], &[1i32, 2]).unwrap().into();
+    /// let proto = tensor.to_tensor_proto().unwrap();
+    ///
+    /// let decoded = AnyTensor::from_tensor_proto(&proto).unwrap();
+    /// assert_eq!(tensor.dtype(), decoded.dtype());
+    /// assert_eq!(tensor.as_bytes(), decoded.as_bytes());
+    /// ```
+    pub fn to_tensor_proto(&self) -> Result<Vec<u8>> {
+        let mut writer = Writer::new();
+        writer.varint(1, self.dtype() as u64);
+        writer.bytes(2, &encode_shape(Some(self.shape())));
+        if self.dtype() == tf::TF_STRING {
+            for value in self.strings()? {
+                writer.bytes(STRING_VAL, &value);
+            }
+        } else if !self.as_bytes().is_empty() {
+            writer.bytes(4, self.as_bytes());
+        }
+        Ok(writer.into_bytes())
+    }
+
+    /// Decodes tensor from `TensorProto` message. Values may be stored
+    /// either in `tensor_content`, or in typed field of tensor data type
+    /// (like `float_val`). Typed fields may hold fewer values than tensor
+    /// has elements, in which case the last value is repeated - scalar
+    /// value fills whole tensor.
+    ///
+    /// ```rust
+    /// # use rustflow::{AnyTensor, TensorType};
+    /// // float tensor of shape [2, 2] with single float_val 3.0
+    /// let proto = [
+    ///     0x08, 0x01, 0x12, 0x08, 0x12, 0x02, 0x08, 0x02, 0x12, 0x02, 0x08, 0x02,
+    ///     0x2a, 0x04, 0x00, 0x00, 0x40, 0x40,
+    /// ];
+    /// let tensor = AnyTensor::from_tensor_proto(&proto).unwrap();
+    /// assert_eq!(f32::TF_TYPE, tensor.dtype());
+    /// assert_eq!(&[2, 2], tensor.shape());
+    /// assert_eq!(&[3.0; 4], &tensor.downcast::<f32>().unwrap()[..]);
+    /// ```
+    pub fn from_tensor_proto(data: &[u8]) -> Result<Self> {
+        let mut dtype = None;
+        let mut shape = vec![];
+        let mut content = None;
+        let mut fields = vec![];
+        for field in Reader::new(data) {
+            match field? {
+                (1, value) => {
+                    let raw = value.varint()? as i32;
+                    dtype = Some(
+                        data_type_from_raw(raw)
+                            .ok_or_else(|| invalid(format!("unknown data type {}", raw)))?,
+                    );
+                }
+                (2, value) => {
+                    shape = decode_shape(value.bytes()?)?
+                        .ok_or_else(|| invalid("tensor shape has unknown rank"))?;
+                }
+                (4, value) => content = Some(value.bytes()?),
+                field => fields.push(field),
+            }
+        }
+
+        let dtype = dtype.ok_or_else(|| invalid("tensor has no data type"))?;
+        if shape.iter().any(|size| *size < 0) {
+            return Err(invalid("tensor shape has unknown dimensions"));
+        }
+        let len = shape.iter().product::<i64>() as usize;
+
+        if dtype == tf::TF_STRING {
+            let values = fields
+                .into_iter()
+                .filter(|(field, _)| *field == STRING_VAL)
+                .map(|(_, value)| value.bytes())
+                .collect::<Result<Vec<_>>>()?;
+            if values.len() > len {
+                return Err(invalid(format!(
+                    "tensor of {} elements has {} values",
+                    len,
+                    values.len()
+                )));
+            }
+            let last = values.last().copied().unwrap_or(&[]);
+            let values: Vec<_> = values
+                .iter()
+                .copied()
+                .chain(std::iter::repeat(last))
+                .take(len)
+                .collect();
+            return AnyTensor::from_strings(&shape, &values);
+        }
+
+        if let Some(content) = content {
+            return AnyTensor::from_bytes(dtype, &shape, content);
+        }
+
+        let (_, value_field, encoding, components) = *VALUE_FIELDS
+            .iter()
+            .find(|(known, ..)| *known == dtype)
+            .ok_or(Error::UnsupportedType(dtype))?;
+        let mut values = vec![];
+        for (field, value) in fields {
+            if field == value_field {
+                values.extend(decode_values(value, encoding)?);
+            }
+        }
+        let data = typed_data(dtype, &values, components, len)?;
+        AnyTensor::from_bytes(dtype, &shape, &data)
+    }
+}
+
+impl<T: TensorType> Tensor<T> {
+    /// Decodes tensor from `TensorProto` message, as
+    /// `AnyTensor::from_tensor_proto` does. If message holds tensor of
+    /// other type than `T`, `Error::TypeMismatch` is returned.
+    pub fn from_tensor_proto(data: &[u8]) -> Result<Self> {
+        AnyTensor::from_tensor_proto(data)?
+            .downcast()
+            .map_err(|tensor| Error::TypeMismatch {
+                expected: T::TF_TYPE,
+                found: tensor.dtype(),
+            })
+    }
+}
+
+impl<T> Tensor<T> {
+    /// Encodes tensor as `TensorProto` message, as
+    /// `AnyTensor::to_tensor_proto` does
+    pub fn to_tensor_proto(&self) -> Result<Vec<u8>> {
+        self.as_ref().to_tensor_proto()
+    }
+}
@@ -1,3 +1,4 @@
+use std::os::raw::c_int;
 use tf;
 
 pub unsafe trait TensorType {
@@ -24,3 +25,57 @@ tensor_type!(u8: tf::TF_UINT8);
 tensor_type!(u16: tf::TF_UINT16);
 tensor_type!(u32: tf::TF_UINT32);
 tensor_type!(u64: tf::TF_UINT64);
+
+/// All data types known to tensorflow, with their names
+const DATA_TYPES: &[(tf::TF_DataType, &str)] = &[
+    (tf::TF_FLOAT, "float"),
+    (tf::TF_DOUBLE, "double"),
+    (tf::TF_INT32, "int32"),
+    (tf::TF_UINT8, "uint8"),
+    (tf::TF_INT16, "int16"),
+    (tf::TF_INT8, "int8"),
+    (tf::TF_STRING, "string"),
+    (tf::TF_COMPLEX64, "complex64"),
+    (tf::TF_INT64, "int64"),
+    (tf::TF_BOOL, "bool"),
+    (tf::TF_QINT8, "qint8"),
+    (tf::TF_QUINT8, "quint8"),
+    (tf::TF_QINT32, "qint32"),
+    (tf::TF_BFLOAT16, "bfloat16"),
+    (tf::TF_QINT16, "qint16"),
+    (tf::TF_QUINT16, "quint16"),
+    (tf::TF_UINT16, "uint16"),
+    (tf::TF_COMPLEX128, "complex128"),
+    (tf::TF_HALF, "half"),
+    (tf::TF_RESOURCE, "resource"),
+    (tf::TF_VARIANT, "variant"),
+    (tf::TF_UINT32, "uint32"),
+    (tf::TF_UINT64, "uint64"),
+];
+
+/// Converts raw tensorflow `DataType` value into `TF_DataType`. Reference
+/// types (like outputs of variables) are converted to their base types.
+pub(crate) fn data_type_from_raw(value: c_int) -> Option<tf::TF_DataType> {
+    // Reference types are base types shifted by 100
+    let value = if value > 100 { value - 100 } else { value };
+    DATA_TYPES
+        .iter()
+        .map(|(dtype, _)| *dtype)
+        .find(|dtype| *dtype as c_int == value)
+}
+
+/// Returns name of data type, as used by tensorflow (like `float` or
+/// `int32`)
+///
+/// ```rust
+/// # use rustflow::TensorType;
+/// # use rustflow::tensor_type::data_type_name;
+/// assert_eq!("float", data_type_name(f32::TF_TYPE));
+/// assert_eq!("uint8", data_type_name(u8::TF_TYPE));
+/// ```
+pub fn data_type_name(dtype: tf::TF_DataType) -> &'static str {
+    DATA_TYPES
+        .iter()
+        .find(|(known, _)| *known == dtype)
+        .map_or("unknown", |(_, name)| name)
+}
@@ -0,0 +1,461 @@
+//! Helpers for training models from rust: variables, optimizers and
+//! checkpoints saving. All helpers are adding operations to graph, which
+//! are then run in session.
+
+use crate::{AnyTensor, Error, Graph, Operation, Output, Result, Session, Tensor};
+use tf;
+
+/// Adds `Const` operation with given value
+fn constant<'a>(graph: &'a Graph, name: &str, value: &AnyTensor) -> Result<Operation<'a>> {
+    graph
+        .new_operation("Const", name)?
+        .set_attr_tensor("value", value)?
+        .set_attr_type("dtype", value.dtype())?
+        .finish()
+}
+
+/// Creates floating point scalar tensor of given type
+fn scalar(dtype: tf::TF_DataType, value: f64) -> Result<AnyTensor> {
+    match dtype {
+        tf::TF_FLOAT => Ok(Tensor::from_slice(&[], &[value as f32])?.into()),
+        tf::TF_DOUBLE => Ok(Tensor::from_slice(&[], &[value])?.into()),
+        dtype => Err(Error::UnsupportedType(dtype)),
+    }
+}
+
+/// Adds operation which does nothing, but waits for all `operations`
+fn group<'a>(graph: &'a Graph, name: &str, operations: &[Operation<'a>]) -> Result<Operation<'a>> {
+    operations
+        .iter()
+        .fold(graph.new_operation("NoOp", name)?, |op, dep| {
+            op.add_control_input(*dep)
+        })
+        .finish()
+}
+
+fn first_output(operation: Operation) -> Output {
+    // Every operation created by this module has at least one output
+    operation.output(0).unwrap()
+}
+
+/// Mutable graph state (`VariableV2` operation) together with its
+/// initializer
+#[derive(Clone)]
+pub struct Variable<'a> {
+    variable: Operation<'a>,
+    initializer: Operation<'a>,
+    dtype: tf::TF_DataType,
+    shape: Vec<i64>,
+}
+
+impl<'a> Variable<'a> {
+    /// Creates variable initialized with given value
+    ///
+    /// ```rust
+    /// # use rustflow::train::Variable;
+    /// # use rustflow::{Graph, Tensor};
+    /// let graph = Graph::new();
+    /// let initial = Tensor::from_slice(&[2], &[1.0f32, 2.0]).unwrap();
+    /// let variable = Variable::new(&graph, "w", initial.as_ref()).unwrap();
+    /// assert_eq!("w", variable.name());
+    /// assert_eq!(&[2], variable.shape());
+    /// ```
+    pub fn new(graph: &'a Graph, name: &str, initial_value: &AnyTensor) -> Result<Self> {
+        let value = constant(graph, &format!("{}/initial_value", name), initial_value)?;
+        Self::with_initializer(
+            graph,
+            name,
+            initial_value.dtype(),
+            initial_value.shape(),
+            first_output(value),
+        )
+    }
+
+    /// Creates variable initialized with value of given output, for example
+    /// random initializer
+    pub fn with_initializer(
+        graph: &'a Graph,
+        name: &str,
+        dtype: tf::TF_DataType,
+        shape: &[i64],
+        initial_value: Output<'a>,
+    ) -> Result<Self> {
+        let variable = graph
+            .new_operation("VariableV2", name)?
+            .set_attr_type("dtype", dtype)?
+            .set_attr_shape("shape", Some(shape))?
+            .finish()?;
+        let initializer = graph
+            .new_operation("Assign", &format!("{}/Assign", name))?
+            .add_input(first_output(variable))
+            .add_input(initial_value)
+            .set_attr_type("T", dtype)?
+            .finish()?;
+
+        Ok(Variable {
+            variable,
+            initializer,
+            dtype,
+            shape: shape.to_vec(),
+        })
+    }
+
+    /// Returns variable name
+    pub fn name(&self) -> &str {
+        self.variable.name()
+    }
+
+    /// Returns output with variable value, which can be used as input for
+    /// other operations, and fetched from session
+    pub fn output(&self) -> Output<'a> {
+        first_output(self.variable)
+    }
+
+    /// Returns operation which assigns initial value to variable
+    pub fn initializer(&self) -> Operation<'a> {
+        self.initializer
+    }
+
+    /// Returns data type of variable value
+    pub fn dtype(&self) -> tf::TF_DataType {
+        self.dtype
+    }
+
+    /// Returns shape of variable value
+    pub fn shape(&self) -> &[i64] {
+        &self.shape
+    }
+}
+
+/// Adds operation initializing all given variables, which should be run
+/// as target before any other use of the variables
+pub fn initializer<'a>(
+    graph: &'a Graph,
+    name: &str,
+    variables: &[Variable<'a>],
+) -> Result<Operation<'a>> {
+    let initializers: Vec<_> = variables.iter().map(Variable::initializer).collect();
+    group(graph, name, &initializers)
+}
+
+/// Operations added to graph by optimizer
+pub struct TrainOp<'a> {
+    operation: Operation<'a>,
+    variables: Vec<Variable<'a>>,
+}
+
+impl<'a> TrainOp<'a> {
+    /// Returns operation performing single training step, to be run as
+    /// session target
+    pub fn operation(&self) -> Operation<'a> {
+        self.operation
+    }
+
+    /// Returns variables created by optimizer for its own state, which
+    /// have to be initialized together with model variables
+    pub fn variables(&self) -> &[Variable<'a>] {
+        &self.variables
+    }
+}
+
+/// Algorithm updating variables to minimize loss
+pub trait Optimizer {
+    /// Adds operations updating `variables` to minimize `loss`. All added
+    /// operations are named with `name` prefix.
+    fn minimize<'a>(
+        &self,
+        graph: &'a Graph,
+        name: &str,
+        loss: Output<'a>,
+        variables: &[Variable<'a>],
+    ) -> Result<TrainOp<'a>>;
+}
+
+fn gradients<'a>(
+    graph: &'a Graph,
+    name: &str,
+    loss: Output<'a>,
+    variables: &[Variable<'a>],
+) -> Result<Vec<Output<'a>>> {
+    let outputs: Vec<_> = variables.iter().map(Variable::output).collect();
+    graph.add_gradients_with_prefix(&format!("{}/gradients", name), &[loss], &outputs, None)
+}
+
+/// Stochastic gradient descent optimizer
+#[derive(Debug, Clone)]
+pub struct GradientDescent {
+    pub learning_rate: f64,
+}
+
+impl GradientDescent {
+    pub fn new(learning_rate: f64) -> Self {
+        GradientDescent { learning_rate }
+    }
+}
+
+impl Optimizer for GradientDescent {
+    fn minimize<'a>(
+        &self,
+        graph: &'a Graph,
+        name: &str,
+        loss: Output<'a>,
+        variables: &[Variable<'a>],
+    ) -> Result<TrainOp<'a>> {
+        let grads = gradients(graph, name, loss, variables)?;
+
+        let updates = variables
+            .iter()
+            .zip(grads)
+            .map(|(variable, grad)| {
+                let prefix = format!("{}/{}", name, variable.name());
+                let learning_rate = constant(
+                    graph,
+                    &format!("{}/learning_rate", prefix),
+                    &scalar(variable.dtype(), self.learning_rate)?,
+                )?;
+
+                graph
+                    .new_operation("ApplyGradientDescent", &format!("{}/update", prefix))?
+                    .add_input(variable.output())
+                    .add_input(first_output(learning_rate))
+                    .add_input(grad)
+                    .set_attr_type("T", variable.dtype())?
+                    .finish()
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(TrainOp {
+            operation: group(graph, name, &updates)?,
+            variables: vec![],
+        })
+    }
+}
+
+/// Adam optimizer
+#[derive(Debug, Clone)]
+pub struct Adam {
+    pub learning_rate: f64,
+    pub beta1: f64,
+    pub beta2: f64,
+    pub epsilon: f64,
+}
+
+impl Adam {
+    /// Creates optimizer with given learning rate, and default values of
+    /// other parameters
+    pub fn new(learning_rate: f64) -> Self {
+        Adam {
+            learning_rate,
+            beta1: 0.9,
+            beta2: 0.999,
+            epsilon: 1e-8,
+        }
+    }
+}
+
+impl Optimizer for Adam {
+    fn minimize<'a>(
+        &self,
+        graph: &'a Graph,
+        name: &str,
+        loss: Output<'a>,
+        variables: &[Variable<'a>],
+    ) -> Result<TrainOp<'a>> {
+        let dtype = match variables.first() {
+            Some(variable) => variable.dtype(),
+            None => {
+                return Ok(TrainOp {
+                    operation: group(graph, name, &[])?,
+                    variables: vec![],
+                })
+            }
+        };
+
+        let grads = gradients(graph, name, loss, variables)?;
+        let constant = |name: &str, dtype, value: f64| -> Result<Output<'a>> {
+            let op = constant(graph, name, &scalar(dtype, value)?)?;
+            Ok(first_output(op))
+        };
+
+        // Like in tensorflow, beta powers are kept in type of first
+        // variable, and cast to types of other variables
+        let beta1_power = Variable::new(
+            graph,
+            &format!("{}/beta1_power", name),
+            &scalar(dtype, self.beta1)?,
+        )?;
+        let beta2_power = Variable::new(
+            graph,
+            &format!("{}/beta2_power", name),
+            &scalar(dtype, self.beta2)?,
+        )?;
+        let mut slots = vec![beta1_power.clone(), beta2_power.clone()];
+        let cast = |name: &str, power: &Variable<'a>, dtype| -> Result<Output<'a>> {
+            if dtype == power.dtype() {
+                return Ok(power.output());
+            }
+            let cast = graph
+                .new_operation("Cast", name)?
+                .add_input(power.output())
+                .set_attr_type("SrcT", power.dtype())?
+                .set_attr_type("DstT", dtype)?
+                .finish()?;
+            Ok(first_output(cast))
+        };
+
+        let mut updates = vec![];
+        for (variable, grad) in variables.iter().zip(grads) {
+            let prefix = format!("{}/{}", name, variable.name());
+            let var_dtype = variable.dtype();
+            let size: i64 = variable.shape().iter().product();
+            let zeros = vec![0u8; size as usize * unsafe { tf::TF_DataTypeSize(var_dtype) }];
+            let zeros = AnyTensor::from_bytes(var_dtype, variable.shape(), &zeros)?;
+            let m = Variable::new(graph, &format!("{}/m", prefix), &zeros)?;
+            let v = Variable::new(graph, &format!("{}/v", prefix), &zeros)?;
+
+            let param =
+                |param: &str, value| constant(&format!("{}/{}", prefix, param), var_dtype, value);
+            let power =
+                |param: &str, power| cast(&format!("{}/{}", prefix, param), power, var_dtype);
+
+            let update = graph
+                .new_operation("ApplyAdam", &format!("{}/update", prefix))?
+                .add_input(variable.output())
+                .add_input(m.output())
+                .add_input(v.output())
+                .add_input(power("beta1_power", &beta1_power)?)
+                .add_input(power("beta2_power", &beta2_power)?)
+                .add_input(param("learning_rate", self.learning_rate)?)
+                .add_input(param("beta1", self.beta1)?)
+                .add_input(param("beta2", self.beta2)?)
+                .add_input(param("epsilon", self.epsilon)?)
+                .add_input(grad)
+                .set_attr_type("T", var_dtype)?
+                .finish()?;
+
+            updates.push(update);
+            slots.push(m);
+            slots.push(v);
+        }
+
+        let beta1 = constant(&format!("{}/beta1", name), dtype, self.beta1)?;
+        let beta2 = constant(&format!("{}/beta2", name), dtype, self.beta2)?;
+
+        // Beta powers are updated once per step, after all variables are
+        // updated
+        let power_updates = [(&beta1_power, beta1), (&beta2_power, beta2)]
+            .iter()
+            .map(|(power, beta)| {
+                let mul = updates
+                    .iter()
+                    .fold(
+                        graph.new_operation("Mul", &format!("{}/update", power.name()))?,
+                        |op, dep| op.add_control_input(*dep),
+                    )
+                    .add_input(power.output())
+                    .add_input(*beta)
+                    .set_attr_type("T", dtype)?
+                    .finish()?;
+
+                graph
+                    .new_operation("Assign", &format!("{}/assign", power.name()))?
+                    .add_input(power.output())
+                    .add_input(first_output(mul))
+                    .set_attr_type("T", dtype)?
+                    .finish()
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(TrainOp {
+            operation: group(graph, name, &power_updates)?,
+            variables: slots,
+        })
+    }
+}
+
+/// Operations saving and restoring variables values to and from checkpoint
+/// on local disk
+pub struct Saver<'a> {
+    prefix: Output<'a>,
+    save: Operation<'a>,
+    restore: Operation<'a>,
+}
+
+impl<'a> Saver<'a> {
+    /// Adds operations saving and restoring given variables. Variables are
+    /// stored in checkpoint under their names.
+    pub fn new(graph: &'a Graph, name: &str, variables: &[Variable<'a>]) -> Result<Self> {
+        let prefix = graph
+            .new_operation("Placeholder", &format!("{}/prefix", name))?
+            .set_attr_type("dtype", tf::TF_STRING)?
+            .set_attr_shape("shape", Some(&[]))?
+            .finish()?;
+        let prefix = first_output(prefix);
+
+        let len = variables.len() as i64;
+        let names: Vec<_> = variables.iter().map(Variable::name).collect();
+        let names = constant(
+            graph,
+            &format!("{}/tensor_names", name),
+            &AnyTensor::from_strings(&[len], &names)?,
+        )?;
+        let slices: Vec<_> = variables.iter().map(|_| "").collect();
+        let slices = constant(
+            graph,
+            &format!("{}/shape_and_slices", name),
+            &AnyTensor::from_strings(&[len], &slices)?,
+        )?;
+        let dtypes: Vec<_> = variables.iter().map(Variable::dtype).collect();
+        let outputs: Vec<_> = variables.iter().map(Variable::output).collect();
+
+        let save = graph
+            .new_operation("SaveV2", &format!("{}/save", name))?
+            .add_input(prefix)
+            .add_input(first_output(names))
+            .add_input(first_output(slices))
+            .add_input_list(&outputs)
+            .set_attr_type_list("dtypes", &dtypes)?
+            .finish()?;
+
+        let restored = graph
+            .new_operation("RestoreV2", &format!("{}/restore_v2", name))?
+            .add_input(prefix)
+            .add_input(first_output(names))
+            .add_input(first_output(slices))
+            .set_attr_type_list("dtypes", &dtypes)?
+            .finish()?;
+        let assigns = variables
+            .iter()
+            .zip(restored.outputs())
+            .map(|(variable, value)| {
+                graph
+                    .new_operation("Assign", &format!("{}/restore/{}", name, variable.name()))?
+                    .add_input(variable.output())
+                    .add_input(value)
+                    .set_attr_type("T", variable.dtype())?
+                    .finish()
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let restore = group(graph, &format!("{}/restore", name), &assigns)?;
+
+        Ok(Saver {
+            prefix,
+            save,
+            restore,
+        })
+    }
+
+    /// Saves variables values to checkpoint with given path prefix
+    pub fn save(&self, session: &Session, prefix: &str) -> Result<()> {
+        let prefix = AnyTensor::from_strings(&[], &[prefix])?;
+        session.run(&[(self.prefix, &prefix)], &[], &[self.save])?;
+        Ok(())
+    }
+
+    /// Restores variables values from checkpoint with given path prefix
+    pub fn restore(&self, session: &Session, prefix: &str) -> Result<()> {
+        let prefix = AnyTensor::from_strings(&[], &[prefix])?;
+        session.run(&[(self.prefix, &prefix)], &[], &[self.restore])?;
+        Ok(())
+    }
+}
@@ -0,0 +1,453 @@
+//! Graph transformations - rewriting operations matching patterns, and
+//! built-in passes cleaning up graphs before deployment
+//!
+//! Transformations work on graph definition, so they can change graphs
+//! which cannot be edited in place, and produce new graph when finished.
+//!
+//! ```rust
+//! # use rustflow::{Graph, TensorType};
+//! # use rustflow::transform::GraphTransform;
+//! let graph = Graph::new();
+//! let x = graph
+//!     .new_operation("Placeholder", "x").unwrap()
+//!     .set_attr_type("dtype", f32::TF_TYPE).unwrap()
+//!     .finish().unwrap();
+//! let id = graph
+//!     .new_operation("Identity", "id").unwrap()
+//!     .add_input(x.output(0).unwrap())
+//!     .finish().unwrap();
+//! graph
+//!     .new_operation("Neg", "neg").unwrap()
+//!     .add_input(id.output(0).unwrap())
+//!     .finish().unwrap();
+//!
+//! let transformed = GraphTransform::new(&graph, &["neg"]).unwrap()
+//!     .remove_identity().unwrap()
+//!     .set_device("/cpu:0").unwrap()
+//!     .finish().unwrap();
+//! assert!(transformed.operation_by_name("id").is_none());
+//! let neg = transformed.operation_by_name("neg").unwrap();
+//! assert_eq!("x", neg.inputs().next().unwrap().producer().operation().name());
+//! ```
+
+use crate::graph::OwnedGraph;
+use crate::graph_def::GraphDef;
+use crate::op_def::AttrValue;
+use crate::{Error, Graph, Result};
+use std::collections::{HashMap, HashSet};
+
+pub use crate::graph_def::{InputRef, NodeDef};
+
+/// Pattern matching operation by type, and optionally its data inputs
+///
+/// Type `"*"` matches any operation, and alternatives can be given
+/// separated by `|`. Pattern without inputs matches regardless of
+/// operation inputs, otherwise operation has to have exactly as many data
+/// inputs as pattern, each matching respective input pattern.
+///
+/// ```rust
+/// # use rustflow::transform::Pattern;
+/// // Matches `BiasAdd` or `Add` of `MatMul` result and any other value
+/// let pattern = Pattern::new("BiasAdd|Add")
+///     .with_inputs(vec![Pattern::new("MatMul"), Pattern::new("*")]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Pattern {
+    op: String,
+    inputs: Vec<Pattern>,
+}
+
+impl Pattern {
+    /// Creates pattern matching operations of given type
+    pub fn new(op: &str) -> Self {
+        Pattern {
+            op: op.to_owned(),
+            inputs: vec![],
+        }
+    }
+
+    /// Sets patterns for operation data inputs
+    pub fn with_inputs(mut self, inputs: Vec<Pattern>) -> Self {
+        self.inputs = inputs;
+        self
+    }
+
+    fn is_wildcard(&self) -> bool {
+        self.op == "*"
+    }
+
+    fn matches_op(&self, op: &str) -> bool {
+        self.is_wildcard() || self.op.split('|').any(|alt| alt == op)
+    }
+}
+
+/// Operation matched by `Pattern`, together with operations matched by
+/// input patterns
+#[derive(Debug, Clone)]
+pub struct Match {
+    /// Matched operation
+    pub node: NodeDef,
+    /// Matches of input patterns, in order of pattern inputs
+    pub inputs: Vec<Match>,
+}
+
+impl Match {
+    /// Collects names of operations matched by `pattern` other than `"*"`
+    fn collect_names<'a>(&'a self, pattern: &Pattern, names: &mut HashSet<&'a str>) {
+        if !pattern.is_wildcard() {
+            names.insert(&self.node.name);
+        }
+        for (input, pattern) in self.inputs.iter().zip(&pattern.inputs) {
+            input.collect_names(pattern, names);
+        }
+    }
+}
+
+/// Returns data inputs of node, skipping control dependencies
+fn data_inputs(node: &NodeDef) -> impl Iterator<Item = InputRef> + '_ {
+    node.inputs
+        .iter()
+        .map(|input| InputRef::parse(input))
+        .filter(|input| matches!(input, InputRef::Data { .. }))
+}
+
+/// Transformation of graph, made of passes applied in order in which
+/// builder methods are called
+///
+/// Transformation keeps track of graph outputs, which are never removed
+/// (except by `replace_matches` callbacks) and are renamed together with
+/// operations.
+#[derive(Debug)]
+pub struct GraphTransform {
+    graph_def: GraphDef,
+    outputs: Vec<String>,
+}
+
+impl GraphTransform {
+    /// Starts transformation of `graph`, with operations of given names
+    /// being graph outputs
+    pub fn new(graph: &Graph, outputs: &[&str]) -> Result<Self> {
+        Ok(GraphTransform {
+            graph_def: GraphDef::from_graph(graph)?,
+            outputs: outputs
+                .iter()
+                .map(|output| InputRef::parse(output).node().to_owned())
+                .collect(),
+        })
+    }
+
+    /// Returns operations of transformed graph
+    pub fn nodes(&self) -> &[NodeDef] {
+        &self.graph_def.nodes
+    }
+
+    fn find_match(
+        &self,
+        pattern: &Pattern,
+        name: &str,
+        index: &HashMap<&str, usize>,
+        matched: &HashSet<String>,
+    ) -> Option<Match> {
+        // Operations matched by `"*"` may be shared by several matches
+        if !pattern.is_wildcard() && matched.contains(name) {
+            return None;
+        }
+        let node = &self.graph_def.nodes[*index.get(name)?];
+        if !pattern.matches_op(&node.op) {
+            return None;
+        }
+
+        let mut inputs = vec![];
+        if !pattern.inputs.is_empty() {
+            let node_inputs: Vec<_> = data_inputs(node).collect();
+            if node_inputs.len() != pattern.inputs.len() {
+                return None;
+            }
+            for (pattern, input) in pattern.inputs.iter().zip(node_inputs) {
+                inputs.push(self.find_match(pattern, input.node(), index, matched)?);
+            }
+        }
+
+        Some(Match {
+            node: node.clone(),
+            inputs,
+        })
+    }
+
+    /// Replaces operations matching `pattern` by operations returned by
+    /// `replace`
+    ///
+    /// Matches do not overlap - operation matched once is not considered
+    /// again, except for operations matched by `"*"`, which may be inputs
+    /// of several matches. Operations of match are removed, except for
+    /// those matched by `"*"`, still used by operations outside of match,
+    /// or being graph outputs - these are kept, unless `replace` returns
+    /// operation of the same name. Returned operations usually have the
+    /// same name as matched root operation, so its consumers use them
+    /// instead.
+    ///
+    /// ```rust
+    /// # use rustflow::{Graph, TensorType};
+    /// # use rustflow::transform::{GraphTransform, NodeDef, Pattern};
+    /// let graph = Graph::new();
+    /// let x = graph
+    ///     .new_operation("Placeholder", "x").unwrap()
+    ///     .set_attr_type("dtype", f32::TF_TYPE).unwrap()
+    ///     .finish().unwrap();
+    /// for name in &["a", "b"] {
+    ///     let neg = graph
+    ///         .new_operation("Neg", &format!("{}/neg", name)).unwrap()
+    ///         .add_input(x.output(0).unwrap())
+    ///         .finish().unwrap();
+    ///     graph
+    ///         .new_operation("Neg", &format!("{}/neg2", name)).unwrap()
+    ///         .add_input(neg.output(0).unwrap())
+    ///         .finish().unwrap();
+    /// }
+    ///
+    /// // Double negation is an identity
+    /// let pattern = Pattern::new("Neg").with_inputs(vec![
+    ///     Pattern::new("Neg").with_inputs(vec![Pattern::new("*")]),
+    /// ]);
+    /// let transformed = GraphTransform::new(&graph, &["a/neg2", "b/neg2"]).unwrap()
+    ///     .replace_matches(&pattern, |m| {
+    ///         let mut node = NodeDef::new(&m.node.name, "Identity");
+    ///         node.inputs = m.inputs[0].node.inputs.clone();
+    ///         node.set_attr("T", &m.node.attr("T")?.unwrap());
+    ///         Ok(vec![node])
+    ///     }).unwrap()
+    ///     .finish().unwrap();
+    /// for name in &["a", "b"] {
+    ///     let neg2 = transformed.operation_by_name(&format!("{}/neg2", name)).unwrap();
+    ///     assert_eq!("Identity", neg2.op_type());
+    ///     assert!(transformed.operation_by_name(&format!("{}/neg", name)).is_none());
+    /// }
+    /// // Input matched by "*" is kept
+    /// assert!(transformed.operation_by_name("x").is_some());
+    /// ```
+    pub fn replace_matches<F>(mut self, pattern: &Pattern, mut replace: F) -> Result<Self>
+    where
+        F: FnMut(&Match) -> Result<Vec<NodeDef>>,
+    {
+        let nodes = &self.graph_def.nodes;
+        let index: HashMap<&str, usize> = nodes
+            .iter()
+            .enumerate()
+            .map(|(i, node)| (&node.name[..], i))
+            .collect();
+        let mut consumers: HashMap<&str, Vec<&str>> = HashMap::new();
+        for node in nodes {
+            for input in &node.inputs {
+                let input = InputRef::parse(input);
+                if let Some(&i) = index.get(input.node()) {
+                    consumers
+                        .entry(&nodes[i].name)
+                        .or_default()
+                        .push(&node.name);
+                }
+            }
+        }
+
+        let mut matched = HashSet::new();
+        let mut removed = HashSet::new();
+        let mut replacements = HashMap::new();
+        for node in nodes {
+            let found = match self.find_match(pattern, &node.name, &index, &matched) {
+                Some(found) => found,
+                None => continue,
+            };
+            let mut names = HashSet::new();
+            names.insert(&node.name[..]);
+            found.collect_names(pattern, &mut names);
+            let new_nodes = replace(&found)?;
+            let new_names: HashSet<_> = new_nodes.iter().map(|node| &node.name[..]).collect();
+
+            for &name in &names {
+                matched.insert(name.to_owned());
+                let used_outside = self.outputs.iter().any(|output| output == name)
+                    || consumers
+                        .get(name)
+                        .into_iter()
+                        .flatten()
+                        .any(|user| !names.contains(user));
+                if name == node.name || new_names.contains(name) || !used_outside {
+                    removed.insert(name.to_owned());
+                }
+            }
+            // Operations matched by "*" are replaced only when returned
+            removed.extend(
+                new_names
+                    .iter()
+                    .filter(|name| index.contains_key(*name))
+                    .map(|name| name.to_string()),
+            );
+            replacements.insert(node.name.clone(), new_nodes);
+        }
+
+        let old_nodes = std::mem::take(&mut self.graph_def.nodes);
+        for node in old_nodes {
+            if let Some(new_nodes) = replacements.remove(&node.name) {
+                self.graph_def.nodes.extend(new_nodes);
+            } else if !removed.contains(&node.name) {
+                self.graph_def.nodes.push(node);
+            }
+        }
+        Ok(self)
+    }
+
+    /// Removes `Identity` operations, connecting their consumers directly
+    /// to identity inputs
+    ///
+    /// Identities being graph outputs, having control dependencies or
+    /// following `Switch` (used by control flow) are kept.
+    pub fn remove_identity(mut self) -> Result<Self> {
+        let ops: HashMap<_, _> = self
+            .graph_def
+            .nodes
+            .iter()
+            .map(|node| (node.name.clone(), node.op.clone()))
+            .collect();
+        let mut identities = HashMap::new();
+        for node in &self.graph_def.nodes {
+            if node.op != "Identity" || self.outputs.contains(&node.name) {
+                continue;
+            }
+            let inputs: Vec<_> = node
+                .inputs
+                .iter()
+                .map(|input| InputRef::parse(input))
+                .collect();
+            if let [input @ InputRef::Data { .. }] = &inputs[..] {
+                if ops.get(input.node()).map(String::as_str) != Some("Switch") {
+                    identities.insert(node.name.clone(), input.clone());
+                }
+            }
+        }
+
+        let resolve = |mut input: InputRef| {
+            while let Some(source) = identities.get(input.node()) {
+                input = match input {
+                    InputRef::Data { .. } => source.clone(),
+                    InputRef::Control(_) => InputRef::Control(source.node().to_owned()),
+                };
+            }
+            input
+        };
+        self.graph_def
+            .nodes
+            .retain(|node| !identities.contains_key(&node.name));
+        for node in &mut self.graph_def.nodes {
+            for input in &mut node.inputs {
+                *input = resolve(InputRef::parse(input)).to_string();
+            }
+        }
+        Ok(self)
+    }
+
+    /// Removes operations not needed to compute graph outputs
+    pub fn strip_unused_nodes(mut self) -> Result<Self> {
+        let index: HashMap<_, _> = self
+            .graph_def
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(i, node)| (&node.name[..], i))
+            .collect();
+        let mut needed = HashSet::new();
+        let mut stack = self.outputs.clone();
+        while let Some(name) = stack.pop() {
+            let node = match index.get(&name[..]) {
+                Some(&i) => &self.graph_def.nodes[i],
+                None => return Err(Error::InvalidGraph(format!("{} not found", name))),
+            };
+            stack.extend(
+                node.inputs
+                    .iter()
+                    .map(|input| InputRef::parse(input).node().to_owned())
+                    .filter(|input| !needed.contains(input)),
+            );
+            needed.insert(name);
+        }
+
+        self.graph_def
+            .nodes
+            .retain(|node| needed.contains(&node.name));
+        Ok(self)
+    }
+
+    /// Moves operations from `old` name scope to `new` one. Operation is in
+    /// scope if its name is the same as scope, or starts with scope followed
+    /// by `/`, so renaming scope `a` doesn't rename operation `ab`. Empty
+    /// scope is the root scope, containing all operations, and trailing `/`
+    /// of scopes is ignored.
+    pub fn rename_prefix(mut self, old: &str, new: &str) -> Result<Self> {
+        let old = old.trim_end_matches('/');
+        let new = new.trim_end_matches('/');
+        let rename = |name: &str| {
+            let rest = if old.is_empty() {
+                Some(name)
+            } else if name == old {
+                Some("")
+            } else {
+                name.strip_prefix(old)
+                    .and_then(|rest| rest.strip_prefix('/'))
+            };
+            match rest {
+                Some(rest) if rest.is_empty() || new.is_empty() => format!("{}{}", new, rest),
+                Some(rest) => format!("{}/{}", new, rest),
+                None => name.to_owned(),
+            }
+        };
+
+        for node in &mut self.graph_def.nodes {
+            node.name = rename(&node.name);
+            for input in &mut node.inputs {
+                *input = match InputRef::parse(input) {
+                    InputRef::Data { node, index } => InputRef::Data {
+                        node: rename(&node),
+                        index,
+                    },
+                    InputRef::Control(node) => InputRef::Control(rename(&node)),
+                }
+                .to_string();
+            }
+
+            // Colocation constraints refer to operations by name
+            if let Some(AttrValue::List(locations)) = node.attr("_class")? {
+                let locations = locations
+                    .into_iter()
+                    .map(|location| match location {
+                        AttrValue::String(value) => {
+                            let value = String::from_utf8_lossy(&value).into_owned();
+                            let value = match value.strip_prefix("loc:@") {
+                                Some(name) => format!("loc:@{}", rename(name)),
+                                None => value,
+                            };
+                            AttrValue::String(value.into_bytes())
+                        }
+                        other => other,
+                    })
+                    .collect();
+                node.set_attr("_class", &AttrValue::List(locations));
+            }
+        }
+
+        for output in &mut self.outputs {
+            *output = rename(output);
+        }
+        Ok(self)
+    }
+
+    /// Places all operations on given device
+    pub fn set_device(mut self, device: &str) -> Result<Self> {
+        for node in &mut self.graph_def.nodes {
+            node.device = device.to_owned();
+        }
+        Ok(self)
+    }
+
+    /// Creates transformed graph
+    pub fn finish(self) -> Result<OwnedGraph> {
+        self.graph_def.import("GraphTransform::finish")
+    }
+}
@@ -0,0 +1,245 @@
+//! Minimal protobuf wire format decoding, used to read messages returned by
+//! tensorflow without depending on generated protobuf code
+
+use crate::{Error, Result};
+
+/// Single field value as encoded on wire
+#[derive(Clone, Copy)]
+pub(crate) enum Value<'a> {
+    Varint(u64),
+    Fixed64(u64),
+    Bytes(&'a [u8]),
+    Fixed32(u32),
+}
+
+impl<'a> Value<'a> {
+    pub fn varint(self) -> Result<u64> {
+        match self {
+            Value::Varint(value) => Ok(value),
+            value => Err(unexpected("varint", value)),
+        }
+    }
+
+    pub fn fixed32(self) -> Result<u32> {
+        match self {
+            Value::Fixed32(value) => Ok(value),
+            value => Err(unexpected("fixed32", value)),
+        }
+    }
+
+    pub fn bytes(self) -> Result<&'a [u8]> {
+        match self {
+            Value::Bytes(value) => Ok(value),
+            value => Err(unexpected("length delimited", value)),
+        }
+    }
+
+    pub fn string(self) -> Result<String> {
+        String::from_utf8(self.bytes()?.to_vec())
+            .map_err(|_| Error::InvalidProtobuf("string field is not valid utf-8".to_owned()))
+    }
+
+    /// Reads repeated varint field entry, which may be either packed or not
+    pub fn packed_varints(self) -> Result<Vec<u64>> {
+        match self {
+            Value::Varint(value) => Ok(vec![value]),
+            Value::Bytes(mut data) => {
+                let mut values = vec![];
+                while !data.is_empty() {
+                    values.push(read_varint(&mut data)?);
+                }
+                Ok(values)
+            }
+            value => Err(unexpected("varint", value)),
+        }
+    }
+
+    /// Reads repeated fixed32 field entry, which may be either packed or not
+    pub fn packed_fixed32(self) -> Result<Vec<u32>> {
+        match self {
+            Value::Fixed32(value) => Ok(vec![value]),
+            Value::Bytes(data) if data.len() % 4 == 0 => Ok(data
+                .chunks(4)
+                .map(|chunk| u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+                .collect()),
+            value => Err(unexpected("fixed32", value)),
+        }
+    }
+
+    /// Reads repeated fixed64 field entry, which may be either packed or not
+    pub fn packed_fixed64(self) -> Result<Vec<u64>> {
+        match self {
+            Value::Fixed64(value) => Ok(vec![value]),
+            Value::Bytes(data) if data.len() % 8 == 0 => Ok(data
+                .chunks(8)
+                .map(|chunk| {
+                    let mut bytes = [0; 8];
+                    bytes.copy_from_slice(chunk);
+                    u64::from_le_bytes(bytes)
+                })
+                .collect()),
+            value => Err(unexpected("fixed64", value)),
+        }
+    }
+}
+
+fn unexpected(expected: &str, value: Value) -> Error {
+    let got = match value {
+        Value::Varint(value) => format!("varint {}", value),
+        Value::Fixed64(value) => format!("fixed64 {}", value),
+        Value::Bytes(value) => format!("{} bytes", value.len()),
+        Value::Fixed32(value) => format!("fixed32 {}", value),
+    };
+    Error::InvalidProtobuf(format!("expected {} field, got {}", expected, got))
+}
+
+/// Reads varint from the beginning of data, advancing it past the varint
+pub fn read_varint(data: &mut &[u8]) -> Result<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let (byte, rest) = data
+            .split_first()
+            .ok_or_else(|| Error::InvalidProtobuf("truncated varint".to_owned()))?;
+        *data = rest;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(Error::InvalidProtobuf("varint too long".to_owned()))
+}
+
+fn read_bytes<'a>(data: &mut &'a [u8], len: usize) -> Result<&'a [u8]> {
+    if data.len() < len {
+        return Err(Error::InvalidProtobuf("truncated field".to_owned()));
+    }
+    let (bytes, rest) = data.split_at(len);
+    *data = rest;
+    Ok(bytes)
+}
+
+/// Iterator over fields of encoded message, yielding field numbers with
+/// their values
+pub(crate) struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Reader { data }
+    }
+
+    fn field(&mut self) -> Result<(u32, Value<'a>)> {
+        let key = read_varint(&mut self.data)?;
+        let value = match key & 0x7 {
+            0 => Value::Varint(read_varint(&mut self.data)?),
+            1 => {
+                let bytes = read_bytes(&mut self.data, 8)?;
+                let mut value = [0; 8];
+                value.copy_from_slice(bytes);
+                Value::Fixed64(u64::from_le_bytes(value))
+            }
+            2 => {
+                let len = read_varint(&mut self.data)? as usize;
+                Value::Bytes(read_bytes(&mut self.data, len)?)
+            }
+            5 => {
+                let bytes = read_bytes(&mut self.data, 4)?;
+                let mut value = [0; 4];
+                value.copy_from_slice(bytes);
+                Value::Fixed32(u32::from_le_bytes(value))
+            }
+            wire_type => {
+                return Err(Error::InvalidProtobuf(format!(
+                    "unsupported wire type {}",
+                    wire_type
+                )))
+            }
+        };
+        Ok(((key >> 3) as u32, value))
+    }
+}
+
+impl<'a> Iterator for Reader<'a> {
+    type Item = Result<(u32, Value<'a>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.data.is_empty() {
+            return None;
+        }
+
+        let field = self.field();
+        if field.is_err() {
+            // Stop iteration after first error
+            self.data = &[];
+        }
+        Some(field)
+    }
+}
+
+/// Encoder of message fields
+#[derive(Default)]
+pub(crate) struct Writer {
+    data: Vec<u8>,
+}
+
+impl Writer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn key(&mut self, field: u32, wire_type: u32) {
+        self.raw_varint(u64::from(field << 3 | wire_type));
+    }
+
+    fn raw_varint(&mut self, mut value: u64) {
+        while value >= 0x80 {
+            self.data.push(value as u8 | 0x80);
+            value >>= 7;
+        }
+        self.data.push(value as u8);
+    }
+
+    pub fn varint(&mut self, field: u32, value: u64) -> &mut Self {
+        self.key(field, 0);
+        self.raw_varint(value);
+        self
+    }
+
+    pub fn bytes(&mut self, field: u32, value: &[u8]) -> &mut Self {
+        self.key(field, 2);
+        self.raw_varint(value.len() as u64);
+        self.data.extend_from_slice(value);
+        self
+    }
+
+    pub fn string(&mut self, field: u32, value: &str) -> &mut Self {
+        self.bytes(field, value.as_bytes())
+    }
+
+    pub fn fixed32(&mut self, field: u32, value: u32) -> &mut Self {
+        self.key(field, 5);
+        self.data.extend_from_slice(&value.to_le_bytes());
+        self
+    }
+
+    pub fn fixed64(&mut self, field: u32, value: u64) -> &mut Self {
+        self.key(field, 1);
+        self.data.extend_from_slice(&value.to_le_bytes());
+        self
+    }
+
+    /// Writes field value as read by `Reader`
+    pub fn value(&mut self, field: u32, value: Value) -> &mut Self {
+        match value {
+            Value::Varint(value) => self.varint(field, value),
+            Value::Fixed64(value) => self.fixed64(field, value),
+            Value::Bytes(value) => self.bytes(field, value),
+            Value::Fixed32(value) => self.fixed32(field, value),
+        }
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.data
+    }
+}
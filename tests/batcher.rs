@@ -0,0 +1,193 @@
+use rustflow::batcher::{BatchConfig, Batcher};
+use rustflow::session::SessionBuilder;
+use rustflow::{Error, Graph, Tensor, TensorType};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+fn addition_batcher(config: BatchConfig) -> Batcher {
+    let proto = include_str!("data/addition.pb");
+    let graph = Arc::new(Graph::from_protobuff(proto).unwrap());
+    let session = SessionBuilder::with_shared_graph(graph.clone())
+        .unwrap()
+        .build()
+        .map(Arc::new)
+        .unwrap();
+
+    let x = graph
+        .operation_by_name("x")
+        .unwrap()
+        .outputs()
+        .next()
+        .unwrap();
+    let y = graph
+        .operation_by_name("y")
+        .unwrap()
+        .outputs()
+        .next()
+        .unwrap();
+    let z = graph
+        .operation_by_name("z")
+        .unwrap()
+        .outputs()
+        .next()
+        .unwrap();
+    Batcher::new(session, &[x, y], &[z], config).unwrap()
+}
+
+#[test]
+fn batches_examples_from_many_threads() {
+    let batcher = addition_batcher(BatchConfig {
+        max_batch_size: 8,
+        timeout: Duration::from_millis(50),
+        padding: false,
+    });
+
+    thread::scope(|scope| {
+        for idx in 0..32 {
+            let batcher = &batcher;
+            scope.spawn(move || {
+                let x_val = Tensor::from_slice(&[3], &[idx, 1, 2]).unwrap();
+                let y_val = Tensor::from_slice(&[3], &[idx, 3, 4]).unwrap();
+                let result = batcher.run(vec![x_val.into(), y_val.into()]).unwrap();
+                let z_val = result
+                    .into_iter()
+                    .next()
+                    .unwrap()
+                    .downcast::<i32>()
+                    .unwrap();
+                assert_eq!(&[3], z_val.shape());
+                assert_eq!(&[2 * idx, 4, 6], &z_val[..]);
+            });
+        }
+    });
+
+    let metrics = batcher.metrics();
+    assert_eq!(32, metrics.examples);
+    assert!(metrics.batches >= 4);
+    assert!(metrics.fill_ratio() > 0.0 && metrics.fill_ratio() <= 1.0);
+}
+
+#[test]
+fn pads_variable_length_examples() {
+    let batcher = addition_batcher(BatchConfig {
+        max_batch_size: 2,
+        timeout: Duration::from_secs(1),
+        padding: true,
+    });
+
+    thread::scope(|scope| {
+        let batcher = &batcher;
+        let short = scope.spawn(move || {
+            let x_val = Tensor::from_slice(&[1], &[1]).unwrap();
+            let y_val = Tensor::from_slice(&[1], &[2]).unwrap();
+            batcher.run(vec![x_val.into(), y_val.into()]).unwrap()
+        });
+        let long = scope.spawn(move || {
+            let x_val = Tensor::from_slice(&[3], &[1, 2, 3]).unwrap();
+            let y_val = Tensor::from_slice(&[3], &[4, 5, 6]).unwrap();
+            batcher.run(vec![x_val.into(), y_val.into()]).unwrap()
+        });
+
+        let short = short.join().unwrap();
+        let short = short.into_iter().next().unwrap().downcast::<i32>().unwrap();
+        assert_eq!(&[3], short.shape());
+        assert_eq!(&[3, 0, 0], &short[..]);
+
+        let long = long.join().unwrap();
+        let long = long.into_iter().next().unwrap().downcast::<i32>().unwrap();
+        assert_eq!(&[5, 7, 9], &long[..]);
+    });
+
+    assert_eq!(1, batcher.metrics().batches);
+    assert_eq!(1.0, batcher.metrics().fill_ratio());
+}
+
+#[test]
+fn runs_differently_shaped_examples_in_separate_batches() {
+    for &padding in &[false, true] {
+        let batcher = addition_batcher(BatchConfig {
+            max_batch_size: 2,
+            timeout: Duration::from_secs(1),
+            padding,
+        });
+
+        thread::scope(|scope| {
+            let batcher = &batcher;
+            // Without padding shapes differ, with padding ranks
+            let vector = scope.spawn(move || {
+                let x_val = Tensor::from_slice(&[2], &[1, 2]).unwrap();
+                let y_val = Tensor::from_slice(&[2], &[3, 4]).unwrap();
+                batcher.run(vec![x_val.into(), y_val.into()]).unwrap()
+            });
+            let matrix = scope.spawn(move || {
+                let x_val = Tensor::from_slice(&[1, 3], &[1, 2, 3]).unwrap();
+                let y_val = Tensor::from_slice(&[1, 3], &[4, 5, 6]).unwrap();
+                batcher.run(vec![x_val.into(), y_val.into()]).unwrap()
+            });
+
+            let vector = vector.join().unwrap();
+            let vector = vector
+                .into_iter()
+                .next()
+                .unwrap()
+                .downcast::<i32>()
+                .unwrap();
+            assert_eq!(&[2], vector.shape());
+            assert_eq!(&[4, 6], &vector[..]);
+
+            let matrix = matrix.join().unwrap();
+            let matrix = matrix
+                .into_iter()
+                .next()
+                .unwrap()
+                .downcast::<i32>()
+                .unwrap();
+            assert_eq!(&[1, 3], matrix.shape());
+            assert_eq!(&[5, 7, 9], &matrix[..]);
+        });
+
+        let metrics = batcher.metrics();
+        assert_eq!(2, metrics.batches);
+        assert_eq!(2, metrics.examples);
+    }
+}
+
+#[test]
+fn rejects_invalid_examples() {
+    let batcher = addition_batcher(BatchConfig::default());
+
+    let x_val = Tensor::from_slice(&[1], &[1.0f32]).unwrap();
+    let y_val = Tensor::from_slice(&[1], &[1.0f32]).unwrap();
+    batcher.run(vec![x_val.into(), y_val.into()]).unwrap_err();
+
+    let x_val = Tensor::from_slice(&[1], &[1]).unwrap();
+    batcher.run(vec![x_val.into()]).unwrap_err();
+}
+
+#[test]
+fn rejects_outputs_missing_in_session_graph() {
+    let proto = include_str!("data/addition.pb");
+    let graph = Arc::new(Graph::from_protobuff(proto).unwrap());
+    let session = SessionBuilder::with_shared_graph(graph)
+        .unwrap()
+        .build()
+        .map(Arc::new)
+        .unwrap();
+
+    let other = Graph::new();
+    let missing = other
+        .new_operation("Placeholder", "missing")
+        .unwrap()
+        .set_attr_type("dtype", i32::TF_TYPE)
+        .unwrap()
+        .finish()
+        .unwrap();
+    let missing = missing.outputs().next().unwrap();
+
+    let result = Batcher::new(session, &[missing], &[missing], BatchConfig::default());
+    assert_eq!(
+        Some(Error::OperationNotFound("missing:0".to_owned())),
+        result.err()
+    );
+}
@@ -0,0 +1,66 @@
+use rustflow::benchmark::{measure, percentile, split_iterations};
+use rustflow::Error;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+fn millis(values: impl IntoIterator<Item = u64>) -> Vec<Duration> {
+    values.into_iter().map(Duration::from_millis).collect()
+}
+
+#[test]
+fn computes_nearest_rank_percentiles() {
+    let latencies = millis(1..=100);
+    assert_eq!(Duration::from_millis(50), percentile(&latencies, 50));
+    assert_eq!(Duration::from_millis(90), percentile(&latencies, 90));
+    assert_eq!(Duration::from_millis(99), percentile(&latencies, 99));
+    assert_eq!(Duration::from_millis(100), percentile(&latencies, 100));
+    assert_eq!(Duration::from_millis(1), percentile(&latencies, 0));
+
+    let latencies = millis(vec![1, 2, 3]);
+    assert_eq!(Duration::from_millis(2), percentile(&latencies, 50));
+    assert_eq!(Duration::from_millis(3), percentile(&latencies, 90));
+
+    let latencies = millis(vec![7]);
+    for percent in &[0, 50, 90, 99, 100] {
+        assert_eq!(Duration::from_millis(7), percentile(&latencies, *percent));
+    }
+}
+
+#[test]
+fn splits_iterations_among_threads() {
+    assert_eq!(vec![10], split_iterations(10, 1));
+    assert_eq!(vec![5, 5], split_iterations(10, 2));
+    assert_eq!(vec![3, 3, 2, 2], split_iterations(10, 4));
+    assert_eq!(vec![1, 0, 0, 0], split_iterations(1, 4));
+    assert_eq!(vec![1, 1, 1, 0, 0], split_iterations(3, 5));
+}
+
+#[test]
+fn measures_all_iterations() {
+    for &(threads, iterations) in &[(1, 1), (4, 1), (3, 10), (8, 3), (2, 100)] {
+        let calls = AtomicUsize::new(0);
+        let report = measure(threads, iterations, || {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(iterations, calls.load(Ordering::SeqCst));
+        assert_eq!(threads, report.threads);
+        assert_eq!(iterations, report.iterations);
+        assert!(report.p50 <= report.p90 && report.p90 <= report.p99);
+        assert!(report.throughput > 0.0);
+    }
+}
+
+#[test]
+fn returns_run_errors() {
+    let calls = AtomicUsize::new(0);
+    let result = measure(2, 10, || {
+        if calls.fetch_add(1, Ordering::SeqCst) == 3 {
+            Err(Error::OperationNotFound("x".to_owned()))
+        } else {
+            Ok(())
+        }
+    });
+    assert_eq!(Err(Error::OperationNotFound("x".to_owned())), result);
+}
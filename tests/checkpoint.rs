@@ -0,0 +1,171 @@
+use rustflow::checkpoint::CheckpointReader;
+use rustflow::session::SessionBuilder;
+use rustflow::train::{self, Saver, Variable};
+use rustflow::{AnyTensor, Error, Graph, Tensor, TensorType};
+use std::fs;
+use std::path::PathBuf;
+
+const PREFIX: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/checkpoint/model");
+
+/// Copies test checkpoint to temporary directory, returning its prefix
+fn copy_checkpoint(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("rustflow-{}-{}", name, std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    for suffix in &[".index", ".data-00000-of-00001"] {
+        fs::copy(
+            format!("{}{}", PREFIX, suffix),
+            dir.join(format!("model{}", suffix)),
+        )
+        .unwrap();
+    }
+    dir.join("model")
+}
+
+#[test]
+fn lists_checkpoint_variables() {
+    let checkpoint = CheckpointReader::open(PREFIX).unwrap();
+    let variables: Vec<_> = checkpoint
+        .variables()
+        .map(|variable| {
+            (
+                variable.name.as_str(),
+                variable.dtype,
+                variable.shape.clone(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        vec![
+            ("dense/bias", f32::TF_TYPE, vec![3]),
+            ("dense/kernel", f32::TF_TYPE, vec![2, 3]),
+            ("global_step", i64::TF_TYPE, vec![]),
+            ("vocabulary", tf::TF_STRING, vec![2]),
+        ],
+        variables
+    );
+}
+
+#[test]
+fn loads_checkpoint_variables() {
+    let checkpoint = CheckpointReader::open(PREFIX).unwrap();
+
+    let kernel = checkpoint.load::<f32>("dense/kernel").unwrap();
+    assert_eq!(&[2, 3], kernel.shape());
+    assert_eq!(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0], &kernel[..]);
+
+    let bias = checkpoint.load::<f32>("dense/bias").unwrap();
+    assert_eq!(&[0.1, 0.2, 0.3], &bias[..]);
+
+    let step = checkpoint.load::<i64>("global_step").unwrap();
+    assert_eq!(&[1000], &step[..]);
+
+    let vocabulary = checkpoint.load_any("vocabulary").unwrap();
+    assert_eq!(
+        vec![b"hello".to_vec(), b"world".to_vec()],
+        vocabulary.strings().unwrap()
+    );
+}
+
+#[test]
+fn error_loading_missing_variable() {
+    let checkpoint = CheckpointReader::open(PREFIX).unwrap();
+    match checkpoint.load_any("dense/missing") {
+        Err(Error::VariableNotFound(name)) => assert_eq!("dense/missing", name),
+        _ => panic!("expected variable not found error"),
+    }
+}
+
+#[test]
+fn error_loading_variable_of_other_type() {
+    let checkpoint = CheckpointReader::open(PREFIX).unwrap();
+    match checkpoint.load::<i32>("global_step") {
+        Err(Error::TypeMismatch { expected, found }) => {
+            assert_eq!(i32::TF_TYPE, expected);
+            assert_eq!(i64::TF_TYPE, found);
+        }
+        _ => panic!("expected type mismatch"),
+    }
+}
+
+#[test]
+fn error_loading_corrupted_variable() {
+    let prefix = copy_checkpoint("corrupted");
+    let data_path = prefix.with_extension("data-00000-of-00001");
+    let mut data = fs::read(&data_path).unwrap();
+    // First element of dense/bias
+    data[0] ^= 0xff;
+    fs::write(&data_path, data).unwrap();
+
+    let checkpoint = CheckpointReader::open(&prefix).unwrap();
+    let bias = checkpoint.load_any("dense/bias");
+    let kernel = checkpoint.load_any("dense/kernel");
+    fs::remove_dir_all(prefix.parent().unwrap()).unwrap();
+
+    match bias {
+        Err(Error::InvalidCheckpoint(_)) => (),
+        _ => panic!("expected invalid checkpoint error"),
+    }
+    kernel.unwrap();
+}
+
+#[test]
+fn error_loading_corrupted_strings() {
+    let prefix = copy_checkpoint("corrupted-strings");
+    let data_path = prefix.with_extension("data-00000-of-00001");
+    let mut data = fs::read(&data_path).unwrap();
+    // Last byte of "world" in vocabulary
+    *data.last_mut().unwrap() ^= 0xff;
+    fs::write(&data_path, data).unwrap();
+
+    let checkpoint = CheckpointReader::open(&prefix).unwrap();
+    let vocabulary = checkpoint.load_any("vocabulary");
+    fs::remove_dir_all(prefix.parent().unwrap()).unwrap();
+    match vocabulary {
+        Err(Error::InvalidCheckpoint(_)) => (),
+        _ => panic!("expected invalid checkpoint error"),
+    }
+}
+
+#[test]
+fn error_opening_corrupted_index() {
+    let prefix = copy_checkpoint("corrupted-index");
+    let index_path = prefix.with_extension("index");
+    let mut index = fs::read(&index_path).unwrap();
+    // Inside name of first variable
+    index[12] ^= 0xff;
+    fs::write(&index_path, index).unwrap();
+
+    let result = CheckpointReader::open(&prefix);
+    fs::remove_dir_all(prefix.parent().unwrap()).unwrap();
+    match result {
+        Err(Error::InvalidCheckpoint(_)) => (),
+        _ => panic!("expected invalid checkpoint error"),
+    }
+}
+
+#[test]
+fn reads_checkpoint_written_by_saver() {
+    let graph = Graph::new();
+    let initial = Tensor::from_slice(&[2, 2], &[1.0f64, 2.0, 3.0, 4.0]).unwrap();
+    // Includes empty string, to check lengths checksum
+    let tokens = AnyTensor::from_strings(&[3], &["a", "", "bcd"]).unwrap();
+    let variables = [
+        Variable::new(&graph, "layer/w", initial.as_ref()).unwrap(),
+        Variable::new(&graph, "tokens", &tokens).unwrap(),
+    ];
+    let init = train::initializer(&graph, "init", &variables).unwrap();
+    let saver = Saver::new(&graph, "save", &variables).unwrap();
+    let session = SessionBuilder::with_graph(&graph).unwrap().build().unwrap();
+    session.run(&[], &[], &[init]).unwrap();
+
+    let prefix = std::env::temp_dir().join(format!("rustflow-reader-{}", std::process::id()));
+    saver.save(&session, prefix.to_str().unwrap()).unwrap();
+
+    let checkpoint = CheckpointReader::open(&prefix).unwrap();
+    let w = checkpoint.load::<f64>("layer/w").unwrap();
+    assert_eq!(&[2, 2], w.shape());
+    assert_eq!(&initial[..], &w[..]);
+    let loaded = checkpoint.load_any("tokens").unwrap();
+    assert_eq!(&[3], loaded.shape());
+    assert_eq!(tokens.strings().unwrap(), loaded.strings().unwrap());
+}
@@ -0,0 +1,17 @@
+use rustflow::session::SessionBuilder;
+use rustflow::{Graph, Session};
+
+fn spawn_session(session: Session<'static>) {
+    std::thread::spawn(move || drop(session));
+}
+
+fn main() {
+    let proto = include_str!("../data/addition.pb");
+    let graph = Graph::from_protobuff(proto).unwrap();
+    let session = SessionBuilder::with_graph(&graph)
+        .unwrap()
+        .build()
+        .unwrap();
+
+    spawn_session(session);
+}
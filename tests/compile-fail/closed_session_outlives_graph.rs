@@ -0,0 +1,16 @@
+use rustflow::session::SessionBuilder;
+use rustflow::Graph;
+
+fn main() {
+    let proto = include_str!("../data/addition.pb");
+    let graph = Graph::from_protobuff(proto).unwrap();
+    let session = SessionBuilder::with_graph(&graph)
+        .unwrap()
+        .build()
+        .unwrap()
+        .close()
+        .unwrap();
+
+    drop(graph);
+    session.delete().unwrap();
+}
@@ -0,0 +1,5 @@
+#[test]
+fn session_cannot_outlive_graph() {
+    let cases = trybuild::TestCases::new();
+    cases.compile_fail("tests/compile-fail/*.rs");
+}
@@ -0,0 +1,149 @@
+use rustflow::session::SessionBuilder;
+use rustflow::{Error, Graph, Output, Tensor, TensorType};
+
+type Result<T> = std::result::Result<T, Error>;
+
+fn constant<'a>(graph: &'a Graph, name: &str, value: i32) -> Result<Output<'a>> {
+    let value = Tensor::from_slice(&[], &[value])?;
+    let op = graph
+        .new_operation("Const", name)?
+        .set_attr_tensor("value", value.as_ref())?
+        .set_attr_type("dtype", i32::TF_TYPE)?
+        .finish()?;
+    Ok(op.output(0).unwrap())
+}
+
+fn binary<'a>(graph: &'a Graph, op_type: &str, x: Output<'a>, y: Output<'a>) -> Result<Output<'a>> {
+    let op = graph
+        .new_operation(op_type, &op_type.to_lowercase())?
+        .add_input(x)
+        .add_input(y)
+        .finish()?;
+    Ok(op.output(0).unwrap())
+}
+
+/// Adds loop computing factorial of `n`
+fn factorial<'a>(graph: &'a Graph, n: Output<'a>) -> Output<'a> {
+    let one = constant(graph, "one", 1).unwrap();
+    let outputs = graph
+        .while_loop(
+            &[n, one],
+            |graph, vars| {
+                let zero = constant(graph, "zero", 0)?;
+                binary(graph, "Greater", vars[0], zero)
+            },
+            |graph, vars| {
+                let one = constant(graph, "one", 1)?;
+                Ok(vec![
+                    binary(graph, "Sub", vars[0], one)?,
+                    binary(graph, "Mul", vars[1], vars[0])?,
+                ])
+            },
+        )
+        .unwrap();
+    outputs[1]
+}
+
+fn placeholder(graph: &Graph) -> Output {
+    graph
+        .new_operation("Placeholder", "n")
+        .unwrap()
+        .set_attr_type("dtype", i32::TF_TYPE)
+        .unwrap()
+        .finish()
+        .unwrap()
+        .output(0)
+        .unwrap()
+}
+
+fn eval(graph: &Graph, input: Output, value: i32, output: Output) -> i32 {
+    let session = SessionBuilder::with_graph(graph).unwrap().build().unwrap();
+    let value = Tensor::from_slice(&[], &[value]).unwrap();
+    let result = session
+        .run(&[(input, value.as_ref())], &[output], &[])
+        .unwrap();
+    let result = result
+        .into_iter()
+        .next()
+        .unwrap()
+        .downcast::<i32>()
+        .unwrap();
+    result[0]
+}
+
+#[test]
+fn computes_factorial_in_while_loop() {
+    let graph = Graph::new();
+    let n = placeholder(&graph);
+    let result = factorial(&graph, n);
+
+    assert_eq!(1, eval(&graph, n, 0, result));
+    assert_eq!(120, eval(&graph, n, 5, result));
+}
+
+#[test]
+fn computes_factorial_in_conditional() {
+    // Factorial of negative numbers is not defined, -1 is returned then
+    let graph = Graph::new();
+    let n = placeholder(&graph);
+    let zero = constant(&graph, "zero", 0).unwrap();
+    let negative = binary(&graph, "Less", n, zero).unwrap();
+    let outputs = graph
+        .cond(
+            "checked_factorial",
+            negative,
+            &[n],
+            |graph, _| Ok(vec![constant(graph, "undefined", -1)?]),
+            |graph, inputs| Ok(vec![factorial(graph, inputs[0])]),
+        )
+        .unwrap();
+    assert_eq!(1, outputs.len());
+
+    assert_eq!(-1, eval(&graph, n, -3, outputs[0]));
+    assert_eq!(24, eval(&graph, n, 4, outputs[0]));
+}
+
+#[test]
+fn rejects_loop_body_with_invalid_outputs() {
+    let graph = Graph::new();
+    let n = placeholder(&graph);
+    let err = graph
+        .while_loop(
+            &[n],
+            |graph, vars| {
+                let zero = constant(graph, "zero", 0)?;
+                binary(graph, "Greater", vars[0], zero)
+            },
+            |_, _| Ok(vec![]),
+        )
+        .map(|_| ())
+        .unwrap_err();
+
+    match err {
+        Error::InvalidControlFlow(_) => (),
+        err => panic!("Unexpected error: {:?}", err),
+    }
+}
+
+#[test]
+fn rejects_branches_with_different_types() {
+    let graph = Graph::new();
+    let n = placeholder(&graph);
+    let zero = constant(&graph, "zero", 0).unwrap();
+    let negative = binary(&graph, "Less", n, zero).unwrap();
+    let err = graph
+        .cond(
+            "invalid",
+            negative,
+            &[n],
+            |_, inputs| Ok(inputs.to_vec()),
+            |_, _| Ok(vec![]),
+        )
+        .map(|_| ())
+        .unwrap_err();
+
+    match err {
+        Error::InvalidControlFlow(_) => (),
+        err => panic!("Unexpected error: {:?}", err),
+    }
+}
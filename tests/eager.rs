@@ -0,0 +1,75 @@
+use rustflow::eager::{Context, TensorHandle};
+use rustflow::{Tensor, TensorType};
+
+#[test]
+fn converts_tensor_to_handle_and_back() {
+    let ctx = Context::new().unwrap();
+    let tensor = Tensor::from_slice(&[2, 2], &[1.0f64, 2.0, 3.0, 4.0]).unwrap();
+    let handle = TensorHandle::new(&ctx, tensor.as_ref()).unwrap();
+
+    assert_eq!(f64::TF_TYPE, handle.dtype());
+    assert_eq!(vec![2, 2], handle.shape().unwrap());
+    assert!(handle.device_name().unwrap().contains("CPU"));
+
+    let resolved = handle.resolve().unwrap().downcast::<f64>().unwrap();
+    assert_eq!(&[2, 2], resolved.shape());
+    assert_eq!(&tensor[..], &resolved[..]);
+}
+
+#[test]
+fn handle_does_not_share_data_with_tensors() {
+    let ctx = Context::new().unwrap();
+    let mut tensor = Tensor::from_slice(&[2], &[1, 2]).unwrap();
+    let handle = TensorHandle::new(&ctx, tensor.as_ref()).unwrap();
+    tensor[0] = 5;
+
+    let mut first = handle.resolve().unwrap().downcast::<i32>().unwrap();
+    let second = handle.resolve().unwrap().downcast::<i32>().unwrap();
+    first[1] = 7;
+    assert_eq!(&[5, 7], &first[..]);
+    assert_eq!(&[1, 2], &second[..]);
+}
+
+#[test]
+fn executes_single_ops() {
+    let ctx = Context::new().unwrap();
+    let a = Tensor::from_slice(&[2, 2], &[1.0f32, 2.0, 3.0, 4.0]).unwrap();
+    let a = TensorHandle::new(&ctx, a.as_ref()).unwrap();
+
+    let product = ctx
+        .new_op("MatMul")
+        .unwrap()
+        .add_input(&a)
+        .unwrap()
+        .add_input(&a)
+        .unwrap()
+        .set_attr_type("T", f32::TF_TYPE)
+        .unwrap()
+        .set_attr_bool("transpose_b", true)
+        .unwrap()
+        .execute(1)
+        .unwrap();
+
+    let product = product[0].resolve().unwrap().downcast::<f32>().unwrap();
+    assert_eq!(&[5.0, 11.0, 11.0, 25.0], &product[..]);
+}
+
+#[test]
+fn fails_executing_invalid_op() {
+    let ctx = Context::new().unwrap();
+    ctx.new_op("NotExistingOp").map(|_| ()).unwrap_err();
+
+    let a = Tensor::from_slice(&[2], &[1, 2]).unwrap();
+    let a = TensorHandle::new(&ctx, a.as_ref()).unwrap();
+    ctx.new_op("MatMul")
+        .unwrap()
+        .add_input(&a)
+        .unwrap()
+        .add_input(&a)
+        .unwrap()
+        .set_attr_type("T", i32::TF_TYPE)
+        .unwrap()
+        .execute(1)
+        .map(|_| ())
+        .unwrap_err();
+}
@@ -0,0 +1,262 @@
+use rustflow::freeze::freeze;
+use rustflow::session::SessionBuilder;
+use rustflow::train::{self, Variable};
+use rustflow::{Error, Graph, Operation, Output, Tensor, TensorType};
+
+fn placeholder(graph: &Graph) -> Output {
+    graph
+        .new_operation("Placeholder", "x")
+        .unwrap()
+        .set_attr_type("dtype", f32::TF_TYPE)
+        .unwrap()
+        .finish()
+        .unwrap()
+        .output(0)
+        .unwrap()
+}
+
+fn mul<'a>(graph: &'a Graph, x: Output<'a>, y: Output<'a>) -> Output<'a> {
+    graph
+        .new_operation("Mul", "mul")
+        .unwrap()
+        .add_input(x)
+        .add_input(y)
+        .finish()
+        .unwrap()
+        .output(0)
+        .unwrap()
+}
+
+/// Runs graph computing `mul` for `x = [1, 2]`
+fn eval_frozen(graph: &Graph) -> Vec<f32> {
+    let x = graph.operation_by_name("x").unwrap().output(0).unwrap();
+    let mul = graph.operation_by_name("mul").unwrap().output(0).unwrap();
+    let session = SessionBuilder::with_graph(graph).unwrap().build().unwrap();
+    let x_val = Tensor::from_slice(&[2], &[1.0f32, 2.0]).unwrap();
+    let result = session.run(&[(x, x_val.as_ref())], &[mul], &[]).unwrap();
+    let result = result
+        .into_iter()
+        .next()
+        .unwrap()
+        .downcast::<f32>()
+        .unwrap();
+    result.to_vec()
+}
+
+#[test]
+fn freezes_reference_variables() {
+    let graph = Graph::new();
+    let x = placeholder(&graph);
+    let initial = Tensor::from_slice(&[], &[3.0f32]).unwrap();
+    let w = Variable::new(&graph, "w", initial.as_ref()).unwrap();
+    let y = mul(&graph, w.output(), x);
+    let init = train::initializer(&graph, "init", &[w]).unwrap();
+
+    let session = SessionBuilder::with_graph(&graph).unwrap().build().unwrap();
+    session.run(&[], &[], &[init]).unwrap();
+    let frozen = freeze(&graph, &session, &[y]).unwrap();
+
+    assert_eq!("Const", frozen.operation_by_name("w").unwrap().op_type());
+    assert!(frozen.operation_by_name("init").is_none());
+    assert!(frozen.operation_by_name("w/Assign").is_none());
+    assert_eq!(vec![3.0, 6.0], eval_frozen(&frozen));
+}
+
+#[test]
+fn freezes_resource_variables() {
+    let graph = Graph::new();
+    let x = placeholder(&graph);
+    let handle = graph
+        .new_operation("VarHandleOp", "w")
+        .unwrap()
+        .set_attr_type("dtype", f32::TF_TYPE)
+        .unwrap()
+        .set_attr_shape("shape", Some(&[]))
+        .unwrap()
+        .set_attr_string("shared_name", "w")
+        .unwrap()
+        .finish()
+        .unwrap()
+        .output(0)
+        .unwrap();
+    let initial = Tensor::from_slice(&[], &[-1.0f32]).unwrap();
+    let initial = graph
+        .new_operation("Const", "w/initial_value")
+        .unwrap()
+        .set_attr_tensor("value", initial.as_ref())
+        .unwrap()
+        .set_attr_type("dtype", f32::TF_TYPE)
+        .unwrap()
+        .finish()
+        .unwrap()
+        .output(0)
+        .unwrap();
+    let init = graph
+        .new_operation("AssignVariableOp", "w/Assign")
+        .unwrap()
+        .add_input(handle)
+        .add_input(initial)
+        .set_attr_type("dtype", f32::TF_TYPE)
+        .unwrap()
+        .finish()
+        .unwrap();
+    let read = graph
+        .new_operation("ReadVariableOp", "w/read")
+        .unwrap()
+        .add_input(handle)
+        .set_attr_type("dtype", f32::TF_TYPE)
+        .unwrap()
+        .finish()
+        .unwrap()
+        .output(0)
+        .unwrap();
+    let y = mul(&graph, read, x);
+
+    let session = SessionBuilder::with_graph(&graph).unwrap().build().unwrap();
+    session.run(&[], &[], &[init]).unwrap();
+    let frozen = freeze(&graph, &session, &[y]).unwrap();
+
+    assert_eq!("Const", frozen.operation_by_name("w").unwrap().op_type());
+    assert_eq!(
+        "Identity",
+        frozen.operation_by_name("w/read").unwrap().op_type()
+    );
+    assert!(frozen.operation_by_name("w/Assign").is_none());
+    assert_eq!(vec![-1.0, -2.0], eval_frozen(&frozen));
+}
+
+/// Creates resource variable `v = [-1, 4]`, returning its handle and
+/// initializer
+fn resource_variable(graph: &Graph) -> (Output, Operation) {
+    let handle = graph
+        .new_operation("VarHandleOp", "v")
+        .unwrap()
+        .set_attr_type("dtype", f32::TF_TYPE)
+        .unwrap()
+        .set_attr_shape("shape", Some(&[2]))
+        .unwrap()
+        .set_attr_string("shared_name", "v")
+        .unwrap()
+        .finish()
+        .unwrap()
+        .output(0)
+        .unwrap();
+    let initial = Tensor::from_slice(&[2], &[-1.0f32, 4.0]).unwrap();
+    let initial = graph
+        .new_operation("Const", "v/initial_value")
+        .unwrap()
+        .set_attr_tensor("value", initial.as_ref())
+        .unwrap()
+        .set_attr_type("dtype", f32::TF_TYPE)
+        .unwrap()
+        .finish()
+        .unwrap()
+        .output(0)
+        .unwrap();
+    let init = graph
+        .new_operation("AssignVariableOp", "v/Assign")
+        .unwrap()
+        .add_input(handle)
+        .add_input(initial)
+        .set_attr_type("dtype", f32::TF_TYPE)
+        .unwrap()
+        .finish()
+        .unwrap();
+    (handle, init)
+}
+
+fn gather<'a>(graph: &'a Graph, handle: Output<'a>) -> Output<'a> {
+    let indices = Tensor::from_slice(&[3], &[1, 0, 1]).unwrap();
+    let indices = graph
+        .new_operation("Const", "indices")
+        .unwrap()
+        .set_attr_tensor("value", indices.as_ref())
+        .unwrap()
+        .set_attr_type("dtype", i32::TF_TYPE)
+        .unwrap()
+        .finish()
+        .unwrap()
+        .output(0)
+        .unwrap();
+    graph
+        .new_operation("ResourceGather", "gather")
+        .unwrap()
+        .add_input(handle)
+        .add_input(indices)
+        .set_attr_type("dtype", f32::TF_TYPE)
+        .unwrap()
+        .set_attr_type("Tindices", i32::TF_TYPE)
+        .unwrap()
+        .finish()
+        .unwrap()
+        .output(0)
+        .unwrap()
+}
+
+#[test]
+fn freezes_gathered_resource_variables() {
+    let graph = Graph::new();
+    let (handle, init) = resource_variable(&graph);
+    // Not needed for output, but provides variable value
+    graph
+        .new_operation("ReadVariableOp", "v/read")
+        .unwrap()
+        .add_input(handle)
+        .set_attr_type("dtype", f32::TF_TYPE)
+        .unwrap()
+        .finish()
+        .unwrap();
+    let gathered = gather(&graph, handle);
+
+    let session = SessionBuilder::with_graph(&graph).unwrap().build().unwrap();
+    session.run(&[], &[], &[init]).unwrap();
+    let frozen = freeze(&graph, &session, &[gathered]).unwrap();
+
+    assert_eq!("Const", frozen.operation_by_name("v").unwrap().op_type());
+    let gathered = frozen.operation_by_name("gather").unwrap();
+    assert_eq!("GatherV2", gathered.op_type());
+    assert!(frozen.operation_by_name("v/read").is_none());
+
+    let session = SessionBuilder::with_graph(&frozen)
+        .unwrap()
+        .build()
+        .unwrap();
+    let result = session
+        .run(&[], &[gathered.output(0).unwrap()], &[])
+        .unwrap();
+    let result = result
+        .into_iter()
+        .next()
+        .unwrap()
+        .downcast::<f32>()
+        .unwrap();
+    assert_eq!(&[4.0, -1.0, 4.0], &result[..]);
+}
+
+#[test]
+fn rejects_unsupported_resource_variable_uses() {
+    let graph = Graph::new();
+    let (handle, init) = resource_variable(&graph);
+    let shape = graph
+        .new_operation("VariableShape", "shape")
+        .unwrap()
+        .add_input(handle)
+        .finish()
+        .unwrap()
+        .output(0)
+        .unwrap();
+    let session = SessionBuilder::with_graph(&graph).unwrap().build().unwrap();
+    session.run(&[], &[], &[init]).unwrap();
+
+    match freeze(&graph, &session, &[shape]).map(|_| ()) {
+        Err(Error::InvalidGraph(message)) => assert!(message.contains("shape (VariableShape)")),
+        result => panic!("unexpected result {:?}", result),
+    }
+
+    // Variable value can't be read without `ReadVariableOp`
+    let gathered = gather(&graph, handle);
+    match freeze(&graph, &session, &[gathered]).map(|_| ()) {
+        Err(Error::InvalidGraph(message)) => assert!(message.contains("never read")),
+        result => panic!("unexpected result {:?}", result),
+    }
+}
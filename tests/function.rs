@@ -0,0 +1,130 @@
+use rustflow::session::SessionBuilder;
+use rustflow::{Function, Graph, Tensor, TensorType};
+
+fn call_add(graph: &Graph, function: &str) -> Vec<i32> {
+    let a = graph
+        .new_operation("Placeholder", "a")
+        .unwrap()
+        .set_attr_type("dtype", i32::TF_TYPE)
+        .unwrap()
+        .finish()
+        .unwrap()
+        .outputs()
+        .next()
+        .unwrap();
+    let call = graph
+        .new_operation(function, "call")
+        .unwrap()
+        .add_input(a)
+        .add_input(a)
+        .finish()
+        .unwrap();
+
+    let session = SessionBuilder::with_graph(graph).unwrap().build().unwrap();
+    let a_val = Tensor::from_slice(&[3], &[1, 2, 3]).unwrap();
+    let result = session
+        .run(
+            &[(a, a_val.as_ref())],
+            &[call.outputs().next().unwrap()],
+            &[],
+        )
+        .unwrap();
+    let result = result
+        .into_iter()
+        .next()
+        .unwrap()
+        .downcast::<i32>()
+        .unwrap();
+    result.to_vec()
+}
+
+fn add_function() -> Function {
+    let proto = include_str!("data/addition.pb");
+    let graph = Graph::from_protobuff(proto).unwrap();
+    let x = graph
+        .operation_by_name("x")
+        .unwrap()
+        .outputs()
+        .next()
+        .unwrap();
+    let y = graph
+        .operation_by_name("y")
+        .unwrap()
+        .outputs()
+        .next()
+        .unwrap();
+    let z = graph
+        .operation_by_name("z")
+        .unwrap()
+        .outputs()
+        .next()
+        .unwrap();
+
+    Function::from_graph(&graph, "add", &[x, y], &[z]).unwrap()
+}
+
+#[test]
+fn calls_function_copied_into_graph() {
+    let function = add_function();
+
+    let graph = Graph::new();
+    graph.copy_function(&function, None).unwrap();
+    assert_eq!(vec![2, 4, 6], call_add(&graph, "add"));
+}
+
+#[test]
+fn calls_function_through_partitioned_call() {
+    let function = add_function();
+
+    let graph = Graph::new();
+    graph.copy_function(&function, None).unwrap();
+    let a = graph
+        .new_operation("Placeholder", "a")
+        .unwrap()
+        .set_attr_type("dtype", i32::TF_TYPE)
+        .unwrap()
+        .finish()
+        .unwrap()
+        .outputs()
+        .next()
+        .unwrap();
+    let call = graph
+        .new_operation("PartitionedCall", "call")
+        .unwrap()
+        .add_input_list(&[a, a])
+        .set_attr_type_list("Tin", &[i32::TF_TYPE, i32::TF_TYPE])
+        .unwrap()
+        .set_attr_type_list("Tout", &[i32::TF_TYPE])
+        .unwrap()
+        .set_attr_func_name("f", "add")
+        .unwrap()
+        .finish()
+        .unwrap();
+
+    let session = SessionBuilder::with_graph(&graph).unwrap().build().unwrap();
+    let a_val = Tensor::from_slice(&[1], &[5]).unwrap();
+    let result = session
+        .run(
+            &[(a, a_val.as_ref())],
+            &[call.outputs().next().unwrap()],
+            &[],
+        )
+        .unwrap();
+    let result = result
+        .into_iter()
+        .next()
+        .unwrap()
+        .downcast::<i32>()
+        .unwrap();
+    assert_eq!(&[10], &result[..]);
+}
+
+#[test]
+fn calls_deserialized_function() {
+    let proto = add_function().to_function_def().unwrap();
+    let function = Function::from_function_def(&proto).unwrap();
+
+    let graph = Graph::new();
+    graph.copy_function(&function, None).unwrap();
+    assert_eq!(vec![2, 4, 6], call_add(&graph, "add"));
+}
@@ -1,4 +1,5 @@
-use rustflow::Graph;
+use rustflow::session::SessionBuilder;
+use rustflow::{Error, Graph, Tensor, TensorType};
 
 #[test]
 fn loads_valid_protobuff_graph() {
@@ -11,3 +12,302 @@ fn error_loadnig_invalid_protobuff_graph() {
     let proto = "invalid";
     Graph::from_protobuff(proto).map(|_| ()).unwrap_err();
 }
+
+#[test]
+fn reports_function_failing_graph_import() {
+    match Graph::from_protobuff_bytes(b"invalid") {
+        Err(Error::TFError(err)) => {
+            assert_eq!(Some("Graph::from_protobuff_bytes"), err.function());
+            assert_eq!(None, err.node());
+            assert!(err
+                .to_string()
+                .starts_with("Graph::from_protobuff_bytes failed ("));
+        }
+        _ => panic!("expected tensorflow error"),
+    }
+}
+
+#[test]
+fn builds_graph_placed_on_cpu() {
+    let graph = Graph::new();
+    let x = graph
+        .new_operation("Placeholder", "x")
+        .unwrap()
+        .set_attr_type("dtype", i32::TF_TYPE)
+        .unwrap()
+        .set_device("/device:CPU:0")
+        .unwrap()
+        .finish()
+        .unwrap();
+    let x = x.outputs().next().unwrap();
+    let neg = graph
+        .new_operation("Neg", "neg")
+        .unwrap()
+        .add_input(x)
+        .set_device("/device:CPU:0")
+        .unwrap()
+        .finish()
+        .unwrap();
+    assert_eq!("/device:CPU:0", neg.device());
+
+    let session = SessionBuilder::with_graph(&graph).unwrap().build().unwrap();
+    let x_val = Tensor::from_slice(&[2], &[1, -2]).unwrap();
+    let result = session
+        .run(
+            &[(x, x_val.as_ref())],
+            &[neg.outputs().next().unwrap()],
+            &[],
+        )
+        .unwrap();
+    let neg_val = result
+        .into_iter()
+        .next()
+        .unwrap()
+        .downcast::<i32>()
+        .unwrap();
+    assert_eq!(&[-1, 2], &neg_val[..]);
+}
+
+#[test]
+fn computes_addition_gradient() {
+    let proto = include_str!("data/addition.pb");
+    let graph = Graph::from_protobuff(proto).unwrap();
+    let x = graph
+        .operation_by_name("x")
+        .unwrap()
+        .outputs()
+        .next()
+        .unwrap();
+    let y = graph
+        .operation_by_name("y")
+        .unwrap()
+        .outputs()
+        .next()
+        .unwrap();
+    let z = graph
+        .operation_by_name("z")
+        .unwrap()
+        .outputs()
+        .next()
+        .unwrap();
+
+    let grads = graph.add_gradients(&[z], &[x], None).unwrap();
+    assert_eq!(1, grads.len());
+
+    let session = SessionBuilder::with_graph(&graph).unwrap().build().unwrap();
+    let x_val = Tensor::from_slice(&[3], &[1, 2, 3]).unwrap();
+    let y_val = Tensor::from_slice(&[3], &[4, 5, 6]).unwrap();
+    let result = session
+        .run(&[(x, x_val.as_ref()), (y, y_val.as_ref())], &grads, &[])
+        .unwrap();
+    let dx = result
+        .into_iter()
+        .next()
+        .unwrap()
+        .downcast::<i32>()
+        .unwrap();
+    assert_eq!(&[3], dx.shape());
+    assert_eq!(&[1, 1, 1], &dx[..]);
+}
+
+#[test]
+fn fails_computing_gradient_for_not_connected_output() {
+    let proto = include_str!("data/addition.pb");
+    let graph = Graph::from_protobuff(proto).unwrap();
+    let x = graph
+        .operation_by_name("x")
+        .unwrap()
+        .outputs()
+        .next()
+        .unwrap();
+    let y = graph
+        .operation_by_name("y")
+        .unwrap()
+        .outputs()
+        .next()
+        .unwrap();
+
+    graph
+        .add_gradients_with_prefix("grads", &[x], &[y], None)
+        .map(|_| ())
+        .unwrap_err();
+}
+
+#[test]
+fn extracts_subgraph_without_init_op() {
+    let proto = include_str!("data/addition.pb");
+    let graph = Graph::from_protobuff(proto).unwrap();
+    let z = graph.operation_by_name("z").unwrap().output(0).unwrap();
+
+    let extracted = graph.extract_subgraph(&[z], &[]).unwrap();
+    let mut names: Vec<_> = extracted
+        .operations()
+        .map(|op| op.name().to_owned())
+        .collect();
+    names.sort();
+    assert_eq!(vec!["x", "y", "z"], names);
+
+    let x = extracted.operation_by_name("x").unwrap().output(0).unwrap();
+    let y = extracted.operation_by_name("y").unwrap().output(0).unwrap();
+    let z = extracted.operation_by_name("z").unwrap().output(0).unwrap();
+    let session = SessionBuilder::with_graph(&extracted)
+        .unwrap()
+        .build()
+        .unwrap();
+    let x_val = Tensor::from_slice(&[2], &[1, 2]).unwrap();
+    let y_val = Tensor::from_slice(&[2], &[3, 4]).unwrap();
+    let result = session
+        .run(&[(x, x_val.as_ref()), (y, y_val.as_ref())], &[z], &[])
+        .unwrap();
+    let z_val = result
+        .into_iter()
+        .next()
+        .unwrap()
+        .downcast::<i32>()
+        .unwrap();
+    assert_eq!(&[4, 6], &z_val[..]);
+}
+
+#[test]
+fn extracts_subgraph_replacing_feeds_with_placeholders() {
+    let graph = Graph::new();
+    let neg = |name, input| {
+        graph
+            .new_operation("Neg", name)
+            .unwrap()
+            .add_input(input)
+            .finish()
+            .unwrap()
+            .output(0)
+            .unwrap()
+    };
+    let a = graph
+        .new_operation("Placeholder", "a")
+        .unwrap()
+        .set_attr_type("dtype", f32::TF_TYPE)
+        .unwrap()
+        .set_attr_shape("shape", Some(&[2]))
+        .unwrap()
+        .finish()
+        .unwrap()
+        .output(0)
+        .unwrap();
+    let b = neg("b", a);
+    let c = neg("c", b);
+
+    let extracted = graph.extract_subgraph(&[c], &[b]).unwrap();
+    assert_eq!(2, extracted.operations().count());
+    let b = extracted.operation_by_name("b").unwrap();
+    assert_eq!("Placeholder", b.op_type());
+    let b = b.output(0).unwrap();
+    assert_eq!(Some(vec![2]), extracted.tensor_shape(b).unwrap());
+
+    let c = extracted.operation_by_name("c").unwrap().output(0).unwrap();
+    let session = SessionBuilder::with_graph(&extracted)
+        .unwrap()
+        .build()
+        .unwrap();
+    let b_val = Tensor::from_slice(&[2], &[1.0f32, -2.0]).unwrap();
+    let result = session.run(&[(b, b_val.as_ref())], &[c], &[]).unwrap();
+    let c_val = result
+        .into_iter()
+        .next()
+        .unwrap()
+        .downcast::<f32>()
+        .unwrap();
+    assert_eq!(&[-1.0, 2.0], &c_val[..]);
+}
+
+#[test]
+fn extracts_subgraph_with_unique_placeholder_names() {
+    let graph = Graph::new();
+    let a = graph
+        .new_operation("Placeholder", "a")
+        .unwrap()
+        .set_attr_type("dtype", f32::TF_TYPE)
+        .unwrap()
+        .set_attr_shape("shape", Some(&[2]))
+        .unwrap()
+        .finish()
+        .unwrap()
+        .output(0)
+        .unwrap();
+    let s = graph
+        .new_operation("Unpack", "s")
+        .unwrap()
+        .add_input(a)
+        .set_attr_int("num", 2)
+        .unwrap()
+        .finish()
+        .unwrap();
+    let neg = |name, input| {
+        graph
+            .new_operation("Neg", name)
+            .unwrap()
+            .add_input(input)
+            .finish()
+            .unwrap()
+            .output(0)
+            .unwrap()
+    };
+    neg("s_1", s.output(0).unwrap());
+    let c = neg("c", s.output(1).unwrap());
+
+    // `a` is not needed for `c`, once `s:1` is fed
+    let extracted = graph
+        .extract_subgraph(&[c], &[s.output(1).unwrap(), a])
+        .unwrap();
+    let mut names: Vec<_> = extracted
+        .operations()
+        .map(|op| op.name().to_owned())
+        .collect();
+    names.sort();
+    assert_eq!(vec!["c", "s_1_1"], names);
+
+    let placeholder = extracted.operation_by_name("s_1_1").unwrap();
+    assert_eq!("Placeholder", placeholder.op_type());
+    assert_eq!(
+        Some(vec![]),
+        extracted
+            .tensor_shape(placeholder.output(0).unwrap())
+            .unwrap()
+    );
+}
+
+#[test]
+fn exports_graph_as_dot() {
+    let graph = Graph::new();
+    let x = graph
+        .new_operation("Placeholder", "model/x")
+        .unwrap()
+        .set_attr_type("dtype", f32::TF_TYPE)
+        .unwrap()
+        .set_attr_shape("shape", Some(&[-1, 3]))
+        .unwrap()
+        .finish()
+        .unwrap();
+    let init = graph
+        .new_operation("NoOp", "init")
+        .unwrap()
+        .finish()
+        .unwrap();
+    graph
+        .new_operation("Neg", "model/layer/neg")
+        .unwrap()
+        .add_input(x.output(0).unwrap())
+        .add_control_input(init)
+        .set_device("/device:CPU:0")
+        .unwrap()
+        .finish()
+        .unwrap();
+
+    let dot = graph.to_dot(true).unwrap();
+    assert!(dot.contains("subgraph \"cluster_model\" {"));
+    assert!(dot.contains("subgraph \"cluster_model/layer\" {"));
+    assert!(dot.contains(r#""model/layer/neg" [label="model/layer/neg\nNeg\n/device:CPU:0"];"#));
+    assert!(dot.contains(r#""model/x" -> "model/layer/neg" [label="float [?, 3]"];"#));
+    assert!(dot.contains(r#""init" -> "model/layer/neg" [style=dashed];"#));
+
+    let dot = graph.to_dot(false).unwrap();
+    assert!(!dot.contains("subgraph"));
+}
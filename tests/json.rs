@@ -0,0 +1,95 @@
+#![cfg(feature = "json")]
+
+use rustflow::{AnyTensor, Error, Tensor, TensorType};
+use serde_json::{json, Value};
+
+fn invalid(value: Value, dtype: tf::TF_DataType) -> String {
+    match AnyTensor::from_json(&value, dtype) {
+        Err(Error::InvalidJson(message)) => message,
+        result => panic!("unexpected result {:?}", result),
+    }
+}
+
+#[test]
+fn converts_nested_arrays() {
+    let value = json!([[1.5, 2.0, -3.0], [4.0, 5.0, 6.0]]);
+    let tensor = AnyTensor::from_json(&value, f32::TF_TYPE).unwrap();
+    assert_eq!(&[2, 3], tensor.shape());
+    assert_eq!(value, tensor.to_json().unwrap());
+
+    let tensor = tensor.downcast::<f32>().unwrap();
+    assert_eq!(&[1.5, 2.0, -3.0, 4.0, 5.0, 6.0], &tensor[..]);
+}
+
+#[test]
+fn converts_scalars() {
+    let tensor = AnyTensor::from_json(&json!(7), i64::TF_TYPE).unwrap();
+    assert!(tensor.shape().is_empty());
+    assert_eq!(json!(7), tensor.to_json().unwrap());
+
+    let tensor = AnyTensor::from_json(&json!(true), tf::TF_BOOL).unwrap();
+    assert!(tensor.shape().is_empty());
+    assert_eq!(json!(true), tensor.to_json().unwrap());
+}
+
+#[test]
+fn converts_empty_arrays() {
+    let tensor = AnyTensor::from_json(&json!([]), f32::TF_TYPE).unwrap();
+    assert_eq!(&[0], tensor.shape());
+    assert_eq!(json!([]), tensor.to_json().unwrap());
+
+    let tensor = AnyTensor::from_json(&json!([[], []]), i32::TF_TYPE).unwrap();
+    assert_eq!(&[2, 0], tensor.shape());
+    assert_eq!(json!([[], []]), tensor.to_json().unwrap());
+
+    let tensor: AnyTensor = Tensor::<i32>::from_slice(&[0, 3], &[]).unwrap().into();
+    assert_eq!(json!([]), tensor.to_json().unwrap());
+}
+
+#[test]
+fn rejects_ragged_arrays() {
+    invalid(json!([[1, 2], [3]]), i32::TF_TYPE);
+    invalid(json!([[1], [2, 3]]), i32::TF_TYPE);
+    invalid(json!([1, [2]]), i32::TF_TYPE);
+    invalid(json!([[1], 2]), i32::TF_TYPE);
+}
+
+#[test]
+fn rejects_invalid_values() {
+    let message = invalid(json!([1, 128]), i8::TF_TYPE);
+    assert!(message.contains("128"));
+    invalid(json!([-1]), u32::TF_TYPE);
+    invalid(json!([1.5]), i32::TF_TYPE);
+    invalid(json!([i64::MAX]), i32::TF_TYPE);
+    invalid(json!(["1"]), f32::TF_TYPE);
+    invalid(json!([1]), tf::TF_STRING);
+
+    let tensor = AnyTensor::from_json(&json!([127, -128]), i8::TF_TYPE).unwrap();
+    assert_eq!(&[127, -128], &tensor.downcast::<i8>().unwrap()[..]);
+    let tensor = AnyTensor::from_json(&json!([u64::MAX]), u64::TF_TYPE).unwrap();
+    assert_eq!(&[u64::MAX], &tensor.downcast::<u64>().unwrap()[..]);
+}
+
+#[test]
+fn converts_strings() {
+    let value = json!([["a", ""], ["bc", "zażółć"]]);
+    let tensor = AnyTensor::from_json(&value, tf::TF_STRING).unwrap();
+    assert_eq!(&[2, 2], tensor.shape());
+    assert_eq!(value, tensor.to_json().unwrap());
+
+    let tensor = AnyTensor::from_strings(&[], &[b"\xffa"]).unwrap();
+    assert_eq!(json!("\u{fffd}a"), tensor.to_json().unwrap());
+}
+
+#[test]
+fn rejects_unsupported_types() {
+    match AnyTensor::from_json(&json!([1.0]), tf::TF_HALF) {
+        Err(Error::UnsupportedType(dtype)) => assert_eq!(tf::TF_HALF, dtype),
+        result => panic!("unexpected result {:?}", result),
+    }
+    let tensor = AnyTensor::from_bytes(tf::TF_HALF, &[1], &[0, 0]).unwrap();
+    match tensor.to_json() {
+        Err(Error::UnsupportedType(dtype)) => assert_eq!(tf::TF_HALF, dtype),
+        result => panic!("unexpected result {:?}", result),
+    }
+}
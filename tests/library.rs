@@ -0,0 +1,27 @@
+use rustflow::{Error, Library};
+
+#[test]
+#[cfg(target_os = "linux")]
+fn lists_ops_of_loaded_library() {
+    // Any shared object can be loaded, this one just registers no operations
+    let library = Library::load("libm.so.6").unwrap();
+    assert!(library.ops().is_empty());
+}
+
+#[test]
+fn fails_loading_missing_library() {
+    let err = Library::load("tests/data/missing_ops.so")
+        .map(|_| ())
+        .unwrap_err();
+
+    match err {
+        Error::TFError(_) => (),
+        err => panic!("Unexpected error: {:?}", err),
+    }
+}
+
+#[test]
+fn fails_loading_library_with_nul_in_path() {
+    let err = Library::load("ops\0.so").map(|_| ()).unwrap_err();
+    assert_eq!(Error::NulString("ops\0.so".to_owned()), err);
+}
@@ -0,0 +1,101 @@
+use rustflow::npy::{read_npz, write_npz};
+use rustflow::{AnyTensor, Error, Tensor, TensorType};
+
+#[test]
+fn reads_npz_saved_by_numpy() {
+    let npz = include_bytes!("data/arrays.npz");
+    let mut arrays = read_npz(&npz[..]).unwrap();
+    assert_eq!(3, arrays.len());
+
+    let weights = arrays.remove("weights").unwrap().downcast::<f32>().unwrap();
+    assert_eq!(&[2, 3], weights.shape());
+    assert_eq!(&[0.0, 1.0, 2.0, 3.0, 4.0, 5.0], &weights[..]);
+
+    // Stored as big-endian
+    let ids = arrays.remove("ids").unwrap().downcast::<i64>().unwrap();
+    assert_eq!(&[3], ids.shape());
+    assert_eq!(&[7, 8, 9], &ids[..]);
+
+    // Stored in Fortran order
+    let pixels = arrays.remove("pixels").unwrap().downcast::<u8>().unwrap();
+    assert_eq!(&[2, 3], pixels.shape());
+    assert_eq!(&[1, 2, 3, 4, 5, 6], &pixels[..]);
+}
+
+#[test]
+fn writes_and_reads_npy() {
+    let tensor = Tensor::from_slice(&[3, 1], &[1u16, 2, 3]).unwrap();
+    let mut npy = vec![];
+    tensor.write_npy(&mut npy).unwrap();
+    assert!(npy.starts_with(b"\x93NUMPY\x01\x00"));
+    // Data is aligned to 64 bytes
+    assert_eq!(0, (npy.len() - 6) % 64);
+
+    let read = Tensor::<u16>::read_npy(&npy[..]).unwrap();
+    assert_eq!(&[3, 1], read.shape());
+    assert_eq!(&[1, 2, 3], &read[..]);
+}
+
+#[test]
+fn writes_and_reads_scalar_npy() {
+    let tensor = AnyTensor::from_bytes(tf::TF_BOOL, &[], &[1]).unwrap();
+    let mut npy = vec![];
+    tensor.write_npy(&mut npy).unwrap();
+
+    let read = AnyTensor::read_npy(&npy[..]).unwrap();
+    assert_eq!(tf::TF_BOOL, read.dtype());
+    assert!(read.shape().is_empty());
+    assert_eq!(&[1], read.as_bytes());
+}
+
+#[test]
+fn error_reading_npy_of_other_type() {
+    let tensor = Tensor::from_slice(&[2], &[1.0f64, 2.0]).unwrap();
+    let mut npy = vec![];
+    tensor.write_npy(&mut npy).unwrap();
+
+    match Tensor::<f32>::read_npy(&npy[..]) {
+        Err(Error::TypeMismatch { expected, found }) => {
+            assert_eq!(f32::TF_TYPE, expected);
+            assert_eq!(f64::TF_TYPE, found);
+        }
+        _ => panic!("expected type mismatch"),
+    }
+}
+
+#[test]
+fn error_reading_invalid_npy() {
+    match AnyTensor::read_npy(&b"not a numpy file"[..]) {
+        Err(Error::InvalidNpy(_)) => (),
+        _ => panic!("expected invalid npy error"),
+    }
+}
+
+#[test]
+fn writes_and_reads_npz() {
+    let x: AnyTensor = Tensor::from_slice(&[2, 2], &[1i32, 2, 3, 4])
+        .unwrap()
+        .into();
+    let y: AnyTensor = Tensor::from_slice(&[0], &[0.0f32; 0]).unwrap().into();
+    let mut npz = vec![];
+    write_npz(&mut npz, &[("x", &x), ("y", &y)]).unwrap();
+
+    let arrays = read_npz(&npz[..]).unwrap();
+    assert_eq!(x.as_bytes(), arrays["x"].as_bytes());
+    assert_eq!(&[2, 2], arrays["x"].shape());
+    assert_eq!(&[0], arrays["y"].shape());
+}
+
+#[test]
+fn error_reading_corrupted_npz() {
+    let x: AnyTensor = Tensor::from_slice(&[2], &[1i32, 2]).unwrap().into();
+    let mut npz = vec![];
+    write_npz(&mut npz, &[("x", &x)]).unwrap();
+    // Last byte of array data
+    npz[30 + 5 + 128 + 7] ^= 0xff;
+
+    match read_npz(&npz[..]) {
+        Err(Error::InvalidNpy(_)) => (),
+        _ => panic!("expected invalid npy error"),
+    }
+}
@@ -0,0 +1,93 @@
+use rustflow::op_def::{ops_registry, AttrValue};
+use rustflow::{Error, Function, Graph, TensorType};
+
+#[test]
+fn decodes_registered_op_definitions() {
+    let registry = ops_registry().unwrap();
+    let add = registry.get("Add").unwrap();
+
+    assert_eq!("Add", add.name);
+    assert!(!add.summary.is_empty());
+    let inputs: Vec<_> = add.input_args.iter().map(|arg| &arg.name[..]).collect();
+    assert_eq!(vec!["x", "y"], inputs);
+    assert_eq!("T", add.output_args[0].type_attr);
+
+    let t = add.attr("T").unwrap();
+    assert_eq!("type", t.attr_type);
+    match &t.allowed_values {
+        Some(AttrValue::List(types)) => {
+            assert!(types.contains(&AttrValue::Type(f32::TF_TYPE)));
+            assert!(types.contains(&AttrValue::Type(i32::TF_TYPE)));
+        }
+        allowed => panic!("Unexpected allowed values: {:?}", allowed),
+    }
+}
+
+#[test]
+fn decodes_attr_default_values() {
+    let registry = ops_registry().unwrap();
+    let placeholder = registry.get("Placeholder").unwrap();
+    let shape = placeholder.attr("shape").unwrap();
+    assert_eq!(Some(AttrValue::Shape(None)), shape.default_value);
+
+    let conv = registry.get("Conv2D").unwrap();
+    let data_format = conv.attr("data_format").unwrap();
+    assert_eq!(
+        Some(AttrValue::String(b"NHWC".to_vec())),
+        data_format.default_value
+    );
+    let dilations = conv.attr("dilations").unwrap();
+    assert_eq!(
+        Some(AttrValue::List(vec![AttrValue::Int(1); 4])),
+        dilations.default_value
+    );
+}
+
+#[test]
+fn accepts_graph_with_registered_ops_and_functions() {
+    let proto = include_str!("data/addition.pb");
+    let graph = Graph::from_protobuff(proto).unwrap();
+    let x = graph.operation_by_name("x").unwrap().output(0).unwrap();
+    let y = graph.operation_by_name("y").unwrap().output(0).unwrap();
+    let z = graph.operation_by_name("z").unwrap().output(0).unwrap();
+    let function = Function::from_graph(&graph, "custom_add", &[x, y], &[z]).unwrap();
+
+    let other = Graph::new();
+    other.copy_function(&function, None).unwrap();
+    let a = other
+        .new_operation("Placeholder", "a")
+        .unwrap()
+        .set_attr_type("dtype", i32::TF_TYPE)
+        .unwrap()
+        .finish()
+        .unwrap()
+        .output(0)
+        .unwrap();
+    other
+        .new_operation("custom_add", "call")
+        .unwrap()
+        .add_input(a)
+        .add_input(a)
+        .finish()
+        .unwrap();
+
+    let registry = ops_registry().unwrap();
+    registry.check_graph(&graph).unwrap();
+    registry.check_graph(&other).unwrap();
+    registry.check_graph_def(proto.as_bytes()).unwrap();
+}
+
+#[test]
+fn reports_unknown_ops_in_graph_def() {
+    // node { name: "a" op: "NotExisting" }
+    let mut node = vec![0x0a, 0x01, b'a', 0x12, 0x0b];
+    node.extend_from_slice(b"NotExisting");
+    let mut graph_def = vec![0x0a, node.len() as u8];
+    graph_def.extend_from_slice(&node);
+
+    let err = ops_registry()
+        .unwrap()
+        .check_graph_def(&graph_def)
+        .unwrap_err();
+    assert_eq!(Error::UnknownOps(vec!["NotExisting".to_owned()]), err);
+}
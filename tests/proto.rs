@@ -0,0 +1,167 @@
+#![cfg(feature = "proto")]
+
+use prost::Message;
+use rustflow::proto::{
+    attr_value, tensor_info, AttrValue, ConfigProto, DataType, GraphDef, MetaGraphDef, NodeDef,
+    SignatureDef, TensorInfo, TensorProto, TensorShapeProto,
+};
+use rustflow::session::SessionBuilder;
+use rustflow::{AnyTensor, Graph, Tensor};
+use std::convert::TryFrom;
+
+#[test]
+fn converts_graph_to_graph_def_and_back() {
+    let proto = include_str!("data/addition.pb");
+    let graph = Graph::from_protobuff(proto).unwrap();
+    let mut graph_def = graph.to_graph_def().unwrap();
+
+    let z = graph_def.node.iter().find(|node| node.name == "z").unwrap();
+    assert_eq!("Add", z.op);
+    assert_eq!(vec!["x", "y"], z.input);
+
+    graph_def.node.retain(|node| node.name != "init");
+    let pruned = Graph::from_graph_def(&graph_def).unwrap();
+    assert!(pruned.operation_by_name("z").is_some());
+    assert!(pruned.operation_by_name("init").is_none());
+}
+
+#[test]
+fn fails_loading_invalid_graph_def() {
+    let mut graph_def = GraphDef::default();
+    graph_def.node.push(Default::default());
+    Graph::from_graph_def(&graph_def).map(|_| ()).unwrap_err();
+}
+
+#[test]
+fn builds_graph_with_const_node() {
+    let value = Tensor::from_slice(&[2], &[1.0f32, 2.0]).unwrap();
+    let attr = |value| AttrValue { value: Some(value) };
+    let node = NodeDef {
+        name: "c".to_owned(),
+        op: "Const".to_owned(),
+        attr: vec![
+            (
+                "dtype".to_owned(),
+                attr(attr_value::Value::Type(DataType::DtFloat as i32)),
+            ),
+            (
+                "value".to_owned(),
+                attr(attr_value::Value::Tensor(
+                    TensorProto::try_from(value.as_ref()).unwrap(),
+                )),
+            ),
+        ]
+        .into_iter()
+        .collect(),
+        ..NodeDef::default()
+    };
+    let graph_def = GraphDef {
+        node: vec![node],
+        ..GraphDef::default()
+    };
+
+    let graph = Graph::from_graph_def(&graph_def).unwrap();
+    assert_eq!("Const", graph.operation_by_name("c").unwrap().op_type());
+}
+
+#[test]
+fn converts_tensor_to_tensor_proto_and_back() {
+    let tensor = Tensor::from_slice(&[2, 2], &[1.0f32, 2.0, 3.0, 4.0]).unwrap();
+    let proto = TensorProto::try_from(tensor.as_ref()).unwrap();
+    assert_eq!(
+        Some(TensorShapeProto::from(&[2, 2][..])),
+        proto.tensor_shape
+    );
+
+    let converted = AnyTensor::try_from(&proto)
+        .unwrap()
+        .downcast::<f32>()
+        .unwrap();
+    assert_eq!(&[2, 2], converted.shape());
+    assert_eq!(&[1.0, 2.0, 3.0, 4.0], &converted[..]);
+
+    let strings = AnyTensor::from_strings(&[2], &["a", "bc"]).unwrap();
+    let proto = TensorProto::try_from(&strings).unwrap();
+    assert_eq!(vec![b"a".to_vec(), b"bc".to_vec()], proto.string_val);
+    let converted = AnyTensor::try_from(&proto).unwrap();
+    assert_eq!(strings.strings().unwrap(), converted.strings().unwrap());
+}
+
+#[test]
+fn converts_tensor_proto_with_typed_values() {
+    let proto = TensorProto {
+        dtype: DataType::DtInt32 as i32,
+        tensor_shape: Some(TensorShapeProto::from(&[3][..])),
+        int_val: vec![7],
+        ..TensorProto::default()
+    };
+    let tensor = AnyTensor::try_from(&proto)
+        .unwrap()
+        .downcast::<i32>()
+        .unwrap();
+    assert_eq!(&[7, 7, 7], &tensor[..]);
+}
+
+#[test]
+fn builds_session_with_config_proto() {
+    let proto = include_str!("data/addition.pb");
+    let graph = Graph::from_protobuff(proto).unwrap();
+    let config = ConfigProto {
+        inter_op_parallelism_threads: 1,
+        device_count: vec![("CPU".to_owned(), 1)].into_iter().collect(),
+        ..ConfigProto::default()
+    };
+
+    let session = SessionBuilder::with_graph(&graph)
+        .unwrap()
+        .set_config_proto(&config)
+        .unwrap()
+        .build()
+        .unwrap();
+    assert!(!session.devices().unwrap().is_empty());
+}
+
+#[test]
+fn keeps_tensor_info_without_shape_unchanged() {
+    // name: "z:0", dtype: DT_INT32
+    let data = [0x0a, 0x03, b'z', b':', b'0', 0x10, 0x03];
+    let info = TensorInfo::decode(&data[..]).unwrap();
+    assert_eq!(
+        Some(tensor_info::Encoding::Name("z:0".to_owned())),
+        info.encoding
+    );
+    assert_eq!(None, info.tensor_shape);
+    assert_eq!(&data[..], &info.encode_to_vec()[..]);
+}
+
+#[test]
+fn converts_meta_graph_def() {
+    let proto = include_str!("data/addition.pb");
+    let graph = Graph::from_protobuff(proto).unwrap();
+
+    let info = |name: &str| TensorInfo {
+        encoding: Some(tensor_info::Encoding::Name(format!("{}:0", name))),
+        dtype: DataType::DtInt32 as i32,
+        tensor_shape: Some(TensorShapeProto::from(&[-1][..])),
+    };
+    let signature = SignatureDef {
+        inputs: vec![("x".to_owned(), info("x")), ("y".to_owned(), info("y"))]
+            .into_iter()
+            .collect(),
+        outputs: vec![("z".to_owned(), info("z"))].into_iter().collect(),
+        method_name: "tensorflow/serving/predict".to_owned(),
+    };
+    let meta_graph = MetaGraphDef {
+        graph_def: Some(graph.to_graph_def().unwrap()),
+        signature_def: vec![("serving_default".to_owned(), signature)]
+            .into_iter()
+            .collect(),
+        ..MetaGraphDef::default()
+    };
+
+    let decoded = MetaGraphDef::decode(&meta_graph.encode_to_vec()[..]).unwrap();
+    assert_eq!(meta_graph, decoded);
+
+    let graph = Graph::from_graph_def(decoded.graph_def.as_ref().unwrap()).unwrap();
+    assert!(graph.operation_by_name("z").is_some());
+}
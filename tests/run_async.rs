@@ -0,0 +1,171 @@
+#![cfg(feature = "async")]
+
+use futures::executor::block_on;
+use futures::future::join_all;
+use rustflow::run_pool::RunPool;
+use rustflow::session::SessionBuilder;
+use rustflow::{Error, Graph, Tensor};
+use std::sync::Arc;
+
+#[test]
+fn runs_many_requests_with_limited_in_flight() {
+    let proto = include_str!("data/addition.pb");
+    let graph = Arc::new(Graph::from_protobuff(proto).unwrap());
+    let session = SessionBuilder::with_shared_graph(graph.clone())
+        .unwrap()
+        .build()
+        .map(Arc::new)
+        .unwrap();
+    let pool = RunPool::new(2, 3);
+
+    let x = graph
+        .operation_by_name("x")
+        .unwrap()
+        .outputs()
+        .next()
+        .unwrap();
+    let y = graph
+        .operation_by_name("y")
+        .unwrap()
+        .outputs()
+        .next()
+        .unwrap();
+    let z = graph
+        .operation_by_name("z")
+        .unwrap()
+        .outputs()
+        .next()
+        .unwrap();
+
+    let runs = (0..64).map(|idx| {
+        let x_val = Tensor::from_slice(&[1], &[idx]).unwrap();
+        let y_val = Tensor::from_slice(&[1], &[idx]).unwrap();
+        session
+            .clone()
+            .run_async(&pool, vec![(x, x_val.into()), (y, y_val.into())], &[z], &[])
+    });
+
+    let results = block_on(join_all(runs));
+    for (idx, result) in results.into_iter().enumerate() {
+        let z_val = result
+            .unwrap()
+            .into_iter()
+            .next()
+            .unwrap()
+            .downcast::<i32>()
+            .unwrap();
+        assert_eq!(&[2 * idx as i32], &z_val[..]);
+    }
+}
+
+#[test]
+fn dropped_run_does_not_block_pool() {
+    let proto = include_str!("data/addition.pb");
+    let graph = Arc::new(Graph::from_protobuff(proto).unwrap());
+    let session = SessionBuilder::with_shared_graph(graph.clone())
+        .unwrap()
+        .build()
+        .map(Arc::new)
+        .unwrap();
+    let pool = RunPool::new(1, 1);
+    let init = graph.operation_by_name("init").unwrap();
+
+    let cancelled = session.clone().run_async(&pool, vec![], &[], &[init]);
+    drop(cancelled);
+
+    let result = block_on(session.run_async(&pool, vec![], &[], &[init])).unwrap();
+    assert!(result.is_empty());
+}
+
+#[test]
+fn run_fails_after_pool_shutdown() {
+    let proto = include_str!("data/addition.pb");
+    let graph = Arc::new(Graph::from_protobuff(proto).unwrap());
+    let session = SessionBuilder::with_shared_graph(graph.clone())
+        .unwrap()
+        .build()
+        .map(Arc::new)
+        .unwrap();
+    let pool = RunPool::new(1, 1);
+    let init = graph.operation_by_name("init").unwrap();
+
+    let run = session.run_async(&pool, vec![], &[], &[init]);
+    drop(pool);
+
+    block_on(run).unwrap_err();
+}
+
+#[test]
+fn run_fails_for_operation_missing_in_session_graph() {
+    let proto = include_str!("data/addition.pb");
+    let graph = Arc::new(Graph::from_protobuff(proto).unwrap());
+    let session = SessionBuilder::with_shared_graph(graph)
+        .unwrap()
+        .build()
+        .map(Arc::new)
+        .unwrap();
+    let pool = RunPool::new(1, 1);
+
+    let other = Graph::new();
+    let missing = other
+        .new_operation("NoOp", "missing")
+        .unwrap()
+        .finish()
+        .unwrap();
+
+    let result = block_on(session.run_async(&pool, vec![], &[], &[missing]));
+    assert_eq!(
+        Err(Error::OperationNotFound("missing".to_owned())),
+        result.map(|_| ())
+    );
+}
+
+#[test]
+fn run_fails_for_output_of_other_graph() {
+    let proto = include_str!("data/addition.pb");
+    let session =
+        SessionBuilder::with_shared_graph(Arc::new(Graph::from_protobuff(proto).unwrap()))
+            .unwrap()
+            .build()
+            .map(Arc::new)
+            .unwrap();
+    let pool = RunPool::new(1, 1);
+
+    // Graph has operation of the same name as session graph
+    let other = Graph::from_protobuff(proto).unwrap();
+    let z = other
+        .operation_by_name("z")
+        .unwrap()
+        .outputs()
+        .next()
+        .unwrap();
+
+    match block_on(session.run_async(&pool, vec![], &[z], &[])) {
+        Err(Error::InvalidGraph(message)) => assert!(message.contains("z")),
+        _ => panic!("expected invalid graph error"),
+    }
+}
+
+#[test]
+fn reports_run_async_failing_run() {
+    let proto = include_str!("data/addition.pb");
+    let graph = Arc::new(Graph::from_protobuff(proto).unwrap());
+    let session = SessionBuilder::with_shared_graph(graph.clone())
+        .unwrap()
+        .build()
+        .map(Arc::new)
+        .unwrap();
+    let pool = RunPool::new(1, 1);
+    let z = graph
+        .operation_by_name("z")
+        .unwrap()
+        .outputs()
+        .next()
+        .unwrap();
+
+    // Placeholders are not fed
+    match block_on(session.run_async(&pool, vec![], &[z], &[])) {
+        Err(Error::TFError(err)) => assert_eq!(Some("Session::run_async"), err.function()),
+        _ => panic!("expected tensorflow error"),
+    }
+}
@@ -0,0 +1,92 @@
+#![cfg(feature = "safetensors")]
+
+use rustflow::safetensors::{read_safetensors, write_safetensors};
+use rustflow::{AnyTensor, Error, Tensor};
+use std::fs::{self, File};
+use std::path::PathBuf;
+
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("rustflow_{}.safetensors", name))
+}
+
+#[test]
+fn reads_safetensors_file() {
+    let path = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/data/weights.safetensors"
+    );
+    let mut tensors = read_safetensors(path).unwrap();
+    assert_eq!(3, tensors.len());
+
+    let embedding = tensors
+        .remove("embedding")
+        .unwrap()
+        .downcast::<f32>()
+        .unwrap();
+    assert_eq!(&[2, 2], embedding.shape());
+    assert_eq!(&[0.5, 1.0, 1.5, 2.0], &embedding[..]);
+
+    let step = tensors.remove("step").unwrap().downcast::<i64>().unwrap();
+    assert!(step.shape().is_empty());
+    assert_eq!(&[42], &step[..]);
+
+    let mask = tensors.remove("mask").unwrap();
+    assert_eq!(tf::TF_BOOL, mask.dtype());
+    assert_eq!(&[1, 0, 1], mask.as_bytes());
+}
+
+#[test]
+fn writes_and_reads_safetensors() {
+    let ids: AnyTensor = Tensor::from_slice(&[3], &[1u8, 2, 3]).unwrap().into();
+    let weights: AnyTensor = Tensor::from_slice(&[2, 2], &[1.0f64, 2.0, 3.0, 4.0])
+        .unwrap()
+        .into();
+    let path = temp_path("round_trip");
+    write_safetensors(
+        File::create(&path).unwrap(),
+        &[("ids", &ids), ("weights", &weights)],
+    )
+    .unwrap();
+
+    let mut tensors = read_safetensors(&path).unwrap();
+    assert_eq!(ids.as_bytes(), tensors["ids"].as_bytes());
+    assert_eq!(&[2, 2], tensors["weights"].shape());
+    // Written data is aligned, so tensor is mapped directly
+    assert_eq!(0, tensors["weights"].as_bytes().as_ptr() as usize % 64);
+
+    // Mapping is private
+    let mut mapped = tensors
+        .remove("weights")
+        .unwrap()
+        .downcast::<f64>()
+        .unwrap();
+    mapped[0] = 10.0;
+    let tensors = read_safetensors(&path).unwrap();
+    assert_eq!(weights.as_bytes(), tensors["weights"].as_bytes());
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn error_writing_string_tensor() {
+    let names = AnyTensor::from_strings(&[1], &["a"]).unwrap();
+    match write_safetensors(vec![], &[("names", &names)]) {
+        Err(Error::UnsupportedType(dtype)) => assert_eq!(tf::TF_STRING, dtype),
+        _ => panic!("expected unsupported type error"),
+    }
+}
+
+#[test]
+fn error_reading_truncated_file() {
+    let ids: AnyTensor = Tensor::from_slice(&[4], &[1i32, 2, 3, 4]).unwrap().into();
+    let mut data = vec![];
+    write_safetensors(&mut data, &[("ids", &ids)]).unwrap();
+    let path = temp_path("truncated");
+    fs::write(&path, &data[..data.len() - 1]).unwrap();
+
+    let result = read_safetensors(&path);
+    fs::remove_file(path).unwrap();
+    match result {
+        Err(Error::InvalidSafetensors(_)) => (),
+        _ => panic!("expected invalid safetensors error"),
+    }
+}
@@ -0,0 +1,154 @@
+use rustflow::error::TFCode;
+use rustflow::session::SessionBuilder;
+use rustflow::{Error, Graph, Session, Tensor};
+use std::sync::Arc;
+use std::thread;
+
+fn assert_send_sync<T: Send + Sync>() {}
+
+#[test]
+fn session_and_graph_are_send_and_sync() {
+    assert_send_sync::<Session>();
+    assert_send_sync::<rustflow::graph::OwnedGraph>();
+}
+
+#[test]
+fn runs_addition_from_many_threads() {
+    let proto = include_str!("data/addition.pb");
+    let graph = Graph::from_protobuff(proto).unwrap();
+    let session = SessionBuilder::with_graph(&graph).unwrap().build().unwrap();
+
+    thread::scope(|scope| {
+        for thread_idx in 0..16 {
+            let graph = &graph;
+            let session = &session;
+            scope.spawn(move || {
+                let x = graph
+                    .operation_by_name("x")
+                    .unwrap()
+                    .outputs()
+                    .next()
+                    .unwrap();
+                let y = graph
+                    .operation_by_name("y")
+                    .unwrap()
+                    .outputs()
+                    .next()
+                    .unwrap();
+                let z = graph
+                    .operation_by_name("z")
+                    .unwrap()
+                    .outputs()
+                    .next()
+                    .unwrap();
+
+                for iter in 0..200 {
+                    let x_val = Tensor::from_slice(&[2], &[thread_idx, iter]).unwrap();
+                    let y_val = Tensor::from_slice(&[2], &[iter, thread_idx]).unwrap();
+
+                    let result = session
+                        .run(&[(x, x_val.as_ref()), (y, y_val.as_ref())], &[z], &[])
+                        .unwrap();
+                    let z_val = result
+                        .into_iter()
+                        .next()
+                        .unwrap()
+                        .downcast::<i32>()
+                        .unwrap();
+                    assert_eq!(&[thread_idx + iter, iter + thread_idx], &z_val[..]);
+                }
+            });
+        }
+    });
+}
+
+#[test]
+fn shared_graph_session_outlives_graph_handle() {
+    let proto = include_str!("data/addition.pb");
+    let graph = Arc::new(Graph::from_protobuff(proto).unwrap());
+    let session = SessionBuilder::with_shared_graph(graph.clone())
+        .unwrap()
+        .build()
+        .unwrap();
+    drop(graph);
+
+    let handle = thread::spawn(move || {
+        let graph = session.graph();
+        let x = graph
+            .operation_by_name("x")
+            .unwrap()
+            .outputs()
+            .next()
+            .unwrap();
+        let y = graph
+            .operation_by_name("y")
+            .unwrap()
+            .outputs()
+            .next()
+            .unwrap();
+        let z = graph
+            .operation_by_name("z")
+            .unwrap()
+            .outputs()
+            .next()
+            .unwrap();
+        let x_val = Tensor::from_slice(&[1], &[1]).unwrap();
+        let y_val = Tensor::from_slice(&[1], &[2]).unwrap();
+
+        let result = session
+            .run(&[(x, x_val.as_ref()), (y, y_val.as_ref())], &[z], &[])
+            .unwrap();
+        let z_val = result
+            .into_iter()
+            .next()
+            .unwrap()
+            .downcast::<i32>()
+            .unwrap();
+        assert_eq!(&[3], &z_val[..]);
+
+        session.close().unwrap().delete().unwrap();
+    });
+    handle.join().unwrap();
+}
+
+#[test]
+fn lists_cpu_device() {
+    let proto = include_str!("data/addition.pb");
+    let graph = Graph::from_protobuff(proto).unwrap();
+    let session = SessionBuilder::with_graph(&graph).unwrap().build().unwrap();
+
+    let devices = session.devices().unwrap();
+    let cpu = devices
+        .iter()
+        .find(|device| device.device_type == "CPU")
+        .unwrap();
+    assert!(cpu.name.ends_with("CPU:0"));
+}
+
+#[test]
+fn reports_node_failing_session_run() {
+    let proto = include_str!("data/addition.pb");
+    let graph = Graph::from_protobuff(proto).unwrap();
+    let session = SessionBuilder::with_graph(&graph).unwrap().build().unwrap();
+    let z = graph
+        .operation_by_name("z")
+        .unwrap()
+        .outputs()
+        .next()
+        .unwrap();
+
+    // Placeholders are not fed
+    let err = match session.run(&[], &[z], &[]) {
+        Err(Error::TFError(err)) => err,
+        _ => panic!("expected tensorflow error"),
+    };
+    assert_eq!(TFCode::InvalidArgument, err.code());
+    assert_eq!(Some("Session::run"), err.function());
+    let node = err.node().unwrap();
+    assert!(node == "x" || node == "y");
+    assert!(err.message().contains("placeholder"));
+    assert!(err.to_string().starts_with(&format!(
+        "Session::run failed at node `{}` (InvalidArgument): ",
+        node
+    )));
+}
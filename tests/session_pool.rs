@@ -0,0 +1,142 @@
+use rustflow::error::TFCode;
+use rustflow::session_pool::SessionPoolBuilder;
+use rustflow::train::Variable;
+use rustflow::{Error, Graph, Tensor};
+use std::sync::Arc;
+use std::thread;
+
+fn addition_graph() -> Arc<rustflow::graph::OwnedGraph> {
+    let proto = include_str!("data/addition.pb");
+    Arc::new(Graph::from_protobuff(proto).unwrap())
+}
+
+#[test]
+fn shares_sessions_between_threads() {
+    let pool = SessionPoolBuilder::new(addition_graph(), 2)
+        .warm_up_feed("x", 0, Tensor::from_slice(&[1], &[0]).unwrap().into())
+        .warm_up_feed("y", 0, Tensor::from_slice(&[1], &[0]).unwrap().into())
+        .warm_up_fetch("z", 0)
+        .build()
+        .unwrap();
+
+    thread::scope(|scope| {
+        for idx in 0..8 {
+            let pool = &pool;
+            scope.spawn(move || {
+                let session = pool.get().unwrap();
+                let graph = session.graph();
+                let x = graph
+                    .operation_by_name("x")
+                    .unwrap()
+                    .outputs()
+                    .next()
+                    .unwrap();
+                let y = graph
+                    .operation_by_name("y")
+                    .unwrap()
+                    .outputs()
+                    .next()
+                    .unwrap();
+                let z = graph
+                    .operation_by_name("z")
+                    .unwrap()
+                    .outputs()
+                    .next()
+                    .unwrap();
+
+                let x_val = Tensor::from_slice(&[1], &[idx]).unwrap();
+                let y_val = Tensor::from_slice(&[1], &[1]).unwrap();
+                let result = session
+                    .run(&[(x, x_val.as_ref()), (y, y_val.as_ref())], &[z], &[])
+                    .unwrap();
+                let z_val = result
+                    .into_iter()
+                    .next()
+                    .unwrap()
+                    .downcast::<i32>()
+                    .unwrap();
+                assert_eq!(&[idx + 1], &z_val[..]);
+            });
+        }
+    });
+}
+
+#[test]
+fn limits_sessions_and_rebuilds_unhealthy() {
+    let pool = SessionPoolBuilder::new(addition_graph(), 1)
+        .warm_up_target("init")
+        .build()
+        .unwrap();
+
+    let session = pool.try_get().unwrap().unwrap();
+    assert!(pool.try_get().is_none());
+
+    session.mark_unhealthy();
+    drop(session);
+
+    let session = pool.try_get().unwrap().unwrap();
+    let init = session.graph().operation_by_name("init").unwrap();
+    session.run(&[], &[], &[init]).unwrap();
+}
+
+#[test]
+fn rebuilds_sessions_failing_with_internal_error() {
+    let graph = Graph::new();
+    let initial = Tensor::from_slice(&[], &[1.0f32]).unwrap();
+    Variable::new(&graph, "v", initial.as_ref()).unwrap();
+    let mutex = graph
+        .new_operation("MutexV2", "mutex")
+        .unwrap()
+        .finish()
+        .unwrap();
+    let lock = graph
+        .new_operation("MutexLock", "lock")
+        .unwrap()
+        .add_input(mutex.output(0).unwrap())
+        .finish()
+        .unwrap();
+    // Mutex locks have no zeros-like function, which tensorflow reports as
+    // internal error
+    graph
+        .new_operation("ZerosLike", "zeros")
+        .unwrap()
+        .add_input(lock.output(0).unwrap())
+        .finish()
+        .unwrap();
+    let pool = SessionPoolBuilder::new(Arc::new(graph), 1).build().unwrap();
+
+    let session = pool.get().unwrap();
+    let graph = session.graph();
+    let init = graph.operation_by_name("v/Assign").unwrap();
+    let v = graph.operation_by_name("v").unwrap().output(0).unwrap();
+    let zeros = graph.operation_by_name("zeros").unwrap();
+    session.run(&[], &[], &[init]).unwrap();
+    session.run(&[], &[v], &[]).unwrap();
+    match session.run(&[], &[], &[zeros]) {
+        Err(Error::TFError(err)) => assert_eq!(TFCode::Internal, err.code()),
+        result => panic!("unexpected result {:?}", result.map(|_| ())),
+    }
+    drop(session);
+
+    // Rebuilt session has variable uninitialized again
+    let session = pool.get().unwrap();
+    let v = session
+        .graph()
+        .operation_by_name("v")
+        .unwrap()
+        .output(0)
+        .unwrap();
+    match session.run(&[], &[v], &[]) {
+        Err(Error::TFError(err)) => assert_eq!(TFCode::FailedPrecondition, err.code()),
+        result => panic!("unexpected result {:?}", result.map(|_| ())),
+    }
+}
+
+#[test]
+fn fails_on_invalid_warm_up() {
+    SessionPoolBuilder::new(addition_graph(), 1)
+        .warm_up_target("missing")
+        .build()
+        .map(|_| ())
+        .unwrap_err();
+}
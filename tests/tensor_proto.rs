@@ -0,0 +1,112 @@
+use rustflow::{AnyTensor, Error, Tensor, TensorType};
+
+/// Encodes `TensorProto` of given type and shape, followed by raw value
+/// fields
+fn tensor_proto(dtype: tf::TF_DataType, shape: &[u8], values: &[u8]) -> Vec<u8> {
+    let mut dims = vec![];
+    for size in shape {
+        dims.extend_from_slice(&[0x12, 0x02, 0x08, *size]);
+    }
+    let mut proto = vec![0x08, dtype as u8, 0x12, dims.len() as u8];
+    proto.extend_from_slice(&dims);
+    proto.extend_from_slice(values);
+    proto
+}
+
+#[test]
+fn converts_tensor_to_tensor_proto_and_back() {
+    let tensor = Tensor::from_slice(&[2, 3], &[1.0f64, 2.0, 3.0, 4.0, 5.0, 6.0]).unwrap();
+    let proto = tensor.to_tensor_proto().unwrap();
+
+    let decoded = Tensor::<f64>::from_tensor_proto(&proto).unwrap();
+    assert_eq!(&[2, 3], decoded.shape());
+    assert_eq!(&tensor[..], &decoded[..]);
+}
+
+#[test]
+fn converts_string_tensor_to_tensor_proto_and_back() {
+    let tensor = AnyTensor::from_strings(&[3], &[&b"a"[..], b"", b"\xff\x00"]).unwrap();
+    let proto = tensor.to_tensor_proto().unwrap();
+
+    let decoded = AnyTensor::from_tensor_proto(&proto).unwrap();
+    assert_eq!(tf::TF_STRING, decoded.dtype());
+    assert_eq!(&[3], decoded.shape());
+    assert_eq!(
+        vec![b"a".to_vec(), vec![], vec![0xff, 0x00]],
+        decoded.strings().unwrap()
+    );
+}
+
+#[test]
+fn converts_empty_tensor_to_tensor_proto_and_back() {
+    let tensor = Tensor::from_slice(&[0, 2], &[0u8; 0]).unwrap();
+    let proto = tensor.to_tensor_proto().unwrap();
+
+    let decoded = Tensor::<u8>::from_tensor_proto(&proto).unwrap();
+    assert_eq!(&[0, 2], decoded.shape());
+    assert!(decoded.is_empty());
+}
+
+#[test]
+fn reads_typed_values() {
+    // int_val [-1, 2], packed
+    let values = [
+        0x3a, 0x0b, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01, 0x02,
+    ];
+    let proto = tensor_proto(tf::TF_INT16, &[2], &values);
+    let tensor = Tensor::<i16>::from_tensor_proto(&proto).unwrap();
+    assert_eq!(&[-1, 2], &tensor[..]);
+
+    // int64_val 5 and 6, not packed
+    let proto = tensor_proto(tf::TF_INT64, &[2], &[0x50, 0x05, 0x50, 0x06]);
+    let tensor = Tensor::<i64>::from_tensor_proto(&proto).unwrap();
+    assert_eq!(&[5, 6], &tensor[..]);
+}
+
+#[test]
+fn repeats_last_typed_value() {
+    // Splat scalar float_val 1.0
+    let proto = tensor_proto(tf::TF_FLOAT, &[3], &[0x2d, 0x00, 0x00, 0x80, 0x3f]);
+    let tensor = Tensor::<f32>::from_tensor_proto(&proto).unwrap();
+    assert_eq!(&[1.0, 1.0, 1.0], &tensor[..]);
+
+    // bool_val [false, true]
+    let proto = tensor_proto(tf::TF_BOOL, &[2, 2], &[0x5a, 0x02, 0x00, 0x01]);
+    let tensor = AnyTensor::from_tensor_proto(&proto).unwrap();
+    assert_eq!(&[0, 1, 1, 1], tensor.as_bytes());
+
+    // string_val "ab"
+    let proto = tensor_proto(tf::TF_STRING, &[2], &[0x42, 0x02, b'a', b'b']);
+    let tensor = AnyTensor::from_tensor_proto(&proto).unwrap();
+    assert_eq!(vec![b"ab".to_vec(); 2], tensor.strings().unwrap());
+}
+
+#[test]
+fn fills_tensor_without_values_with_zeros() {
+    let proto = tensor_proto(tf::TF_UINT32, &[2], &[]);
+    let tensor = Tensor::<u32>::from_tensor_proto(&proto).unwrap();
+    assert_eq!(&[0, 0], &tensor[..]);
+}
+
+#[test]
+fn error_reading_too_many_values() {
+    let proto = tensor_proto(tf::TF_INT32, &[1], &[0x3a, 0x02, 0x01, 0x02]);
+    match AnyTensor::from_tensor_proto(&proto) {
+        Err(Error::InvalidProtobuf(_)) => (),
+        _ => panic!("expected invalid protobuf error"),
+    }
+}
+
+#[test]
+fn error_reading_tensor_proto_of_other_type() {
+    let tensor = Tensor::from_slice(&[1], &[1i32]).unwrap();
+    let proto = tensor.to_tensor_proto().unwrap();
+
+    match Tensor::<u32>::from_tensor_proto(&proto) {
+        Err(Error::TypeMismatch { expected, found }) => {
+            assert_eq!(u32::TF_TYPE, expected);
+            assert_eq!(i32::TF_TYPE, found);
+        }
+        _ => panic!("expected type mismatch"),
+    }
+}
@@ -0,0 +1,254 @@
+use rustflow::session::SessionBuilder;
+use rustflow::train::{self, Adam, GradientDescent, Optimizer, Saver, Variable};
+use rustflow::{Graph, Operation, Output, Tensor, TensorType};
+
+struct Model<'a> {
+    x: Output<'a>,
+    y: Output<'a>,
+    loss: Output<'a>,
+    w: Variable<'a>,
+    b: Variable<'a>,
+}
+
+fn op<'a>(graph: &'a Graph, op_type: &str, name: &str, inputs: &[Output<'a>]) -> Operation<'a> {
+    inputs
+        .iter()
+        .fold(graph.new_operation(op_type, name).unwrap(), |op, input| {
+            op.add_input(*input)
+        })
+        .set_attr_type("T", f32::TF_TYPE)
+        .unwrap()
+        .finish()
+        .unwrap()
+}
+
+fn placeholder<'a>(graph: &'a Graph, name: &str) -> Output<'a> {
+    graph
+        .new_operation("Placeholder", name)
+        .unwrap()
+        .set_attr_type("dtype", f32::TF_TYPE)
+        .unwrap()
+        .finish()
+        .unwrap()
+        .output(0)
+        .unwrap()
+}
+
+/// Linear regression: loss = mean((w * x + b - y)^2)
+fn model(graph: &Graph) -> Model {
+    let x = placeholder(graph, "x");
+    let y = placeholder(graph, "y");
+    let zero = Tensor::from_slice(&[], &[0.0f32]).unwrap();
+    let w = Variable::new(graph, "w", zero.as_ref()).unwrap();
+    let b = Variable::new(graph, "b", zero.as_ref()).unwrap();
+
+    let mul = op(graph, "Mul", "mul", &[w.output(), x]);
+    let add = op(graph, "Add", "add", &[mul.output(0).unwrap(), b.output()]);
+    let diff = op(graph, "Sub", "sub", &[add.output(0).unwrap(), y]);
+    let square = op(graph, "Square", "square", &[diff.output(0).unwrap()]);
+    let axis = graph
+        .new_operation("Const", "axis")
+        .unwrap()
+        .set_attr_tensor("value", Tensor::from_slice(&[1], &[0]).unwrap().as_ref())
+        .unwrap()
+        .set_attr_type("dtype", i32::TF_TYPE)
+        .unwrap()
+        .finish()
+        .unwrap();
+    let loss = graph
+        .new_operation("Mean", "loss")
+        .unwrap()
+        .add_input(square.output(0).unwrap())
+        .add_input(axis.output(0).unwrap())
+        .set_attr_type("T", f32::TF_TYPE)
+        .unwrap()
+        .set_attr_type("Tidx", i32::TF_TYPE)
+        .unwrap()
+        .finish()
+        .unwrap();
+
+    Model {
+        x,
+        y,
+        loss: loss.output(0).unwrap(),
+        w,
+        b,
+    }
+}
+
+fn fetch(session: &rustflow::Session, variable: &Variable) -> f32 {
+    let result = session.run(&[], &[variable.output()], &[]).unwrap();
+    let value = result
+        .into_iter()
+        .next()
+        .unwrap()
+        .downcast::<f32>()
+        .unwrap();
+    value[0]
+}
+
+fn trains_linear_regression(optimizer: impl Optimizer, steps: usize) {
+    let graph = Graph::new();
+    let model = model(&graph);
+    let train_op = optimizer
+        .minimize(
+            &graph,
+            "train",
+            model.loss,
+            &[model.w.clone(), model.b.clone()],
+        )
+        .unwrap();
+    let mut variables = vec![model.w.clone(), model.b.clone()];
+    variables.extend_from_slice(train_op.variables());
+    let init = train::initializer(&graph, "init", &variables).unwrap();
+
+    let session = SessionBuilder::with_graph(&graph).unwrap().build().unwrap();
+    session.run(&[], &[], &[init]).unwrap();
+
+    let xs: Vec<f32> = (0..8).map(|x| x as f32 / 4.0).collect();
+    let ys: Vec<f32> = xs.iter().map(|x| 3.0 * x + 2.0).collect();
+    let xs = Tensor::from_slice(&[8], &xs).unwrap();
+    let ys = Tensor::from_slice(&[8], &ys).unwrap();
+    for _ in 0..steps {
+        session
+            .run(
+                &[(model.x, xs.as_ref()), (model.y, ys.as_ref())],
+                &[],
+                &[train_op.operation()],
+            )
+            .unwrap();
+    }
+
+    assert!((fetch(&session, &model.w) - 3.0).abs() < 0.05);
+    assert!((fetch(&session, &model.b) - 2.0).abs() < 0.05);
+}
+
+#[test]
+fn gradient_descent_fits_linear_regression() {
+    trains_linear_regression(GradientDescent::new(0.1), 1000);
+}
+
+#[test]
+fn adam_fits_linear_regression() {
+    trains_linear_regression(Adam::new(0.05), 2000);
+}
+
+#[test]
+fn adam_updates_variables_of_different_types() {
+    let graph = Graph::new();
+    let a = Variable::new(
+        &graph,
+        "a",
+        Tensor::from_slice(&[], &[1.0f32]).unwrap().as_ref(),
+    )
+    .unwrap();
+    let b = Variable::new(
+        &graph,
+        "b",
+        Tensor::from_slice(&[], &[1.0f64]).unwrap().as_ref(),
+    )
+    .unwrap();
+    let square_a = op(&graph, "Square", "square_a", &[a.output()]);
+    let square_b = graph
+        .new_operation("Square", "square_b")
+        .unwrap()
+        .add_input(b.output())
+        .set_attr_type("T", f64::TF_TYPE)
+        .unwrap()
+        .finish()
+        .unwrap();
+    let cast_b = graph
+        .new_operation("Cast", "cast_b")
+        .unwrap()
+        .add_input(square_b.output(0).unwrap())
+        .set_attr_type("SrcT", f64::TF_TYPE)
+        .unwrap()
+        .set_attr_type("DstT", f32::TF_TYPE)
+        .unwrap()
+        .finish()
+        .unwrap();
+    let loss = op(
+        &graph,
+        "Add",
+        "loss",
+        &[square_a.output(0).unwrap(), cast_b.output(0).unwrap()],
+    );
+
+    let train_op = Adam::new(0.1)
+        .minimize(
+            &graph,
+            "train",
+            loss.output(0).unwrap(),
+            &[a.clone(), b.clone()],
+        )
+        .unwrap();
+    let mut variables = vec![a.clone(), b.clone()];
+    variables.extend_from_slice(train_op.variables());
+    let init = train::initializer(&graph, "init", &variables).unwrap();
+
+    let session = SessionBuilder::with_graph(&graph).unwrap().build().unwrap();
+    session.run(&[], &[], &[init]).unwrap();
+    for _ in 0..100 {
+        session.run(&[], &[], &[train_op.operation()]).unwrap();
+    }
+
+    assert!(fetch(&session, &a).abs() < 0.5);
+    let result = session.run(&[], &[b.output()], &[]).unwrap();
+    let value = result
+        .into_iter()
+        .next()
+        .unwrap()
+        .downcast::<f64>()
+        .unwrap();
+    assert!(value[0].abs() < 0.5);
+}
+
+#[test]
+fn saves_and_restores_variables() {
+    let graph = Graph::new();
+    let initial = Tensor::from_slice(&[2], &[1.0f32, 2.0]).unwrap();
+    let variables = [Variable::new(&graph, "w", initial.as_ref()).unwrap()];
+    let w = &variables[0];
+    let init = train::initializer(&graph, "init", &variables).unwrap();
+    let saver = Saver::new(&graph, "save", &variables).unwrap();
+    let session = SessionBuilder::with_graph(&graph).unwrap().build().unwrap();
+    session.run(&[], &[], &[init]).unwrap();
+
+    let prefix = std::env::temp_dir().join(format!("rustflow-saver-{}", std::process::id()));
+    let prefix = prefix.to_str().unwrap();
+    saver.save(&session, prefix).unwrap();
+
+    let other = Tensor::from_slice(&[2], &[5.0f32, 6.0]).unwrap();
+    let assign = graph
+        .new_operation("Assign", "overwrite")
+        .unwrap()
+        .add_input(w.output())
+        .add_input(
+            graph
+                .new_operation("Const", "other")
+                .unwrap()
+                .set_attr_tensor("value", other.as_ref())
+                .unwrap()
+                .set_attr_type("dtype", f32::TF_TYPE)
+                .unwrap()
+                .finish()
+                .unwrap()
+                .output(0)
+                .unwrap(),
+        )
+        .set_attr_type("T", f32::TF_TYPE)
+        .unwrap()
+        .finish()
+        .unwrap();
+    session.run(&[], &[], &[assign]).unwrap();
+
+    saver.restore(&session, prefix).unwrap();
+    let result = session.run(&[], &[w.output()], &[]).unwrap();
+    let value = result
+        .into_iter()
+        .next()
+        .unwrap()
+        .downcast::<f32>()
+        .unwrap();
+    assert_eq!(&[1.0, 2.0], &value[..]);
+}
@@ -0,0 +1,277 @@
+use rustflow::op_def::AttrValue;
+use rustflow::session::SessionBuilder;
+use rustflow::transform::{GraphTransform, NodeDef, Pattern};
+use rustflow::{Error, Graph, Operation, Tensor, TensorType};
+
+/// Builds graph computing `neg = -(x + 1)`, with `unused` constant and
+/// identities between operations
+fn build_graph(graph: &Graph) {
+    let x = graph
+        .new_operation("Placeholder", "x")
+        .unwrap()
+        .set_attr_type("dtype", f32::TF_TYPE)
+        .unwrap()
+        .finish()
+        .unwrap();
+    let one = graph
+        .new_operation("Const", "one")
+        .unwrap()
+        .set_attr_type("dtype", f32::TF_TYPE)
+        .unwrap()
+        .set_attr_tensor(
+            "value",
+            Tensor::from_slice(&[], &[1.0f32]).unwrap().as_ref(),
+        )
+        .unwrap()
+        .finish()
+        .unwrap();
+    graph
+        .new_operation("Const", "unused")
+        .unwrap()
+        .set_attr_type("dtype", f32::TF_TYPE)
+        .unwrap()
+        .set_attr_tensor(
+            "value",
+            Tensor::from_slice(&[], &[2.0f32]).unwrap().as_ref(),
+        )
+        .unwrap()
+        .finish()
+        .unwrap();
+    let x_id = identity(graph, "x_id", x);
+    let add = graph
+        .new_operation("Add", "add")
+        .unwrap()
+        .add_input(x_id.output(0).unwrap())
+        .add_input(one.output(0).unwrap())
+        .finish()
+        .unwrap();
+    let add_id = identity(graph, "add_id", add);
+    graph
+        .new_operation("Neg", "neg")
+        .unwrap()
+        .add_input(add_id.output(0).unwrap())
+        .finish()
+        .unwrap();
+}
+
+fn identity<'a>(graph: &'a Graph, name: &str, input: Operation<'a>) -> Operation<'a> {
+    graph
+        .new_operation("Identity", name)
+        .unwrap()
+        .add_input(input.output(0).unwrap())
+        .finish()
+        .unwrap()
+}
+
+/// Runs graph for `x = [1, 2]`
+fn eval(graph: &Graph, x: &str, output: &str) -> Vec<f32> {
+    let x = graph.operation_by_name(x).unwrap().output(0).unwrap();
+    let output = graph.operation_by_name(output).unwrap().output(0).unwrap();
+    let session = SessionBuilder::with_graph(graph).unwrap().build().unwrap();
+    let x_val = Tensor::from_slice(&[2], &[1.0f32, 2.0]).unwrap();
+    let result = session.run(&[(x, x_val.as_ref())], &[output], &[]).unwrap();
+    let result = result
+        .into_iter()
+        .next()
+        .unwrap()
+        .downcast::<f32>()
+        .unwrap();
+    result.to_vec()
+}
+
+#[test]
+fn removes_identities_and_unused_nodes() {
+    let graph = Graph::new();
+    build_graph(&graph);
+    let transformed = GraphTransform::new(&graph, &["neg"])
+        .unwrap()
+        .remove_identity()
+        .unwrap()
+        .strip_unused_nodes()
+        .unwrap()
+        .finish()
+        .unwrap();
+
+    let mut names: Vec<_> = transformed
+        .operations()
+        .map(|op| op.name().to_owned())
+        .collect();
+    names.sort();
+    assert_eq!(vec!["add", "neg", "one", "x"], names);
+    assert_eq!(vec![-2.0, -3.0], eval(&transformed, "x", "neg"));
+}
+
+#[test]
+fn keeps_output_identities() {
+    let graph = Graph::new();
+    build_graph(&graph);
+    let transformed = GraphTransform::new(&graph, &["add_id"])
+        .unwrap()
+        .remove_identity()
+        .unwrap()
+        .finish()
+        .unwrap();
+
+    assert!(transformed.operation_by_name("x_id").is_none());
+    assert!(transformed.operation_by_name("add_id").is_some());
+    assert_eq!(vec![2.0, 3.0], eval(&transformed, "x", "add_id"));
+}
+
+#[test]
+fn error_stripping_with_unknown_output() {
+    let graph = Graph::new();
+    build_graph(&graph);
+    let result = GraphTransform::new(&graph, &["missing"])
+        .unwrap()
+        .strip_unused_nodes();
+    match result {
+        Err(Error::InvalidGraph(_)) => (),
+        _ => panic!("expected invalid graph error"),
+    }
+}
+
+#[test]
+fn reports_transform_failing_graph_import() {
+    let graph = Graph::new();
+    build_graph(&graph);
+    let result = GraphTransform::new(&graph, &["neg"])
+        .unwrap()
+        .replace_matches(&Pattern::new("Neg"), |m| {
+            let mut node = NodeDef::new(&m.node.name, "NotExistingOp");
+            node.inputs = m.node.inputs.clone();
+            Ok(vec![node])
+        })
+        .unwrap()
+        .finish();
+    match result {
+        Err(Error::TFError(err)) => assert_eq!(Some("GraphTransform::finish"), err.function()),
+        result => panic!("unexpected result {:?}", result.map(|_| ())),
+    }
+}
+
+#[test]
+fn renames_prefix_and_sets_device() {
+    let graph = Graph::new();
+    build_graph(&graph);
+    let transformed = GraphTransform::new(&graph, &["neg"])
+        .unwrap()
+        .rename_prefix("", "model/")
+        .unwrap()
+        .set_device("/device:CPU:0")
+        .unwrap()
+        .finish()
+        .unwrap();
+
+    assert!(transformed.operation_by_name("neg").is_none());
+    for op in transformed.operations() {
+        assert!(op.name().starts_with("model/"));
+        assert_eq!("/device:CPU:0", op.device());
+    }
+    assert_eq!(vec![-2.0, -3.0], eval(&transformed, "model/x", "model/neg"));
+}
+
+#[test]
+fn renames_only_operations_in_scope() {
+    let graph = Graph::new();
+    build_graph(&graph);
+    let transformed = GraphTransform::new(&graph, &["neg"])
+        .unwrap()
+        .rename_prefix("add", "sum")
+        .unwrap()
+        .rename_prefix("", "model/")
+        .unwrap()
+        .rename_prefix("model", "net")
+        .unwrap()
+        .finish()
+        .unwrap();
+
+    assert!(transformed.operation_by_name("net/sum").is_some());
+    assert!(transformed.operation_by_name("net/add_id").is_some());
+    assert!(transformed.operation_by_name("net/sum_id").is_none());
+    assert_eq!(vec![-2.0, -3.0], eval(&transformed, "net/x", "net/neg"));
+}
+
+#[test]
+fn replaces_matched_operations() {
+    let graph = Graph::new();
+    build_graph(&graph);
+
+    // Replaces `-(x + y)` with `(-x) - y`
+    let pattern = Pattern::new("Neg").with_inputs(vec![
+        Pattern::new("Identity").with_inputs(vec![Pattern::new("Add|AddV2")])
+    ]);
+    let mut found = 0;
+    let transformed = GraphTransform::new(&graph, &["neg"])
+        .unwrap()
+        .replace_matches(&pattern, |m| {
+            found += 1;
+            let add = &m.inputs[0].inputs[0].node;
+            let dtype = m.node.attr("T")?.unwrap();
+            let mut neg = NodeDef::new(&format!("{}/x", m.node.name), "Neg");
+            neg.inputs = vec![add.inputs[0].clone()];
+            neg.set_attr("T", &dtype);
+            let mut sub = NodeDef::new(&m.node.name, "Sub");
+            sub.inputs = vec![neg.name.clone(), add.inputs[1].clone()];
+            sub.set_attr("T", &dtype);
+            Ok(vec![neg, sub])
+        })
+        .unwrap()
+        .finish()
+        .unwrap();
+
+    assert_eq!(1, found);
+    assert!(transformed.operation_by_name("add").is_none());
+    assert!(transformed.operation_by_name("add_id").is_none());
+    assert_eq!(
+        "Sub",
+        transformed.operation_by_name("neg").unwrap().op_type()
+    );
+    assert_eq!(vec![-2.0, -3.0], eval(&transformed, "x", "neg"));
+}
+
+#[test]
+fn keeps_matched_operations_used_elsewhere() {
+    let graph = Graph::new();
+    build_graph(&graph);
+    let transformed = GraphTransform::new(&graph, &["neg", "add"])
+        .unwrap()
+        .replace_matches(
+            &Pattern::new("Identity").with_inputs(vec![Pattern::new("Add")]),
+            |m| {
+                let mut node = NodeDef::new(&m.node.name, "Neg");
+                node.inputs = m.node.inputs.clone();
+                node.set_attr("T", &AttrValue::Type(f32::TF_TYPE));
+                Ok(vec![node])
+            },
+        )
+        .unwrap()
+        .finish()
+        .unwrap();
+
+    assert_eq!(
+        "Add",
+        transformed.operation_by_name("add").unwrap().op_type()
+    );
+    assert_eq!(vec![2.0, 3.0], eval(&transformed, "x", "neg"));
+}
+
+#[test]
+fn reads_const_values_from_node_defs() {
+    let graph = Graph::new();
+    build_graph(&graph);
+    let transform = GraphTransform::new(&graph, &["neg"]).unwrap();
+
+    let one = transform
+        .nodes()
+        .iter()
+        .find(|node| node.name == "one")
+        .unwrap();
+    match one.attr("value").unwrap() {
+        Some(AttrValue::Tensor(proto)) => {
+            let value = Tensor::<f32>::from_tensor_proto(&proto).unwrap();
+            assert!(value.shape().is_empty());
+            assert_eq!(&[1.0], &value[..]);
+        }
+        value => panic!("unexpected value {:?}", value),
+    }
+}